@@ -0,0 +1,242 @@
+use crate::{Patch, PatchOperation};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Patch with metadata attached to every operation, like the source or the rule which produced
+/// it. Metadata is kept in memory only: the patch serializes to a standard RFC 6902 patch. Use
+/// [`WithMetadata`] to serialize the metadata as well.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{patch, AnnotatedPatch, PatchOperation};
+/// use jsonptr::PointerBuf;
+/// use serde_json::json;
+///
+/// let mut p = AnnotatedPatch::new();
+/// p.push(PatchOperation::add(PointerBuf::parse("/owner").unwrap(), "andrew"), "default-owner");
+/// p.push(PatchOperation::remove(PointerBuf::parse("/draft").unwrap()), "publish");
+/// assert_eq!(
+///     serde_json::to_value(&p).unwrap(),
+///     json!([
+///         { "op": "add", "path": "/owner", "value": "andrew" },
+///         { "op": "remove", "path": "/draft" },
+///     ])
+/// );
+///
+/// let mut doc = json!({});
+/// let err = patch(&mut doc, &p.to_patch()).unwrap_err();
+/// assert_eq!(p.metadata(err.operation), Some(&"publish"));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnotatedPatch<M>(pub Vec<AnnotatedOperation<M>>);
+
+/// Operation of an [`AnnotatedPatch`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnotatedOperation<M> {
+    /// Operation.
+    pub operation: PatchOperation,
+    /// Metadata of the operation.
+    pub metadata: M,
+}
+
+impl<M> AnnotatedPatch<M> {
+    /// Creates an empty patch.
+    pub fn new() -> Self {
+        AnnotatedPatch(Vec::new())
+    }
+
+    /// Appends an operation with its metadata.
+    pub fn push(&mut self, operation: PatchOperation, metadata: M) {
+        self.0.push(AnnotatedOperation {
+            operation,
+            metadata,
+        });
+    }
+
+    /// Appends all operations of the patch with the same metadata.
+    pub fn append(&mut self, patch: Patch, metadata: M)
+    where
+        M: Clone,
+    {
+        self.0
+            .extend(patch.into_iter().map(|operation| AnnotatedOperation {
+                operation,
+                metadata: metadata.clone(),
+            }));
+    }
+
+    /// Returns the metadata of the operation with the given index, like [`PatchError::operation`].
+    ///
+    /// [`PatchError::operation`]: crate::PatchError::operation
+    pub fn metadata(&self, operation: usize) -> Option<&M> {
+        self.0.get(operation).map(|op| &op.metadata)
+    }
+
+    /// Returns the patch without metadata.
+    pub fn to_patch(&self) -> Patch {
+        self.0.iter().map(|op| op.operation.clone()).collect()
+    }
+}
+
+impl<M> Default for AnnotatedPatch<M> {
+    fn default() -> Self {
+        AnnotatedPatch::new()
+    }
+}
+
+impl<M> FromIterator<AnnotatedOperation<M>> for AnnotatedPatch<M> {
+    fn from_iter<T: IntoIterator<Item = AnnotatedOperation<M>>>(iter: T) -> Self {
+        AnnotatedPatch(iter.into_iter().collect())
+    }
+}
+
+impl<M> From<AnnotatedPatch<M>> for Patch {
+    fn from(patch: AnnotatedPatch<M>) -> Self {
+        patch.0.into_iter().map(|op| op.operation).collect()
+    }
+}
+
+impl<M> Serialize for AnnotatedPatch<M> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|op| &op.operation))
+    }
+}
+
+impl Patch {
+    /// Attaches the same metadata to every operation of this patch.
+    pub fn annotate<M: Clone>(self, metadata: M) -> AnnotatedPatch<M> {
+        let mut patch = AnnotatedPatch::new();
+        patch.append(self, metadata);
+        patch
+    }
+}
+
+/// [`AnnotatedPatch`] serialized with the metadata of each operation in its `meta` member.
+/// Consumers implementing RFC 6902 ignore unknown members, so they can still apply the patch.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{Patch, WithMetadata};
+/// use serde_json::{from_value, json};
+///
+/// let p: Patch = from_value(json!([{ "op": "remove", "path": "/draft" }])).unwrap();
+/// let with_metadata = WithMetadata(p.annotate(json!({ "rule": "publish" })));
+///
+/// let value = serde_json::to_value(&with_metadata).unwrap();
+/// assert_eq!(value, json!([
+///   { "op": "remove", "path": "/draft", "meta": { "rule": "publish" } },
+/// ]));
+/// assert_eq!(from_value::<WithMetadata<serde_json::Value>>(value).unwrap(), with_metadata);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct WithMetadata<M>(pub AnnotatedPatch<M>);
+
+#[derive(Serialize, Deserialize)]
+struct Repr<O, M> {
+    #[serde(flatten)]
+    operation: O,
+    meta: M,
+}
+
+impl<M: Serialize> Serialize for WithMetadata<M> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0 .0.iter().map(|op| Repr {
+            operation: &op.operation,
+            meta: &op.metadata,
+        }))
+    }
+}
+
+impl<'de, M: Deserialize<'de>> Deserialize<'de> for WithMetadata<M> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ops = Vec::<Repr<PatchOperation, M>>::deserialize(deserializer)?;
+        Ok(WithMetadata(
+            ops.into_iter()
+                .map(|op| AnnotatedOperation {
+                    operation: op.operation,
+                    metadata: op.meta,
+                })
+                .collect(),
+        ))
+    }
+}
+
+impl<M> From<AnnotatedPatch<M>> for WithMetadata<M> {
+    fn from(patch: AnnotatedPatch<M>) -> Self {
+        WithMetadata(patch)
+    }
+}
+
+impl<M> From<WithMetadata<M>> for AnnotatedPatch<M> {
+    fn from(patch: WithMetadata<M>) -> Self {
+        patch.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnnotatedPatch, WithMetadata};
+    use crate::Patch;
+    use serde::{Deserialize, Serialize};
+    use serde_json::{from_value, json, to_value};
+
+    #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+    struct Provenance {
+        source: String,
+        rule: Option<String>,
+    }
+
+    #[test]
+    fn annotated() {
+        let defaults: Patch = from_value(json!([
+            { "op": "add", "path": "/a", "value": 1 },
+            { "op": "add", "path": "/b", "value": 2 },
+        ]))
+        .unwrap();
+        let policy: Patch = from_value(json!([
+            { "op": "replace", "path": "/a", "value": { "meta": 3 } },
+        ]))
+        .unwrap();
+
+        let mut p: AnnotatedPatch<Provenance> = defaults.clone().annotate(Provenance {
+            source: "defaults".into(),
+            rule: None,
+        });
+        p.append(
+            policy.clone(),
+            Provenance {
+                source: "policy".into(),
+                rule: Some("no-a".into()),
+            },
+        );
+        assert_eq!(p.metadata(2).unwrap().source, "policy");
+        assert!(p.metadata(3).is_none());
+
+        let standard: Patch = defaults.into_iter().chain(policy).collect();
+        assert_eq!(p.to_patch(), standard);
+        assert_eq!(to_value(&p).unwrap(), to_value(&standard).unwrap());
+        assert_eq!(Patch::from(p.clone()), standard);
+
+        let value = to_value(WithMetadata(p.clone())).unwrap();
+        assert_eq!(
+            value,
+            json!([
+                { "op": "add", "path": "/a", "value": 1,
+                  "meta": { "source": "defaults", "rule": null } },
+                { "op": "add", "path": "/b", "value": 2,
+                  "meta": { "source": "defaults", "rule": null } },
+                { "op": "replace", "path": "/a", "value": { "meta": 3 },
+                  "meta": { "source": "policy", "rule": "no-a" } },
+            ])
+        );
+        let WithMetadata(parsed) = from_value::<WithMetadata<Provenance>>(value).unwrap();
+        assert_eq!(parsed, p);
+
+        let err = from_value::<WithMetadata<Provenance>>(json!([
+            { "op": "remove", "path": "/a" },
+        ]))
+        .unwrap_err();
+        assert_eq!(err.to_string(), "missing field `meta`");
+    }
+}
@@ -0,0 +1,267 @@
+use crate::{Patch, PatchOperation};
+#[cfg(feature = "anonymize_hashed")]
+use hmac::{Hmac, Mac};
+use jsonptr::Pointer;
+use serde_json::Value;
+#[cfg(feature = "anonymize_hashed")]
+use sha2::Sha256;
+
+impl Patch {
+    /// Returns a copy of this patch with the sensitive values replaced by the anonymizer, so real
+    /// patches can be shared for debugging. Locations are kept, so the anonymized patch applies
+    /// to a document anonymized with [`Anonymizer::anonymize_document`] the same way the original
+    /// patch applies to the original document.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::{Anonymizer, Patch};
+    /// use jsonptr::Pointer;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let anonymizer = Anonymizer::new(&[Pointer::from_static("/users/*/email")]);
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "test", "path": "/users/0/email", "value": "andrew@example.com" },
+    ///   { "op": "add", "path": "/users/-", "value": { "name": "Maxim", "email": "max@example.com" } },
+    /// ])).unwrap();
+    ///
+    /// assert_eq!(p.anonymize(&anonymizer), from_value(json!([
+    ///   { "op": "test", "path": "/users/0/email", "value": "[REDACTED]" },
+    ///   { "op": "add", "path": "/users/-", "value": { "name": "Maxim", "email": "[REDACTED]" } },
+    /// ])).unwrap());
+    /// ```
+    pub fn anonymize(&self, anonymizer: &Anonymizer) -> Patch {
+        self.iter()
+            .map(|op| anonymizer.anonymize_operation(op))
+            .collect()
+    }
+}
+
+/// Replaces values at sensitive locations in patches and documents, see [`Patch::anonymize`].
+///
+/// Sensitive locations are given as pointer patterns, where a `*` token matches any array index
+/// or object key. Values at (or under) matching locations are replaced with [`Patch::REDACTED`]
+/// or, with [`Anonymizer::hashed`], with a keyed hash of the value, so equal values stay equal
+/// and `test` operations keep passing against an anonymized document. If one pattern matches a
+/// parent of the location matched by another one, the parent is replaced as a whole.
+#[derive(Clone, Debug)]
+pub struct Anonymizer {
+    patterns: Vec<Vec<String>>,
+    #[cfg(feature = "anonymize_hashed")]
+    key: Option<String>,
+}
+
+impl Anonymizer {
+    /// Creates an anonymizer replacing the values at locations matching any of the patterns with
+    /// [`Patch::REDACTED`].
+    pub fn new(patterns: &[&Pointer]) -> Self {
+        let mut patterns: Vec<Vec<String>> = patterns
+            .iter()
+            .map(|pattern| pattern.tokens().map(|t| t.decoded().into_owned()).collect())
+            .collect();
+        // Parent locations are replaced first, so nested patterns find nothing to replace
+        patterns.sort_by_key(Vec::len);
+        Anonymizer {
+            patterns,
+            #[cfg(feature = "anonymize_hashed")]
+            key: None,
+        }
+    }
+
+    /// Replaces the values with their HMAC-SHA256, computed with the given secret key, instead
+    /// of [`Patch::REDACTED`]. Hashes are strings like `anon:` followed by 32 hex digits (the
+    /// first 128 bits of the HMAC). Without the key, the original values cannot be found by
+    /// hashing candidates, so it must be kept secret.
+    #[cfg(feature = "anonymize_hashed")]
+    pub fn hashed(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Replaces the sensitive values of the document in place.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::{patch, Anonymizer, Patch};
+    /// use jsonptr::Pointer;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let anonymizer = Anonymizer::new(&[Pointer::from_static("/ssn")]);
+    /// let mut doc = json!({ "ssn": "078-05-1120", "age": 42 });
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "test", "path": "/ssn", "value": "078-05-1120" },
+    ///   { "op": "replace", "path": "/age", "value": 43 },
+    /// ])).unwrap();
+    ///
+    /// anonymizer.anonymize_document(&mut doc);
+    /// assert_eq!(doc["ssn"], Patch::REDACTED);
+    /// patch(&mut doc, &p.anonymize(&anonymizer)).unwrap();
+    /// ```
+    pub fn anonymize_document(&self, doc: &mut Value) {
+        for pattern in &self.patterns {
+            self.replace_matching(doc, pattern);
+        }
+    }
+
+    fn anonymize_operation(&self, op: &PatchOperation) -> PatchOperation {
+        let mut op = op.clone();
+        let (path, value) = match &mut op {
+            PatchOperation::Add(op) => (&op.path, &mut op.value),
+            PatchOperation::Replace(op) => (&op.path, &mut op.value),
+            PatchOperation::Test(op) => (&op.path, &mut op.value),
+            PatchOperation::Remove(_) | PatchOperation::Move(_) | PatchOperation::Copy(_) => {
+                return op;
+            }
+        };
+        let path: Vec<_> = path.tokens().map(|t| t.decoded().into_owned()).collect();
+        for pattern in &self.patterns {
+            let common = pattern.len().min(path.len());
+            if !pattern_matches(&pattern[..common], &path[..common]) {
+                continue;
+            }
+            if pattern.len() <= path.len() {
+                *value = self.replacement(value);
+                break;
+            }
+            self.replace_matching(value, &pattern[path.len()..]);
+        }
+        op
+    }
+
+    fn replace_matching(&self, value: &mut Value, pattern: &[String]) {
+        let Some((token, rest)) = pattern.split_first() else {
+            *value = self.replacement(value);
+            return;
+        };
+        match value {
+            Value::Object(obj) if token == "*" => {
+                obj.values_mut()
+                    .for_each(|value| self.replace_matching(value, rest));
+            }
+            Value::Object(obj) => {
+                if let Some(value) = obj.get_mut(token) {
+                    self.replace_matching(value, rest);
+                }
+            }
+            Value::Array(arr) if token == "*" => {
+                arr.iter_mut()
+                    .for_each(|value| self.replace_matching(value, rest));
+            }
+            Value::Array(arr) => {
+                if let Some(value) = token.parse().ok().and_then(|idx: usize| arr.get_mut(idx)) {
+                    self.replace_matching(value, rest);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[cfg_attr(not(feature = "anonymize_hashed"), allow(unused_variables))]
+    fn replacement(&self, value: &Value) -> Value {
+        #[cfg(feature = "anonymize_hashed")]
+        if let Some(ref key) = self.key {
+            let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes())
+                .expect("HMAC accepts keys of any length");
+            // Hash the canonical form, so the key order of objects does not change the hash
+            let canonical =
+                serde_json::to_vec(&crate::Canonical(value)).expect("value is always serializable");
+            mac.update(&canonical);
+            let mac = mac.finalize().into_bytes();
+            let hex = mac[..16].iter().map(|byte| format!("{byte:02x}"));
+            return Value::from(format!("anon:{}", hex.collect::<String>()));
+        }
+        Value::from(Patch::REDACTED)
+    }
+}
+
+fn pattern_matches(pattern: &[String], path: &[String]) -> bool {
+    pattern
+        .iter()
+        .zip(path)
+        .all(|(expected, token)| expected == "*" || expected == token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Anonymizer;
+    use crate::Patch;
+    use jsonptr::Pointer;
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn anonymize() {
+        let anonymizer = Anonymizer::new(&[
+            Pointer::from_static("/users/*/contacts/email"),
+            Pointer::from_static("/users/*/contacts"),
+            Pointer::from_static("/tokens/*"),
+        ]);
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "", "value": {
+                "users": [{ "name": "a", "contacts": { "email": "a@b" } }],
+                "tokens": { "x": 1, "y": 2 },
+            } },
+            { "op": "replace", "path": "/users/0/contacts/email", "value": "c@d" },
+            { "op": "add", "path": "/users/1", "value": { "name": "b" } },
+            { "op": "move", "from": "/tokens/x", "path": "/tokens/z" },
+        ]))
+        .unwrap();
+        let expected: Patch = from_value(json!([
+            { "op": "add", "path": "", "value": {
+                "users": [{ "name": "a", "contacts": "[REDACTED]" }],
+                "tokens": { "x": "[REDACTED]", "y": "[REDACTED]" },
+            } },
+            { "op": "replace", "path": "/users/0/contacts/email", "value": "[REDACTED]" },
+            { "op": "add", "path": "/users/1", "value": { "name": "b" } },
+            { "op": "move", "from": "/tokens/x", "path": "/tokens/z" },
+        ]))
+        .unwrap();
+        assert_eq!(p.anonymize(&anonymizer), expected);
+    }
+
+    #[cfg(feature = "anonymize_hashed")]
+    #[test]
+    fn hashed() {
+        let anonymizer = Anonymizer::new(&[Pointer::from_static("/items/*/owner")]).hashed("salt");
+        let original = json!({ "items": [{ "owner": "andrew" }, { "owner": "maxim" }] });
+        let p: Patch = from_value(json!([
+            { "op": "test", "path": "/items/1", "value": { "owner": "maxim" } },
+            { "op": "test", "path": "/items/0/owner", "value": "andrew" },
+            { "op": "replace", "path": "/items/0/owner", "value": "maxim" },
+        ]))
+        .unwrap();
+
+        let mut doc = original.clone();
+        anonymizer.anonymize_document(&mut doc);
+        assert_ne!(doc, original);
+        assert_ne!(doc["items"][0]["owner"], doc["items"][1]["owner"]);
+        assert!(doc["items"][0]["owner"]
+            .as_str()
+            .unwrap()
+            .starts_with("anon:"));
+
+        let mut expected = original.clone();
+        crate::patch(&mut expected, &p).unwrap();
+        anonymizer.anonymize_document(&mut expected);
+        crate::patch(&mut doc, &p.anonymize(&anonymizer)).unwrap();
+        assert_eq!(doc, expected);
+
+        let other = Anonymizer::new(&[Pointer::from_static("/items/*/owner")]).hashed("pepper");
+        assert_ne!(p.anonymize(&anonymizer), p.anonymize(&other));
+    }
+
+    #[cfg(feature = "anonymize_hashed")]
+    #[test]
+    fn hashed_ignores_key_order() {
+        let anonymizer = Anonymizer::new(&[Pointer::from_static("/a")]).hashed("salt");
+        let mut left = json!({ "a": { "x": 1, "y": [{ "p": 1, "q": 2 }] } });
+        let mut right = json!({ "a": { "y": [{ "q": 2, "p": 1 }], "x": 1 } });
+        anonymizer.anonymize_document(&mut left);
+        anonymizer.anonymize_document(&mut right);
+        assert_eq!(left, right);
+
+        let mut other = json!({ "a": { "x": 1, "y": [{ "p": 2, "q": 1 }] } });
+        anonymizer.anonymize_document(&mut other);
+        assert_ne!(left, other);
+    }
+}
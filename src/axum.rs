@@ -0,0 +1,252 @@
+//! Integration with [axum](https://docs.rs/axum).
+//!
+//! [`Patch`] and [`MergePatch`] can be used as extractors of `application/json-patch+json` and
+//! `application/merge-patch+json` request bodies respectively, and [`AnyPatch`] accepts either
+//! of them. The size of the body is limited
+//! by axum's `DefaultBodyLimit`. [`PatchError`] can be returned as a response, rendered as an
+//! RFC 7807 problem details object.
+//!
+//! # Example
+//!
+//! ```rust
+//! use axum::{extract::Path, routing::patch, Router};
+//! use json_patch::{Patch, PatchError};
+//! use serde_json::{json, Value};
+//!
+//! async fn update(Path(id): Path<u32>, p: Patch) -> Result<String, PatchError> {
+//!     let mut doc = json!({ "id": id });
+//!     json_patch::patch(&mut doc, &p)?;
+//!     Ok(doc.to_string())
+//! }
+//!
+//! let app: Router = Router::new().route("/items/{id}", patch(update));
+//! ```
+use crate::{media_type, AnyPatch, MergePatch, ParsePatchError, Patch, PatchError, PatchErrorKind};
+use ::axum::{
+    body::Bytes,
+    extract::{rejection::BytesRejection, FromRequest, Request},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Value};
+use thiserror::Error;
+
+/// Rejection of a JSON Patch or a JSON Merge Patch request body.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PatchRejection {
+    /// Request `Content-Type` is missing or is not the media type of the patch.
+    #[error("unsupported `Content-Type`, expected {expected}")]
+    UnsupportedMediaType {
+        /// Expected media types.
+        expected: &'static str,
+    },
+    /// Request body is not a valid patch.
+    #[error("failed to parse the request body: {0}")]
+    InvalidBody(#[from] serde_json::Error),
+    /// Failed to read the request body, for example, because it is too large.
+    #[error(transparent)]
+    Body(#[from] BytesRejection),
+}
+
+impl PatchRejection {
+    /// Status code of the response for this rejection.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            PatchRejection::UnsupportedMediaType { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            PatchRejection::InvalidBody(_) => StatusCode::BAD_REQUEST,
+            PatchRejection::Body(rejection) => rejection.status(),
+        }
+    }
+}
+
+impl IntoResponse for PatchRejection {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let mut accept = HeaderMap::new();
+        if let PatchRejection::UnsupportedMediaType { expected } = self {
+            accept.insert("Accept-Patch", HeaderValue::from_static(expected));
+        }
+        let problem = problem(status, &self.to_string(), None);
+        (accept, problem).into_response()
+    }
+}
+
+impl IntoResponse for PatchError {
+    /// Failed `test` operation results in `409 Conflict`, and other errors result in
+    /// `422 Unprocessable Entity`. Fields of the error are included as extension members.
+    fn into_response(self) -> Response {
+        let status = match self.kind {
+            PatchErrorKind::TestFailed => StatusCode::CONFLICT,
+            _ => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        let extensions = serde_json::to_value(&self).ok();
+        problem(status, &self.to_string(), extensions)
+    }
+}
+
+fn problem(status: StatusCode, detail: &str, extensions: Option<Value>) -> Response {
+    let mut body = json!({
+        "type": "about:blank",
+        "title": status.canonical_reason().unwrap_or_default(),
+        "status": status.as_u16(),
+        "detail": detail,
+    });
+    if let (Value::Object(body), Some(Value::Object(extensions))) = (&mut body, extensions) {
+        body.extend(extensions);
+    }
+    let content_type = HeaderValue::from_static("application/problem+json");
+    (
+        status,
+        [(header::CONTENT_TYPE, content_type)],
+        body.to_string(),
+    )
+        .into_response()
+}
+
+fn content_type(req: &Request) -> &str {
+    req.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+async fn read_body<S: Send + Sync>(
+    req: Request,
+    state: &S,
+    expected: &'static str,
+) -> Result<Bytes, PatchRejection> {
+    if !media_type(content_type(&req)).eq_ignore_ascii_case(expected) {
+        return Err(PatchRejection::UnsupportedMediaType { expected });
+    }
+    Ok(Bytes::from_request(req, state).await?)
+}
+
+impl<S: Send + Sync> FromRequest<S> for Patch {
+    type Rejection = PatchRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let body = read_body(req, state, Patch::CONTENT_TYPE).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+impl<S: Send + Sync> FromRequest<S> for MergePatch {
+    type Rejection = PatchRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let body = read_body(req, state, MergePatch::CONTENT_TYPE).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+impl<S: Send + Sync> FromRequest<S> for AnyPatch {
+    type Rejection = PatchRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = content_type(&req).to_owned();
+        if !AnyPatch::is_supported(&content_type) {
+            return Err(PatchRejection::UnsupportedMediaType {
+                expected: AnyPatch::ACCEPT_PATCH,
+            });
+        }
+        let body = Bytes::from_request(req, state).await?;
+        AnyPatch::from_slice(&content_type, &body).map_err(|err| match err {
+            ParsePatchError::InvalidBody(err) => PatchRejection::InvalidBody(err),
+            ParsePatchError::UnsupportedMediaType(_) => PatchRejection::UnsupportedMediaType {
+                expected: AnyPatch::ACCEPT_PATCH,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AnyPatch, MergePatch, Patch, PatchOperation};
+    use ::axum::{
+        body::{to_bytes, Body},
+        extract::{FromRequest, Request},
+        http::StatusCode,
+        response::IntoResponse,
+    };
+    use serde_json::{json, Value};
+
+    fn request(content_type: &str, body: &'static str) -> Request {
+        Request::builder()
+            .header("Content-Type", content_type)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    fn response_body(response: impl IntoResponse) -> (StatusCode, Value) {
+        let response = response.into_response();
+        let status = response.status();
+        assert_eq!(
+            response.headers()["Content-Type"],
+            "application/problem+json"
+        );
+        let body = pollster::block_on(to_bytes(response.into_body(), usize::MAX)).unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[test]
+    fn extract_patch() {
+        let req = request(
+            "application/json-patch+json; charset=utf-8",
+            r#"[{ "op": "remove", "path": "/a" }]"#,
+        );
+        let patch = pollster::block_on(Patch::from_request(req, &())).unwrap();
+        assert_eq!(
+            patch,
+            Patch(vec![PatchOperation::remove(
+                jsonptr::PointerBuf::parse("/a").unwrap()
+            )])
+        );
+
+        let req = request("application/merge-patch+json", r#"{ "a": null }"#);
+        let patch = pollster::block_on(MergePatch::from_request(req, &())).unwrap();
+        assert_eq!(patch, MergePatch(json!({ "a": null })));
+
+        let req = request("Application/Merge-Patch+JSON", r#"{ "a": null }"#);
+        let patch = pollster::block_on(AnyPatch::from_request(req, &())).unwrap();
+        assert_eq!(patch, AnyPatch::Merge(MergePatch(json!({ "a": null }))));
+    }
+
+    #[test]
+    fn reject_patch() {
+        let req = request("application/json", r#"[]"#);
+        let rejection = pollster::block_on(Patch::from_request(req, &())).unwrap_err();
+        let (status, body) = response_body(rejection);
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(
+            body["detail"],
+            "unsupported `Content-Type`, expected application/json-patch+json"
+        );
+
+        let req = request("application/json-patch+json", r#"[{ "op": "remove" }]"#);
+        let rejection = pollster::block_on(Patch::from_request(req, &())).unwrap_err();
+        assert_eq!(response_body(rejection).0, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn patch_error_response() {
+        let patch: Patch =
+            serde_json::from_value(json!([{ "op": "test", "path": "/a", "value": 2 }])).unwrap();
+        let err = crate::patch(&mut json!({ "a": 1 }), &patch).unwrap_err();
+        let (status, body) = response_body(err);
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(
+            body,
+            json!({
+                "type": "about:blank",
+                "title": "Conflict",
+                "status": 409,
+                "detail": "operation '/0' failed at path '/a': value did not match",
+                "operation": 0,
+                "op": "test",
+                "path": "/a",
+                "kind": "TestFailed",
+            })
+        );
+    }
+}
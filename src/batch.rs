@@ -0,0 +1,100 @@
+use crate::{translate_error, validate_operation, PatchError, PatchOperation};
+use serde_json::Value;
+
+/// Apply the same patch to many documents, returning the result for every document in order.
+///
+/// The patch is checked for errors which do not depend on the document (see
+/// [`Patch::validate`](crate::Patch::validate)) once, before any document is modified; the first
+/// such error is returned as the outer error. Each document is patched atomically, same as
+/// [`patch`](crate::patch), so a failure in one document leaves it unchanged and does not affect
+/// the others.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{patch_many, Patch};
+/// use serde_json::{from_value, json};
+///
+/// let mut docs = vec![json!({ "replicas": 1 }), json!({}), json!({ "replicas": 3 })];
+/// let p: Patch = from_value(json!([
+///   { "op": "replace", "path": "/replicas", "value": 2 },
+/// ])).unwrap();
+///
+/// let results = patch_many(&mut docs, &p).unwrap();
+/// assert!(results[0].is_ok());
+/// assert!(results[1].is_err());
+/// assert_eq!(docs, vec![json!({ "replicas": 2 }), json!({}), json!({ "replicas": 2 })]);
+/// ```
+pub fn patch_many<'a>(
+    docs: impl IntoIterator<Item = &'a mut Value>,
+    patch: &[PatchOperation],
+) -> Result<Vec<Result<(), PatchError>>, PatchError> {
+    check(patch)?;
+    Ok(docs
+        .into_iter()
+        .map(|doc| crate::patch(doc, patch))
+        .collect())
+}
+
+/// Apply the same patch to many documents in parallel, same as [`patch_many`].
+#[cfg(feature = "rayon")]
+pub fn patch_many_par<'a>(
+    docs: impl rayon::iter::IntoParallelIterator<Item = &'a mut Value>,
+    patch: &[PatchOperation],
+) -> Result<Vec<Result<(), PatchError>>, PatchError> {
+    use rayon::iter::ParallelIterator;
+
+    check(patch)?;
+    Ok(docs
+        .into_par_iter()
+        .map(|doc| crate::patch(doc, patch))
+        .collect())
+}
+
+fn check(patch: &[PatchOperation]) -> Result<(), PatchError> {
+    for (operation, op) in patch.iter().enumerate() {
+        if let Some(kind) = validate_operation(op) {
+            return Err(translate_error(kind, operation, op));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::patch_many;
+    use crate::{Patch, PatchErrorKind};
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn invalid_patch() {
+        let mut docs = vec![json!({ "a": 1 })];
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "/b", "value": 1 },
+            { "op": "remove", "path": "" },
+        ]))
+        .unwrap();
+        let err = patch_many(&mut docs, &p).unwrap_err();
+        assert_eq!(err.operation, 1);
+        assert!(matches!(err.kind, PatchErrorKind::InvalidPointer));
+        assert_eq!(docs, vec![json!({ "a": 1 })]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel() {
+        use super::patch_many_par;
+
+        let mut docs: Vec<_> = (0..100).map(|idx| json!({ "idx": idx })).collect();
+        let p: Patch = from_value(json!([
+            { "op": "test", "path": "/idx", "value": 7 },
+            { "op": "add", "path": "/found", "value": true },
+        ]))
+        .unwrap();
+        let results = patch_many_par(&mut docs, &p).unwrap();
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(results[7].is_ok());
+        assert_eq!(docs[7], json!({ "idx": 7, "found": true }));
+        assert_eq!(docs[8], json!({ "idx": 8 }));
+    }
+}
@@ -0,0 +1,506 @@
+//! Compact binary encoding of JSON Patches, for exchanging patches over constrained links.
+//!
+//! The encoding is specific to this crate and is considerably smaller than both the JSON text and
+//! generic binary formats like CBOR, since it does not repeat member names, encodes operation
+//! kinds as single bytes and only stores the part of each pointer that differs from the previous
+//! one.
+//!
+//! # Format
+//!
+//! All integers are unsigned LEB128 varints. A patch starts with the format version byte (`1`)
+//! and the number of operations, followed by the operations. Each operation is a single op code
+//! byte (`0` to `5` for `add`, `remove`, `replace`, `move`, `copy` and `test`), followed by the
+//! `from` pointer for `move` and `copy`, the `path` pointer, and the value for `add`, `replace`
+//! and `test`.
+//!
+//! A pointer is encoded as the number of leading tokens shared with the previously encoded
+//! pointer of the patch, the number of the following tokens, and the tokens themselves. A token
+//! which is an array index is encoded as `index << 1 | 1`; any other token as `len << 1` and its
+//! `len` bytes of unescaped UTF-8.
+//!
+//! A value is a tag byte followed by its contents: `0` for `null`, `1` for `false`, `2` for
+//! `true`, `3` and a varint for non-negative integers, `4` and a varint `n` for negative integer
+//! `-n - 1`, `5` and 8 bytes of a little endian `f64` for other numbers, `6` and a length-prefixed
+//! string, `7` and a number of elements followed by the elements for arrays, `8` and a number of
+//! members followed by the length-prefixed keys and values for objects.
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::{binary, Patch};
+//! use serde_json::{from_value, json};
+//!
+//! let p: Patch = from_value(json!([
+//!   { "op": "test", "path": "/users/12/name", "value": "Andrew" },
+//!   { "op": "replace", "path": "/users/12/name", "value": "Maxim" },
+//!   { "op": "add", "path": "/users/12/happy", "value": true },
+//! ])).unwrap();
+//!
+//! let bytes = binary::to_vec(&p);
+//! assert!(bytes.len() < p.to_string().len() / 3);
+//! assert_eq!(binary::from_slice(&bytes).unwrap(), p);
+//! ```
+use crate::{
+    AddOperation, CopyOperation, MoveOperation, Patch, PatchOperation, RemoveOperation,
+    ReplaceOperation, TestOperation,
+};
+use jsonptr::{Pointer, PointerBuf, Token};
+use serde_json::{Map, Number, Value};
+use thiserror::Error;
+
+const VERSION: u8 = 1;
+
+// Nesting depth of values accepted by the decoder, same as the default of `serde_json`
+const RECURSION_LIMIT: usize = 128;
+
+const OP_ADD: u8 = 0;
+const OP_REMOVE: u8 = 1;
+const OP_REPLACE: u8 = 2;
+const OP_MOVE: u8 = 3;
+const OP_COPY: u8 = 4;
+const OP_TEST: u8 = 5;
+
+const TAG_NULL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_UINT: u8 = 3;
+const TAG_NEG_INT: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_STRING: u8 = 6;
+const TAG_ARRAY: u8 = 7;
+const TAG_OBJECT: u8 = 8;
+
+/// Error that can occur while decoding a patch, see [`from_slice`].
+#[derive(Debug, Error, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// Format version is not supported.
+    #[error("unsupported format version {0}")]
+    UnsupportedVersion(u8),
+    /// Input ended in the middle of the patch.
+    #[error("unexpected end of input")]
+    UnexpectedEof,
+    /// Input continues after the end of the patch.
+    #[error("trailing bytes after the patch")]
+    TrailingBytes,
+    /// Varint does not fit into 64 bits.
+    #[error("varint overflow at offset {0}")]
+    VarintOverflow(usize),
+    /// Operation code is not known.
+    #[error("invalid op code {code} at offset {offset}")]
+    InvalidOpCode {
+        /// Op code found.
+        code: u8,
+        /// Offset of the op code.
+        offset: usize,
+    },
+    /// Value tag is not known.
+    #[error("invalid value tag {tag} at offset {offset}")]
+    InvalidTag {
+        /// Tag found.
+        tag: u8,
+        /// Offset of the tag.
+        offset: usize,
+    },
+    /// Pointer shares more tokens with the previous pointer than it has.
+    #[error("invalid pointer prefix at offset {0}")]
+    InvalidPrefix(usize),
+    /// String is not valid UTF-8.
+    #[error("invalid UTF-8 string at offset {0}")]
+    InvalidUtf8(usize),
+    /// Number is not a valid JSON number (infinite or NaN).
+    #[error("invalid number at offset {0}")]
+    InvalidNumber(usize),
+    /// Values are nested too deeply.
+    #[error("recursion limit exceeded at offset {0}")]
+    RecursionLimitExceeded(usize),
+}
+
+/// Encode the patch as a byte vector.
+pub fn to_vec(patch: &Patch) -> Vec<u8> {
+    let mut encoder = Encoder {
+        buf: vec![VERSION],
+        previous: PointerBuf::new(),
+    };
+    encoder.varint(patch.len() as u64);
+    for op in patch.iter() {
+        encoder.operation(op);
+    }
+    encoder.buf
+}
+
+/// Decode a patch from a byte slice.
+pub fn from_slice(bytes: &[u8]) -> Result<Patch, DecodeError> {
+    let mut decoder = Decoder {
+        bytes,
+        offset: 0,
+        previous: PointerBuf::new(),
+    };
+    let version = decoder.byte()?;
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+    let len = decoder.len()?;
+    let mut ops = Vec::with_capacity(len);
+    for _ in 0..len {
+        ops.push(decoder.operation()?);
+    }
+    if decoder.offset != bytes.len() {
+        return Err(DecodeError::TrailingBytes);
+    }
+    Ok(Patch(ops))
+}
+
+struct Encoder {
+    buf: Vec<u8>,
+    previous: PointerBuf,
+}
+
+impl Encoder {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    fn string(&mut self, s: &str) {
+        self.varint(s.len() as u64);
+        self.buf.extend_from_slice(s.as_bytes());
+    }
+
+    fn operation(&mut self, op: &PatchOperation) {
+        match op {
+            PatchOperation::Add(AddOperation { path, value }) => {
+                self.buf.push(OP_ADD);
+                self.pointer(path);
+                self.value(value);
+            }
+            PatchOperation::Remove(RemoveOperation { path }) => {
+                self.buf.push(OP_REMOVE);
+                self.pointer(path);
+            }
+            PatchOperation::Replace(ReplaceOperation { path, value }) => {
+                self.buf.push(OP_REPLACE);
+                self.pointer(path);
+                self.value(value);
+            }
+            PatchOperation::Move(MoveOperation { from, path }) => {
+                self.buf.push(OP_MOVE);
+                self.pointer(from);
+                self.pointer(path);
+            }
+            PatchOperation::Copy(CopyOperation { from, path }) => {
+                self.buf.push(OP_COPY);
+                self.pointer(from);
+                self.pointer(path);
+            }
+            PatchOperation::Test(TestOperation { path, value }) => {
+                self.buf.push(OP_TEST);
+                self.pointer(path);
+                self.value(value);
+            }
+        }
+    }
+
+    fn pointer(&mut self, pointer: &Pointer) {
+        let shared = self
+            .previous
+            .tokens()
+            .zip(pointer.tokens())
+            .take_while(|(previous, token)| previous == token)
+            .count();
+        self.varint(shared as u64);
+        self.varint((pointer.count() - shared) as u64);
+        for token in pointer.tokens().skip(shared) {
+            let decoded = token.decoded();
+            match decoded.parse::<u64>() {
+                // Only canonical indices, so that "01" is not decoded as "1"
+                Ok(index) if index < 1 << 63 && index.to_string() == decoded => {
+                    self.varint(index << 1 | 1)
+                }
+                _ => {
+                    self.varint((decoded.len() as u64) << 1);
+                    self.buf.extend_from_slice(decoded.as_bytes());
+                }
+            }
+        }
+        self.previous = pointer.to_buf();
+    }
+
+    fn value(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.buf.push(TAG_NULL),
+            Value::Bool(false) => self.buf.push(TAG_FALSE),
+            Value::Bool(true) => self.buf.push(TAG_TRUE),
+            Value::Number(n) => {
+                if let Some(n) = n.as_u64() {
+                    self.buf.push(TAG_UINT);
+                    self.varint(n);
+                } else if let Some(n) = n.as_i64() {
+                    self.buf.push(TAG_NEG_INT);
+                    self.varint(!n as u64);
+                } else {
+                    self.buf.push(TAG_FLOAT);
+                    let n = n.as_f64().unwrap_or_default();
+                    self.buf.extend_from_slice(&n.to_le_bytes());
+                }
+            }
+            Value::String(s) => {
+                self.buf.push(TAG_STRING);
+                self.string(s);
+            }
+            Value::Array(items) => {
+                self.buf.push(TAG_ARRAY);
+                self.varint(items.len() as u64);
+                for item in items {
+                    self.value(item);
+                }
+            }
+            Value::Object(members) => {
+                self.buf.push(TAG_OBJECT);
+                self.varint(members.len() as u64);
+                for (key, value) in members {
+                    self.string(key);
+                    self.value(value);
+                }
+            }
+        }
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    previous: PointerBuf,
+}
+
+impl<'a> Decoder<'a> {
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let byte = *self
+            .bytes
+            .get(self.offset)
+            .ok_or(DecodeError::UnexpectedEof)?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.bytes.len() - self.offset < len {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        let taken = &self.bytes[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(taken)
+    }
+
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let start = self.offset;
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            let bits = u64::from(byte & 0x7f);
+            if shift == 63 && bits > 1 {
+                return Err(DecodeError::VarintOverflow(start));
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::VarintOverflow(start))
+    }
+
+    // Length of a sequence of items taking at least a byte each, checked against the remaining
+    // input so that corrupted lengths do not cause huge allocations
+    fn len(&mut self) -> Result<usize, DecodeError> {
+        let len = self.varint()?;
+        if len > (self.bytes.len() - self.offset) as u64 {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        Ok(len as usize)
+    }
+
+    fn string(&mut self, len: usize) -> Result<String, DecodeError> {
+        let offset = self.offset;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::InvalidUtf8(offset))
+    }
+
+    fn operation(&mut self) -> Result<PatchOperation, DecodeError> {
+        let offset = self.offset;
+        Ok(match self.byte()? {
+            OP_ADD => PatchOperation::Add(AddOperation {
+                path: self.pointer()?,
+                value: self.value(0)?,
+            }),
+            OP_REMOVE => PatchOperation::Remove(RemoveOperation {
+                path: self.pointer()?,
+            }),
+            OP_REPLACE => PatchOperation::Replace(ReplaceOperation {
+                path: self.pointer()?,
+                value: self.value(0)?,
+            }),
+            OP_MOVE => PatchOperation::Move(MoveOperation {
+                from: self.pointer()?,
+                path: self.pointer()?,
+            }),
+            OP_COPY => PatchOperation::Copy(CopyOperation {
+                from: self.pointer()?,
+                path: self.pointer()?,
+            }),
+            OP_TEST => PatchOperation::Test(TestOperation {
+                path: self.pointer()?,
+                value: self.value(0)?,
+            }),
+            code => return Err(DecodeError::InvalidOpCode { code, offset }),
+        })
+    }
+
+    fn pointer(&mut self) -> Result<PointerBuf, DecodeError> {
+        let offset = self.offset;
+        let shared = self.varint()?;
+        if shared > self.previous.count() as u64 {
+            return Err(DecodeError::InvalidPrefix(offset));
+        }
+        let mut pointer = PointerBuf::from_tokens(self.previous.tokens().take(shared as usize));
+        for _ in 0..self.len()? {
+            let token = self.varint()?;
+            if token & 1 == 1 {
+                pointer.push_back((token >> 1).to_string());
+            } else {
+                let len = usize::try_from(token >> 1).map_err(|_| DecodeError::UnexpectedEof)?;
+                pointer.push_back(Token::new(self.string(len)?));
+            }
+        }
+        self.previous = pointer.clone();
+        Ok(pointer)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, DecodeError> {
+        let offset = self.offset;
+        if depth > RECURSION_LIMIT {
+            return Err(DecodeError::RecursionLimitExceeded(offset));
+        }
+        Ok(match self.byte()? {
+            TAG_NULL => Value::Null,
+            TAG_FALSE => Value::Bool(false),
+            TAG_TRUE => Value::Bool(true),
+            TAG_UINT => Value::Number(self.varint()?.into()),
+            TAG_NEG_INT => {
+                let n = self.varint()?;
+                if n > i64::MAX as u64 {
+                    return Err(DecodeError::InvalidNumber(offset));
+                }
+                Value::Number((!(n as i64)).into())
+            }
+            TAG_FLOAT => {
+                let bytes = self.take(8)?.try_into().unwrap();
+                Number::from_f64(f64::from_le_bytes(bytes))
+                    .map(Value::Number)
+                    .ok_or(DecodeError::InvalidNumber(offset))?
+            }
+            TAG_STRING => {
+                let len = self.len()?;
+                Value::String(self.string(len)?)
+            }
+            TAG_ARRAY => {
+                let len = self.len()?;
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push(self.value(depth + 1)?);
+                }
+                Value::Array(items)
+            }
+            TAG_OBJECT => {
+                let len = self.len()?;
+                let mut members = Map::new();
+                for _ in 0..len {
+                    let key_len = self.len()?;
+                    let key = self.string(key_len)?;
+                    members.insert(key, self.value(depth + 1)?);
+                }
+                Value::Object(members)
+            }
+            tag => return Err(DecodeError::InvalidTag { tag, offset }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_slice, to_vec, DecodeError};
+    use crate::Patch;
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn roundtrip() {
+        let patch: Patch = from_value(json!([
+            { "op": "add", "path": "/a~1b/0", "value": { "x": [1, -2, 2.5, null, "s", true] } },
+            { "op": "remove", "path": "/a~1b/01" },
+            { "op": "replace", "path": "", "value": false },
+            { "op": "move", "from": "/d/~0", "path": "/d/-" },
+            { "op": "copy", "from": "/e", "path": "/f/18446744073709551615" },
+            { "op": "test", "path": "/f", "value": i64::MIN },
+            { "op": "add", "path": "/g", "value": u64::MAX },
+        ]))
+        .unwrap();
+
+        let bytes = to_vec(&patch);
+        assert!(bytes.len() < patch.to_string().len() / 2);
+        assert_eq!(from_slice(&bytes).unwrap(), patch);
+        assert_eq!(
+            from_slice(&to_vec(&Patch::default())).unwrap(),
+            Patch::default()
+        );
+    }
+
+    #[test]
+    fn shared_prefixes() {
+        let patch: Patch = from_value(json!([
+            { "op": "replace", "path": "/users/5/name", "value": null },
+            { "op": "replace", "path": "/users/5/email", "value": null },
+        ]))
+        .unwrap();
+        let bytes = to_vec(&patch);
+        // Second pointer shares two tokens and adds "email"
+        assert_eq!(&bytes[bytes.len() - 10..], b"\x02\x02\x01\x0aemail\x00");
+    }
+
+    #[test]
+    fn invalid_input() {
+        let patch: Patch =
+            from_value(json!([{ "op": "add", "path": "/a", "value": [[1]] }])).unwrap();
+        let bytes = to_vec(&patch);
+
+        assert_eq!(from_slice(&[]), Err(DecodeError::UnexpectedEof));
+        assert_eq!(from_slice(&[2, 0]), Err(DecodeError::UnsupportedVersion(2)));
+        assert_eq!(
+            from_slice(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::UnexpectedEof)
+        );
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(from_slice(&trailing), Err(DecodeError::TrailingBytes));
+        assert_eq!(
+            from_slice(&[1, 1, 9]),
+            Err(DecodeError::InvalidOpCode { code: 9, offset: 2 })
+        );
+        assert_eq!(
+            from_slice(&[1, 1, 1, 1, 0]),
+            Err(DecodeError::InvalidPrefix(3))
+        );
+        assert_eq!(
+            from_slice(&[1, 1, 1, 0, 1, 2, 0xff]),
+            Err(DecodeError::InvalidUtf8(6))
+        );
+
+        let mut nested = vec![1, 1, 0, 0, 0];
+        nested.extend([7, 1].repeat(200));
+        assert_eq!(
+            from_slice(&nested),
+            Err(DecodeError::RecursionLimitExceeded(263))
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! Zero-copy representation of JSON Patch, borrowing from the input buffer.
+//!
+//! [`BorrowedPatch`] keeps pointers as slices of the input (unless they contain escape sequences)
+//! and values as unparsed [`RawValue`]s, which are only parsed into [`Value`] when the patch is
+//! applied. This avoids paying for full ownership of every string when a patch is parsed only to
+//! be immediately applied.
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::borrowed::BorrowedPatch;
+//! use serde_json::json;
+//!
+//! let input = r#"[{ "op": "add", "path": "/0/happy", "value": true }]"#;
+//! let p: BorrowedPatch = serde_json::from_str(input).unwrap();
+//!
+//! let mut doc = json!([{ "name": "Andrew" }]);
+//! p.apply(&mut doc).unwrap();
+//! assert_eq!(doc, json!([{ "name": "Andrew", "happy": true }]));
+//! ```
+use crate::{
+    AddOperation, CopyOperation, MoveOperation, OpKind, Patch, PatchError, PatchOperation,
+    PatchOptions, RemoveOperation, ReplaceOperation, TestOperation,
+};
+use jsonptr::Pointer;
+use serde::{Deserialize, Deserializer};
+use serde_json::{value::RawValue, Value};
+use std::borrow::Cow;
+
+/// JSON Patch borrowing its pointers and values from the input.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct BorrowedPatch<'a>(#[serde(borrow)] pub Vec<BorrowedOperation<'a>>);
+
+/// Single JSON Patch operation borrowing its pointers and value from the input.
+#[derive(Clone, Debug)]
+pub struct BorrowedOperation<'a> {
+    kind: OpKind,
+    path: Cow<'a, str>,
+    from: Option<Cow<'a, str>>,
+    value: Option<&'a RawValue>,
+}
+
+// `Option<Cow<str>>` is always deserialized as owned, so wrap the `Cow` to borrow it.
+#[derive(Deserialize)]
+struct Str<'a>(#[serde(borrow)] Cow<'a, str>);
+
+#[derive(Deserialize)]
+struct RawOperation<'a> {
+    #[serde(borrow)]
+    op: Cow<'a, str>,
+    #[serde(borrow)]
+    path: Cow<'a, str>,
+    #[serde(borrow, default)]
+    from: Option<Str<'a>>,
+    #[serde(borrow, default, deserialize_with = "present")]
+    value: Option<&'a RawValue>,
+}
+
+// `Option<&RawValue>` is `None` for `null`, so only use `None` for missing members.
+fn present<'de: 'a, 'a, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<&'a RawValue>, D::Error> {
+    <&'a RawValue>::deserialize(deserializer).map(Some)
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for BorrowedOperation<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let raw = RawOperation::deserialize(deserializer)?;
+        let kind = match raw.op.as_ref() {
+            "add" => OpKind::Add,
+            "remove" => OpKind::Remove,
+            "replace" => OpKind::Replace,
+            "move" => OpKind::Move,
+            "copy" => OpKind::Copy,
+            "test" => OpKind::Test,
+            op => {
+                return Err(D::Error::unknown_variant(
+                    op,
+                    &["add", "remove", "replace", "move", "copy", "test"],
+                ))
+            }
+        };
+        Pointer::parse(raw.path.as_ref()).map_err(D::Error::custom)?;
+        let needs_from = matches!(kind, OpKind::Move | OpKind::Copy);
+        let from = match raw.from {
+            Some(Str(from)) if needs_from => {
+                Pointer::parse(from.as_ref()).map_err(D::Error::custom)?;
+                Some(from)
+            }
+            None if needs_from => return Err(D::Error::missing_field("from")),
+            _ => None,
+        };
+        let needs_value = matches!(kind, OpKind::Add | OpKind::Replace | OpKind::Test);
+        let value = match raw.value {
+            None if needs_value => return Err(D::Error::missing_field("value")),
+            value if needs_value => value,
+            _ => None,
+        };
+        Ok(BorrowedOperation {
+            kind,
+            path: raw.path,
+            from,
+            value,
+        })
+    }
+}
+
+fn pointer(ptr: &str) -> &Pointer {
+    Pointer::parse(ptr).expect("pointer is validated when deserialized")
+}
+
+fn parse(value: &RawValue) -> Value {
+    serde_json::from_str(value.get()).expect("raw value is always valid JSON")
+}
+
+impl<'a> BorrowedOperation<'a> {
+    /// Returns the kind of the operation.
+    pub fn kind(&self) -> OpKind {
+        self.kind
+    }
+
+    /// Returns a reference to the path the operation applies to.
+    pub fn path(&self) -> &Pointer {
+        pointer(&self.path)
+    }
+
+    /// Returns a reference to the `from` path of 'move' and 'copy' operations.
+    pub fn from(&self) -> Option<&Pointer> {
+        self.from.as_deref().map(pointer)
+    }
+
+    /// Returns the unparsed value of 'add', 'replace' and 'test' operations.
+    pub fn value(&self) -> Option<&'a RawValue> {
+        self.value
+    }
+
+    /// Converts this operation into an owned [`PatchOperation`].
+    pub fn to_operation(&self) -> PatchOperation {
+        let path = self.path().to_buf();
+        let from = || self.from().unwrap().to_buf();
+        let value = || parse(self.value.unwrap());
+        match self.kind {
+            OpKind::Add => PatchOperation::Add(AddOperation {
+                path,
+                value: value(),
+            }),
+            OpKind::Remove => PatchOperation::Remove(RemoveOperation { path }),
+            OpKind::Replace => PatchOperation::Replace(ReplaceOperation {
+                path,
+                value: value(),
+            }),
+            OpKind::Move => PatchOperation::Move(MoveOperation { from: from(), path }),
+            OpKind::Copy => PatchOperation::Copy(CopyOperation { from: from(), path }),
+            OpKind::Test => PatchOperation::Test(TestOperation {
+                path,
+                value: value(),
+            }),
+        }
+    }
+}
+
+impl BorrowedPatch<'_> {
+    /// Converts this patch into an owned [`Patch`].
+    pub fn to_patch(&self) -> Patch {
+        self.0.iter().map(BorrowedOperation::to_operation).collect()
+    }
+
+    /// Patch provided JSON document in-place, same as [`patch`](crate::patch). Values are only
+    /// parsed when the patch is applied.
+    pub fn apply(&self, doc: &mut Value) -> Result<(), PatchError> {
+        self.apply_with(doc, &PatchOptions::default())
+    }
+
+    /// Patch provided JSON document in-place, same as [`patch_with`](crate::patch_with).
+    pub fn apply_with(&self, doc: &mut Value, options: &PatchOptions) -> Result<(), PatchError> {
+        crate::patch_with(doc, &self.to_patch(), options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BorrowedPatch;
+    use crate::{DashSemantics, PatchOptions};
+    use serde_json::json;
+    use std::borrow::Cow;
+
+    #[test]
+    fn borrows_from_input() {
+        let input = r#"[
+            { "op": "move", "from": "/a", "path": "/b~1c" },
+            { "op": "add", "path": "/\u0064", "value": { "x": [1, 2] } }
+        ]"#;
+        let patch: BorrowedPatch = serde_json::from_str(input).unwrap();
+        assert!(matches!(patch.0[0].path, Cow::Borrowed("/b~1c")));
+        assert!(matches!(patch.0[0].from, Some(Cow::Borrowed("/a"))));
+        // Escaped strings cannot be borrowed
+        assert!(matches!(patch.0[1].path, Cow::Owned(_)));
+        assert_eq!(patch.0[1].value().unwrap().get(), r#"{ "x": [1, 2] }"#);
+
+        assert_eq!(
+            patch.to_patch(),
+            serde_json::from_value(json!([
+                { "op": "move", "from": "/a", "path": "/b~1c" },
+                { "op": "add", "path": "/d", "value": { "x": [1, 2] } }
+            ]))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn apply_reverts_on_error() {
+        let input = r#"[
+            { "op": "replace", "path": "/a", "value": 2 },
+            { "op": "copy", "from": "/a", "path": "/b" },
+            { "op": "test", "path": "/b", "value": 3 }
+        ]"#;
+        let patch: BorrowedPatch = serde_json::from_str(input).unwrap();
+        let mut doc = json!({ "a": 1 });
+        let err = patch.apply(&mut doc).unwrap_err();
+        assert_eq!(err.operation, 2);
+        assert_eq!(doc, json!({ "a": 1 }));
+    }
+
+    #[test]
+    fn null_values() {
+        let input = r#"[
+            { "op": "add", "path": "/a", "value": null },
+            { "op": "test", "path": "/a", "value": null },
+            { "op": "replace", "path": "/b", "value": null }
+        ]"#;
+        let patch: BorrowedPatch = serde_json::from_str(input).unwrap();
+        assert_eq!(patch.0[0].value().unwrap().get(), "null");
+        let mut doc = json!({ "b": 1 });
+        patch.apply(&mut doc).unwrap();
+        assert_eq!(doc, json!({ "a": null, "b": null }));
+    }
+
+    #[test]
+    fn apply_with_options() {
+        let input = r#"[{ "op": "replace", "path": "/list/-", "value": 3 }]"#;
+        let patch: BorrowedPatch = serde_json::from_str(input).unwrap();
+        let options = PatchOptions {
+            dash: DashSemantics::LastElement,
+            ..PatchOptions::default()
+        };
+        let mut doc = json!({ "list": [1, 2] });
+        patch.apply_with(&mut doc, &options).unwrap();
+        assert_eq!(doc, json!({ "list": [1, 3] }));
+        assert!(patch.apply(&mut doc).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_operations() {
+        for input in [
+            r#"[{ "op": "add", "path": "/a" }]"#,
+            r#"[{ "op": "move", "path": "/a" }]"#,
+            r#"[{ "op": "remove", "path": "a" }]"#,
+            r#"[{ "op": "copy", "from": "a", "path": "/a" }]"#,
+            r#"[{ "op": "unknown", "path": "/a" }]"#,
+        ] {
+            assert!(
+                serde_json::from_str::<BorrowedPatch>(input).is_err(),
+                "{input}"
+            );
+        }
+    }
+}
@@ -0,0 +1,299 @@
+//! Encoding and decoding of JSON Patches as [CBOR (RFC 8949)](https://tools.ietf.org/html/rfc8949).
+//!
+//! CBOR keeps the same structure as the JSON representation (operations are maps with `op`,
+//! `path`, `from` and `value` members), but is considerably more compact for numbers and binary-ish
+//! strings, which makes it a better fit for persisting large numbers of patches.
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::{cbor, Patch};
+//! use serde_json::{from_value, json};
+//!
+//! let p: Patch = from_value(json!([
+//!   { "op": "add", "path": "/0/happy", "value": true }
+//! ])).unwrap();
+//!
+//! let bytes = cbor::to_vec(&p).unwrap();
+//! assert_eq!(cbor::from_slice(&bytes).unwrap(), p);
+//! ```
+//!
+//! Patches can also be applied to CBOR documents given as [`ciborium::Value`] with [`patch`] (and
+//! computed with [`diff`]). Map members are matched by text keys or by the decimal representation
+//! of integer keys; members with other keys (like byte strings) cannot be referred to by a JSON
+//! Pointer. Tagged values are transparent to JSON Pointers.
+//!
+//! ```rust
+//! use json_patch::{cbor, Patch};
+//! use ciborium::Value;
+//! use serde_json::{from_value, json};
+//!
+//! let mut doc = Value::Map(vec![
+//!     (Value::Integer(1.into()), Value::Text("temperature".into())),
+//!     (Value::Text("reading".into()), Value::Float(21.5)),
+//! ]);
+//! let p: Patch = from_value(json!([
+//!   { "op": "test", "path": "/1", "value": "temperature" },
+//!   { "op": "replace", "path": "/reading", "value": 22.0 },
+//! ])).unwrap();
+//! cbor::patch(&mut doc, &p).unwrap();
+//! assert_eq!(doc.as_map().unwrap()[1].1, Value::Float(22.0));
+//! ```
+#[cfg(feature = "diff")]
+use crate::document::Node;
+use crate::{document::Document, parse_index, Patch, PatchError, PatchErrorKind, PatchOperation};
+use ciborium::Value;
+use jsonptr::Token;
+use std::io;
+
+/// Error that can occur while encoding a patch as CBOR.
+pub type EncodeError = ciborium::ser::Error<io::Error>;
+
+/// Error that can occur while decoding a patch from CBOR.
+pub type DecodeError = ciborium::de::Error<io::Error>;
+
+/// Encode the patch as CBOR into the given writer.
+pub fn to_writer<W: io::Write>(patch: &Patch, writer: W) -> Result<(), EncodeError> {
+    ciborium::into_writer(patch, writer)
+}
+
+/// Encode the patch as a CBOR byte vector.
+pub fn to_vec(patch: &Patch) -> Result<Vec<u8>, EncodeError> {
+    let mut buf = Vec::new();
+    to_writer(patch, &mut buf)?;
+    Ok(buf)
+}
+
+/// Decode a patch from CBOR read from the given reader.
+pub fn from_reader<R: io::Read>(reader: R) -> Result<Patch, DecodeError> {
+    ciborium::from_reader(reader)
+}
+
+/// Decode a patch from a CBOR byte slice.
+pub fn from_slice(bytes: &[u8]) -> Result<Patch, DecodeError> {
+    from_reader(bytes)
+}
+
+/// Patch provided CBOR document in place, same as [`crate::patch`]. If any of the operations
+/// fails, the document is left unchanged.
+pub fn patch(doc: &mut Value, patch: &[PatchOperation]) -> Result<(), PatchError> {
+    crate::document::patch(doc, patch)
+}
+
+/// Error that can occur while converting a CBOR value to JSON.
+pub type ValueError = ciborium::value::Error;
+
+/// Diff two CBOR documents and generate a JSON Patch (RFC 6902), same as [`crate::diff`].
+///
+/// Returns an error if a value added by the patch cannot be represented as JSON, for example, a
+/// byte string or a map with keys which are neither text nor integers.
+#[cfg(feature = "diff")]
+pub fn diff(left: &Value, right: &Value) -> Result<Patch, ValueError> {
+    crate::document::diff(left, right)
+}
+
+fn key_str(key: &Value) -> Option<String> {
+    match key {
+        Value::Text(key) => Some(key.clone()),
+        Value::Integer(key) => Some(i128::from(*key).to_string()),
+        _ => None,
+    }
+}
+
+fn position(map: &[(Value, Value)], token: &Token) -> Option<usize> {
+    let token = token.decoded();
+    map.iter()
+        .position(|(key, _)| key_str(key).is_some_and(|key| key == token))
+}
+
+impl Document for Value {
+    fn from_json(value: &serde_json::Value) -> Result<Self, PatchErrorKind> {
+        Ok(Value::serialized(value).expect("JSON is always representable as CBOR"))
+    }
+
+    fn child(&self, token: &Token) -> Option<&Self> {
+        match self {
+            Value::Map(map) => Some(&map[position(map, token)?].1),
+            Value::Array(arr) => arr.get(parse_index(token.encoded(), arr.len()).ok()?),
+            Value::Tag(_, value) => value.child(token),
+            _ => None,
+        }
+    }
+
+    fn child_mut(&mut self, token: &Token) -> Option<&mut Self> {
+        match self {
+            Value::Map(map) => {
+                let idx = position(map, token)?;
+                Some(&mut map[idx].1)
+            }
+            Value::Array(arr) => {
+                let idx = parse_index(token.encoded(), arr.len()).ok()?;
+                arr.get_mut(idx)
+            }
+            Value::Tag(_, value) => value.child_mut(token),
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, token: &Token, value: Self) -> Result<(), PatchErrorKind> {
+        match self {
+            Value::Map(map) => match position(map, token) {
+                Some(idx) => map[idx].1 = value,
+                None => map.push((Value::Text(token.decoded().into_owned()), value)),
+            },
+            Value::Array(arr) if token.encoded() == "-" => arr.push(value),
+            Value::Array(arr) => {
+                let idx = parse_index(token.encoded(), arr.len() + 1)?;
+                arr.insert(idx, value);
+            }
+            Value::Tag(_, tagged) => return tagged.insert(token, value),
+            _ => return Err(PatchErrorKind::InvalidPointer),
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, token: &Token) -> Option<Self> {
+        match self {
+            Value::Map(map) => {
+                let idx = position(map, token)?;
+                Some(map.remove(idx).1)
+            }
+            Value::Array(arr) => {
+                let idx = parse_index(token.encoded(), arr.len()).ok()?;
+                Some(arr.remove(idx))
+            }
+            Value::Tag(_, value) => value.remove(token),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "diff")]
+    type JsonError = ValueError;
+
+    #[cfg(feature = "diff")]
+    fn to_json(&self) -> Result<serde_json::Value, ValueError> {
+        self.deserialized()
+    }
+
+    #[cfg(feature = "diff")]
+    fn node(&self) -> Node<'_, Self> {
+        match self {
+            Value::Map(map) => map
+                .iter()
+                .map(|(key, value)| Some((key_str(key)?, value)))
+                .collect::<Option<_>>()
+                .map_or(Node::Other, Node::Object),
+            Value::Array(arr) => Node::Array(arr),
+            Value::Tag(tag, value) => Node::Tagged(tag.to_string(), value),
+            _ => Node::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Patch;
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn roundtrip() {
+        let patch: Patch = from_value(json!([
+            { "op": "add", "path": "/a~1b/0", "value": { "x": [1, 2.5, null, "s"] } },
+            { "op": "remove", "path": "/c" },
+            { "op": "replace", "path": "", "value": false },
+            { "op": "move", "from": "/d", "path": "/e" },
+            { "op": "copy", "from": "/e", "path": "/f" },
+            { "op": "test", "path": "/f", "value": -1 },
+        ]))
+        .unwrap();
+
+        let bytes = super::to_vec(&patch).unwrap();
+        assert!(bytes.len() < patch.to_string().len());
+        assert_eq!(super::from_slice(&bytes).unwrap(), patch);
+    }
+
+    #[test]
+    fn decode_invalid_pointer() {
+        let bad = json!([{ "op": "remove", "path": "c" }]);
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&bad, &mut bytes).unwrap();
+        assert!(super::from_slice(&bytes).is_err());
+    }
+
+    #[test]
+    fn patch_document() {
+        use ciborium::Value;
+
+        let mut doc = Value::Map(vec![
+            (
+                Value::Integer(1.into()),
+                Value::Tag(40, Box::new(Value::Array(vec![]))),
+            ),
+            (Value::Bytes(vec![1]), Value::Null),
+        ]);
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "/1/-", "value": 1 },
+            { "op": "copy", "from": "/1/0", "path": "/1/0" },
+            { "op": "add", "path": "/a", "value": { "b": true } },
+            { "op": "move", "from": "/a/b", "path": "/c" },
+        ]))
+        .unwrap();
+        super::patch(&mut doc, &p).unwrap();
+
+        let ints = |ints: &[i32]| ints.iter().map(|&i| Value::Integer(i.into())).collect();
+        assert_eq!(
+            doc,
+            Value::Map(vec![
+                (
+                    Value::Integer(1.into()),
+                    Value::Tag(40, Box::new(Value::Array(ints(&[1, 1]))))
+                ),
+                (Value::Bytes(vec![1]), Value::Null),
+                (Value::Text("a".into()), Value::Map(vec![])),
+                (Value::Text("c".into()), Value::Bool(true)),
+            ])
+        );
+
+        let p: Patch = from_value(json!([{ "op": "remove", "path": "/1/2" }])).unwrap();
+        assert!(super::patch(&mut doc, &p).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "diff")]
+    fn diff_documents() {
+        use ciborium::Value;
+
+        let left = Value::Map(vec![
+            (
+                Value::Integer(1.into()),
+                Value::Array(vec![Value::Null, Value::Null]),
+            ),
+            (Value::Text("a".into()), Value::Integer(1.into())),
+        ]);
+        let right = Value::Map(vec![
+            (
+                Value::Integer(1.into()),
+                Value::Array(vec![Value::Bool(true)]),
+            ),
+            (Value::Text("b".into()), Value::Text("x".into())),
+        ]);
+        let p = super::diff(&left, &right).unwrap();
+        assert_eq!(
+            p,
+            from_value::<Patch>(json!([
+                { "op": "replace", "path": "/1/0", "value": true },
+                { "op": "remove", "path": "/1/1" },
+                { "op": "remove", "path": "/a" },
+                { "op": "add", "path": "/b", "value": "x" },
+            ]))
+            .unwrap()
+        );
+
+        let mut patched = left.clone();
+        super::patch(&mut patched, &p).unwrap();
+        assert_eq!(patched, right);
+
+        let right = Value::Map(vec![(Value::Bytes(vec![1]), Value::Null)]);
+        assert!(super::diff(&left, &right).is_err());
+    }
+}
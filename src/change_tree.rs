@@ -0,0 +1,280 @@
+use crate::{Patch, PatchError, PatchOperation};
+use jsonptr::{Pointer, PointerBuf, Token};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+/// Status of a node of a [`ChangeTree`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeStatus {
+    /// The value was added.
+    Added,
+    /// The value was removed.
+    Removed,
+    /// The value was replaced, or (for nodes with children) some of its descendants changed.
+    Modified,
+    /// Nothing was changed. Only used for the root of a tree built from a patch without
+    /// changes.
+    Unchanged,
+}
+
+/// Tree of the locations changed by a patch, created by [`Patch::change_tree`]. Suitable for
+/// rendering expandable diffs: each node is annotated with its status, nodes with children are
+/// containers some descendants of which changed, and nodes without children are the changed
+/// values themselves.
+///
+/// Serialized as nested objects, with children keyed by the (decoded) object key or array index:
+///
+/// ```json
+/// { "status": "modified", "children": {
+///   "title": { "status": "modified", "old": "Goodbye!", "new": "Hello!" }
+/// } }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ChangeTree {
+    /// Status of the node.
+    pub status: ChangeStatus,
+    /// Value before the patch, only set for changed values when built with the base document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    /// Value after the patch, only set for changed values when built with the base document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+    /// Changed children, in the order they were first changed by the patch.
+    #[serde(
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_children"
+    )]
+    pub children: Vec<(String, ChangeTree)>,
+}
+
+fn serialize_children<S: Serializer>(
+    children: &[(String, ChangeTree)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(children.iter().map(|(key, child)| (key, child)))
+}
+
+impl Patch {
+    /// Builds the tree of the locations changed by this patch, for presenting the changes to
+    /// end users.
+    ///
+    /// Without the base document, statuses are derived from the operations alone: `add` and
+    /// `copy` add a value (even if the location already existed), `remove` removes it, `replace`
+    /// modifies it and `move` does both. Changes made by several operations to the same location
+    /// are combined (a value added and then removed does not appear in the tree at all).
+    ///
+    /// With the base document, the patch is applied to a copy of it, `-` array indices are
+    /// resolved to the actual ones, and every changed value gets the `old` and `new` values found
+    /// at its location before and after the patch, with the status derived from them. Values
+    /// equal before and after the patch are left out. Note that array elements are compared by
+    /// position, so inserting or removing elements also reports the shifted ones at the same
+    /// location as modified.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::Patch;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let doc = json!({ "title": "Goodbye!", "tags": ["example"] });
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "replace", "path": "/title", "value": "Hello!" },
+    ///   { "op": "add", "path": "/tags/-", "value": "sample" },
+    /// ])).unwrap();
+    ///
+    /// let tree = p.change_tree(Some(&doc)).unwrap();
+    /// assert_eq!(serde_json::to_value(&tree).unwrap(), json!({
+    ///   "status": "modified",
+    ///   "children": {
+    ///     "title": { "status": "modified", "old": "Goodbye!", "new": "Hello!" },
+    ///     "tags": { "status": "modified", "children": {
+    ///       "1": { "status": "added", "new": "sample" },
+    ///     } },
+    ///   },
+    /// }));
+    /// ```
+    pub fn change_tree(&self, base: Option<&Value>) -> Result<ChangeTree, PatchError> {
+        let mut tree = ChangeTree::empty();
+        match base {
+            Some(base) => {
+                let resolved = self.to_reversible(base)?.forward();
+                let mut patched = base.clone();
+                crate::patch(&mut patched, &resolved)?;
+                record_all(&mut tree, &resolved);
+                tree.compare(&mut PointerBuf::new(), base, &patched);
+            }
+            None => record_all(&mut tree, self),
+        }
+        tree.prune();
+        Ok(tree)
+    }
+}
+
+fn record_all(tree: &mut ChangeTree, patch: &[PatchOperation]) {
+    for op in patch {
+        match op {
+            PatchOperation::Add(op) => tree.record(&op.path, ChangeStatus::Added),
+            PatchOperation::Remove(op) => tree.record(&op.path, ChangeStatus::Removed),
+            PatchOperation::Replace(op) => tree.record(&op.path, ChangeStatus::Modified),
+            PatchOperation::Move(op) => {
+                tree.record(&op.from, ChangeStatus::Removed);
+                tree.record(&op.path, ChangeStatus::Added);
+            }
+            PatchOperation::Copy(op) => tree.record(&op.path, ChangeStatus::Added),
+            PatchOperation::Test(_) => {}
+        }
+    }
+}
+
+impl ChangeTree {
+    fn empty() -> ChangeTree {
+        ChangeTree {
+            status: ChangeStatus::Unchanged,
+            old: None,
+            new: None,
+            children: Vec::new(),
+        }
+    }
+
+    // Changed value, as opposed to a container with changed descendants
+    fn is_leaf(&self) -> bool {
+        self.status != ChangeStatus::Unchanged && self.children.is_empty()
+    }
+
+    fn record(&mut self, path: &Pointer, status: ChangeStatus) {
+        let mut node = self;
+        for token in path.tokens() {
+            if node.is_leaf() {
+                // Change inside of an already changed value
+                return;
+            }
+            node.status = ChangeStatus::Modified;
+            let key = token.decoded();
+            let idx = match node.children.iter().position(|(k, _)| *k == key) {
+                Some(idx) => idx,
+                None => {
+                    node.children.push((key.into_owned(), ChangeTree::empty()));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[idx].1;
+        }
+
+        let previous = match node.status {
+            _ if node.is_leaf() => Some(node.status),
+            ChangeStatus::Unchanged => None,
+            // Container which existed before the patch
+            _ => Some(ChangeStatus::Modified),
+        };
+        node.status = match (previous, status) {
+            (Some(ChangeStatus::Added), ChangeStatus::Removed) => ChangeStatus::Unchanged,
+            (Some(ChangeStatus::Added), _) => ChangeStatus::Added,
+            (Some(ChangeStatus::Removed), ChangeStatus::Added) => ChangeStatus::Modified,
+            (Some(ChangeStatus::Modified), ChangeStatus::Added) => ChangeStatus::Modified,
+            (_, status) => status,
+        };
+        node.children.clear();
+    }
+
+    fn compare(&mut self, path: &mut PointerBuf, base: &Value, patched: &Value) {
+        if self.is_leaf() {
+            let old = base.pointer(path.as_str());
+            let new = patched.pointer(path.as_str());
+            self.status = match (old, new) {
+                (Some(old), Some(new)) if old == new => ChangeStatus::Unchanged,
+                (Some(_), Some(_)) => ChangeStatus::Modified,
+                (None, Some(_)) => ChangeStatus::Added,
+                (Some(_), None) => ChangeStatus::Removed,
+                (None, None) => ChangeStatus::Unchanged,
+            };
+            self.old = old.cloned();
+            self.new = new.cloned();
+            return;
+        }
+        for (key, child) in &mut self.children {
+            path.push_back(Token::new(key.as_str()));
+            child.compare(path, base, patched);
+            path.pop_back();
+        }
+    }
+
+    // Removes nodes which ended up unchanged and updates the status of containers accordingly
+    fn prune(&mut self) {
+        if self.children.is_empty() {
+            if self.status == ChangeStatus::Unchanged {
+                self.old = None;
+                self.new = None;
+            }
+            return;
+        }
+        for (_, child) in &mut self.children {
+            child.prune();
+        }
+        self.children
+            .retain(|(_, child)| child.status != ChangeStatus::Unchanged);
+        if self.children.is_empty() {
+            self.status = ChangeStatus::Unchanged;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChangeStatus;
+    use crate::Patch;
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn without_base() {
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "/a/b", "value": {} },
+            { "op": "add", "path": "/a/b/c", "value": 1 },
+            { "op": "remove", "path": "/x" },
+            { "op": "add", "path": "/x", "value": 2 },
+            { "op": "add", "path": "/tmp", "value": 3 },
+            { "op": "remove", "path": "/tmp" },
+            { "op": "move", "from": "/list/0", "path": "/list/-" },
+            { "op": "test", "path": "/y", "value": 3 },
+        ]))
+        .unwrap();
+        let tree = p.change_tree(None).unwrap();
+        assert_eq!(
+            serde_json::to_value(&tree).unwrap(),
+            json!({ "status": "modified", "children": {
+                "a": { "status": "modified", "children": { "b": { "status": "added" } } },
+                "x": { "status": "modified" },
+                "list": { "status": "modified", "children": {
+                    "0": { "status": "removed" },
+                    "-": { "status": "added" },
+                } },
+            } })
+        );
+    }
+
+    #[test]
+    fn with_base() {
+        let doc = json!({ "a": { "b": 1, "c": 2 }, "d": [1] });
+        let p: Patch = from_value(json!([
+            { "op": "replace", "path": "/a/b", "value": 1 },
+            { "op": "remove", "path": "/a/c" },
+            { "op": "replace", "path": "/d", "value": [1, 2] },
+            { "op": "add", "path": "/d/0", "value": 0 },
+        ]))
+        .unwrap();
+        let tree = p.change_tree(Some(&doc)).unwrap();
+        assert_eq!(
+            serde_json::to_value(&tree).unwrap(),
+            json!({ "status": "modified", "children": {
+                "a": { "status": "modified", "children": {
+                    "c": { "status": "removed", "old": 2 },
+                } },
+                "d": { "status": "modified", "old": [1], "new": [0, 1, 2] },
+            } })
+        );
+
+        let tree = Patch::default().change_tree(Some(&doc)).unwrap();
+        assert_eq!(tree.status, ChangeStatus::Unchanged);
+        assert!(p.change_tree(Some(&json!(null))).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+//! Tamper-evident chains of patches.
+//!
+//! A [`ChangeSet`] records a patch along with its author, timestamp, the hash of the document it
+//! was applied to and the hash of the previous change set. The [hash](ChangeSet::hash) of the
+//! change set covers all of these, so [`verify_chain`] detects change sets which are modified,
+//! missing, reordered, or applied to a different document.
+//!
+//! Hashes are SHA-256 digests of the canonical JSON serialization (see
+//! [`Patch::to_canonical_string`]), encoded as lowercase hex strings. They are not keyed, so
+//! anyone modifying a change set can recompute the hashes of it and all the change sets after it.
+//! The chain is only tamper-evident if the hash of its last change set (the head) is stored
+//! somewhere trusted and compared with the head of the chain being verified.
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::changeset::{document_hash, verify_chain, ChangeSet};
+//! use json_patch::Patch;
+//! use serde_json::{from_value, json};
+//!
+//! let base = json!({ "title": "Draft" });
+//! let p: Patch = from_value(json!([
+//!   { "op": "replace", "path": "/title", "value": "Hello!" },
+//! ])).unwrap();
+//!
+//! let change = ChangeSet::new(None, "andrew", 1700000000000, &base, p);
+//! let head = change.hash.clone();
+//! let doc = verify_chain(&base, &[change.clone()]).unwrap();
+//! assert_eq!(doc, json!({ "title": "Hello!" }));
+//!
+//! let mut forged = change;
+//! forged.author = "john".into();
+//! assert!(verify_chain(&base, &[forged.clone()]).is_err());
+//!
+//! // Rehashed forgery passes the verification, but not the comparison with the trusted head
+//! forged.hash = forged.compute_hash();
+//! assert!(verify_chain(&base, &[forged.clone()]).is_ok());
+//! assert_ne!(forged.hash, head);
+//! ```
+use crate::{patch, Canonical, Patch, PatchError};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use thiserror::Error;
+
+/// Patch with metadata, linked to the document it applies to and to the previous change set by
+/// hash.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChangeSet {
+    /// Author of the change.
+    pub author: String,
+    /// Time of the change, in milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Hash of the document the patch applies to, see [`document_hash`].
+    pub parent: String,
+    /// Hash of the previous change set in the chain, `None` for the first one.
+    pub previous: Option<String>,
+    /// Hash of the change set itself, covering the patch and all metadata.
+    pub hash: String,
+    /// Recorded patch.
+    pub patch: Patch,
+}
+
+impl ChangeSet {
+    /// Records a patch applied to the `parent` document, following the `previous` change set
+    /// (`None` to start a new chain).
+    pub fn new(
+        previous: Option<&ChangeSet>,
+        author: impl Into<String>,
+        timestamp: u64,
+        parent: &Value,
+        patch: Patch,
+    ) -> Self {
+        let mut change = ChangeSet {
+            author: author.into(),
+            timestamp,
+            parent: document_hash(parent),
+            previous: previous.map(|previous| previous.hash.clone()),
+            hash: String::new(),
+            patch,
+        };
+        change.hash = change.compute_hash();
+        change
+    }
+
+    /// Computes the hash of the change set from its contents, ignoring the recorded
+    /// [`hash`](ChangeSet::hash).
+    pub fn compute_hash(&self) -> String {
+        let content = json!({
+            "author": self.author,
+            "timestamp": self.timestamp,
+            "parent": self.parent,
+            "previous": self.previous,
+            "patch": self.patch,
+        });
+        document_hash(&content)
+    }
+
+    /// Checks if the recorded hash matches the contents of the change set.
+    pub fn is_intact(&self) -> bool {
+        self.compute_hash() == self.hash
+    }
+}
+
+/// Error found by [`verify_chain`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ChainError {
+    /// Contents of the change set do not match its hash.
+    #[error("change set '/{index}' does not match its hash")]
+    Tampered {
+        /// Index of the change set in the chain.
+        index: usize,
+    },
+    /// Change set was recorded against a different document.
+    #[error("change set '/{index}' does not apply to the current document")]
+    ParentMismatch {
+        /// Index of the change set in the chain.
+        index: usize,
+    },
+    /// Change set does not follow the previous change set of the chain.
+    #[error("change set '/{index}' does not follow the previous change set")]
+    PreviousMismatch {
+        /// Index of the change set in the chain.
+        index: usize,
+    },
+    /// Recorded patch failed to apply.
+    #[error("change set '/{index}' failed to apply: {source}")]
+    Patch {
+        /// Index of the change set in the chain.
+        index: usize,
+        /// Patch error.
+        source: PatchError,
+    },
+}
+
+/// Computes the hash of a JSON document: the SHA-256 digest of its canonical serialization, as a
+/// lowercase hex string. Order of object members does not affect the hash.
+pub fn document_hash(doc: &Value) -> String {
+    let mut hasher = Sha256::new();
+    serde_json::to_writer(&mut hasher, &Canonical(doc)).expect("value is always serializable");
+    hasher
+        .finalize()
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+/// Applies the chain of change sets to the `base` document, checking that every change set is
+/// intact, follows the previous change set of the chain (the first one may follow any change set,
+/// so a chain can be verified from the middle) and was recorded against the document it is
+/// applied to. Returns the resulting document.
+///
+/// Since anyone can build a valid chain, compare the [`hash`](ChangeSet::hash) of the last change
+/// set with a trusted one. The hash of the resulting document can be compared with the recorded
+/// one (for example, the [`parent`](ChangeSet::parent) of the next change set) using
+/// [`document_hash`].
+pub fn verify_chain(base: &Value, chain: &[ChangeSet]) -> Result<Value, ChainError> {
+    let mut doc = base.clone();
+    let mut previous = None;
+    for (index, change) in chain.iter().enumerate() {
+        if !change.is_intact() {
+            return Err(ChainError::Tampered { index });
+        }
+        if document_hash(&doc) != change.parent {
+            return Err(ChainError::ParentMismatch { index });
+        }
+        if previous.is_some() && change.previous.as_ref() != previous {
+            return Err(ChainError::PreviousMismatch { index });
+        }
+        previous = Some(&change.hash);
+        patch(&mut doc, &change.patch).map_err(|source| ChainError::Patch { index, source })?;
+    }
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{document_hash, verify_chain, ChainError, ChangeSet};
+    use crate::Patch;
+    use serde_json::{from_value, json, Value};
+
+    fn chain(base: &Value, patches: Value) -> Vec<ChangeSet> {
+        let patches: Vec<Patch> = from_value(patches).unwrap();
+        let mut doc = base.clone();
+        let mut changes: Vec<ChangeSet> = Vec::new();
+        for (idx, p) in patches.into_iter().enumerate() {
+            let change = ChangeSet::new(changes.last(), "andrew", idx as u64, &doc, p);
+            crate::patch(&mut doc, &change.patch).unwrap();
+            changes.push(change);
+        }
+        changes
+    }
+
+    #[test]
+    fn document_hash_is_canonical() {
+        let left = json!({ "a": 1, "b": [true, null] });
+        let right: Value = serde_json::from_str(r#"{"b":[true,null],"a":1}"#).unwrap();
+        assert_eq!(document_hash(&left), document_hash(&right));
+        assert_ne!(document_hash(&left), document_hash(&json!({ "a": 2 })));
+        assert_eq!(
+            document_hash(&json!({})),
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+    }
+
+    #[test]
+    fn verify() {
+        let base = json!({ "count": 0 });
+        let changes = chain(
+            &base,
+            json!([
+                [{ "op": "replace", "path": "/count", "value": 1 }],
+                [{ "op": "add", "path": "/name", "value": "x" }],
+            ]),
+        );
+        let doc = verify_chain(&base, &changes).unwrap();
+        assert_eq!(doc, json!({ "count": 1, "name": "x" }));
+
+        let mut tampered = changes.clone();
+        tampered[1].patch =
+            from_value(json!([{ "op": "add", "path": "/name", "value": "y" }])).unwrap();
+        assert!(matches!(
+            verify_chain(&base, &tampered),
+            Err(ChainError::Tampered { index: 1 })
+        ));
+
+        let skipped = &changes[1..];
+        assert!(matches!(
+            verify_chain(&base, skipped),
+            Err(ChainError::ParentMismatch { index: 0 })
+        ));
+
+        // Change set recorded against the same document, but not following the previous one
+        let mut unlinked = changes.clone();
+        let p = from_value(json!([{ "op": "add", "path": "/name", "value": "y" }])).unwrap();
+        unlinked[1] = ChangeSet::new(None, "john", 1, &json!({ "count": 1 }), p);
+        assert!(matches!(
+            verify_chain(&base, &unlinked),
+            Err(ChainError::PreviousMismatch { index: 1 })
+        ));
+        assert!(verify_chain(&json!({ "count": 1 }), &changes[1..]).is_ok());
+
+        let json = serde_json::to_string(&changes).unwrap();
+        let parsed: Vec<ChangeSet> = serde_json::from_str(&json).unwrap();
+        assert_eq!(verify_chain(&base, &parsed).unwrap(), doc);
+    }
+}
@@ -0,0 +1,357 @@
+//! Patches for collections of records, such as NDJSON files or database tables.
+//!
+//! Records are identified by the value at the key pointer, which must be a string or a number
+//! (numbers are keyed by their JSON representation). A [`CollectionPatch`] is a list of
+//! [`RecordChange`]s, each inserting, deleting or patching a single record, and can be applied to
+//! any [`RecordStore`], like a `HashMap` or a `BTreeMap` of records by key.
+use crate::{Patch, PatchError};
+#[cfg(feature = "diff")]
+use jsonptr::Pointer;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+use thiserror::Error;
+
+/// Change of a single record, see [`CollectionPatch`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum RecordChange {
+    /// Insert a new record.
+    Insert {
+        /// Key of the record.
+        key: String,
+        /// Inserted record.
+        value: Value,
+    },
+    /// Delete an existing record.
+    Delete {
+        /// Key of the record.
+        key: String,
+    },
+    /// Patch an existing record.
+    Patch {
+        /// Key of the record.
+        key: String,
+        /// Patch to apply to the record.
+        patch: Patch,
+    },
+}
+
+impl RecordChange {
+    /// Returns the key of the changed record.
+    pub fn key(&self) -> &str {
+        match self {
+            RecordChange::Insert { key, .. }
+            | RecordChange::Delete { key }
+            | RecordChange::Patch { key, .. } => key,
+        }
+    }
+}
+
+/// Error that can occur while building or applying a [`CollectionPatch`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum CollectionError {
+    /// Record has no value at the key pointer, or it is neither a string nor a number.
+    #[error("record {index} has no valid key")]
+    InvalidKey {
+        /// Index of the record in its collection.
+        index: usize,
+    },
+    /// Several records of the same collection have the same key.
+    #[error("duplicate key '{key}'")]
+    DuplicateKey {
+        /// Duplicate key.
+        key: String,
+    },
+    /// Inserted record already exists in the store.
+    #[error("record '{key}' already exists")]
+    AlreadyExists {
+        /// Key of the record.
+        key: String,
+    },
+    /// Deleted or patched record does not exist in the store.
+    #[error("record '{key}' does not exist")]
+    NotFound {
+        /// Key of the record.
+        key: String,
+    },
+    /// Record patch failed to apply.
+    #[error("patching record '{key}' failed: {source}")]
+    Patch {
+        /// Key of the record.
+        key: String,
+        /// Patch error.
+        source: PatchError,
+    },
+}
+
+/// Store of records by key, which [`CollectionPatch`] can be applied to.
+pub trait RecordStore {
+    /// Returns the record with the given key.
+    fn get(&self, key: &str) -> Option<&Value>;
+    /// Inserts the record, replacing the existing one with the same key.
+    fn insert(&mut self, key: String, value: Value);
+    /// Removes the record with the given key, returning it.
+    fn remove(&mut self, key: &str) -> Option<Value>;
+}
+
+impl<S: BuildHasher> RecordStore for HashMap<String, Value, S> {
+    fn get(&self, key: &str) -> Option<&Value> {
+        HashMap::get(self, key)
+    }
+
+    fn insert(&mut self, key: String, value: Value) {
+        HashMap::insert(self, key, value);
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Value> {
+        HashMap::remove(self, key)
+    }
+}
+
+impl RecordStore for BTreeMap<String, Value> {
+    fn get(&self, key: &str) -> Option<&Value> {
+        BTreeMap::get(self, key)
+    }
+
+    fn insert(&mut self, key: String, value: Value) {
+        BTreeMap::insert(self, key, value);
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Value> {
+        BTreeMap::remove(self, key)
+    }
+}
+
+/// Changes between two collections of records, as per-record inserts, deletes and patches.
+///
+/// See the [module documentation](self).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CollectionPatch {
+    /// Changes, at most one per record. Every change is checked against the store before any
+    /// of them is applied.
+    pub changes: Vec<RecordChange>,
+}
+
+impl CollectionPatch {
+    /// Diff two collections of records keyed by the value at the `key` pointer. Changes are
+    /// ordered by key. Unchanged records are omitted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::collection::{CollectionPatch, RecordChange};
+    /// use jsonptr::Pointer;
+    /// use serde_json::json;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let key = Pointer::from_static("/id");
+    /// let left = [json!({ "id": "a", "n": 1 }), json!({ "id": "b", "n": 2 })];
+    /// let right = [json!({ "id": "b", "n": 3 }), json!({ "id": "c", "n": 4 })];
+    ///
+    /// let p = CollectionPatch::diff(key, &left, &right).unwrap();
+    /// assert_eq!(p.changes[0], RecordChange::Delete { key: "a".into() });
+    ///
+    /// let mut store: BTreeMap<String, _> = BTreeMap::new();
+    /// store.insert("a".into(), left[0].clone());
+    /// store.insert("b".into(), left[1].clone());
+    /// p.apply(&mut store).unwrap();
+    /// assert_eq!(store.into_values().collect::<Vec<_>>(), right);
+    /// ```
+    #[cfg(feature = "diff")]
+    pub fn diff<'a>(
+        key: &Pointer,
+        left: impl IntoIterator<Item = &'a Value>,
+        right: impl IntoIterator<Item = &'a Value>,
+    ) -> Result<CollectionPatch, CollectionError> {
+        let mut left = index(key, left)?;
+        let right = index(key, right)?;
+
+        let mut changes = Vec::new();
+        for (k, value) in right {
+            match left.remove(&k) {
+                Some(previous) => {
+                    let patch = crate::diff(previous, value);
+                    if !patch.is_empty() {
+                        changes.push(RecordChange::Patch { key: k, patch });
+                    }
+                }
+                None => changes.push(RecordChange::Insert {
+                    key: k,
+                    value: value.clone(),
+                }),
+            }
+        }
+        changes.extend(left.into_keys().map(|k| RecordChange::Delete { key: k }));
+        changes.sort_by(|a, b| a.key().cmp(b.key()));
+        Ok(CollectionPatch { changes })
+    }
+
+    /// Returns `true` if the patch changes no records.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Apply the patch to the store. All changes are checked, and record patches applied to
+    /// copies of the records, before the store is modified, so on error the store is left
+    /// unchanged.
+    pub fn apply(&self, store: &mut impl RecordStore) -> Result<(), CollectionError> {
+        let mut updates = Vec::with_capacity(self.changes.len());
+        for change in &self.changes {
+            let key = change.key();
+            let existing = store.get(key);
+            let update = match (change, existing) {
+                (RecordChange::Insert { value, .. }, None) => Some(value.clone()),
+                (RecordChange::Insert { .. }, Some(_)) => {
+                    return Err(CollectionError::AlreadyExists { key: key.into() })
+                }
+                (RecordChange::Delete { .. }, Some(_)) => None,
+                (RecordChange::Patch { patch, .. }, Some(existing)) => {
+                    let mut value = existing.clone();
+                    crate::patch(&mut value, patch).map_err(|source| CollectionError::Patch {
+                        key: key.into(),
+                        source,
+                    })?;
+                    Some(value)
+                }
+                (RecordChange::Delete { .. } | RecordChange::Patch { .. }, None) => {
+                    return Err(CollectionError::NotFound { key: key.into() })
+                }
+            };
+            updates.push((key, update));
+        }
+
+        for (key, update) in updates {
+            match update {
+                Some(value) => store.insert(key.into(), value),
+                None => {
+                    store.remove(key);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the key of the record: strings as is, numbers by their JSON representation.
+#[cfg(feature = "diff")]
+fn record_key(key: &Pointer, record: &Value) -> Option<String> {
+    match key.resolve(record).ok()? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "diff")]
+fn index<'a>(
+    key: &Pointer,
+    records: impl IntoIterator<Item = &'a Value>,
+) -> Result<BTreeMap<String, &'a Value>, CollectionError> {
+    let mut indexed = BTreeMap::new();
+    for (idx, record) in records.into_iter().enumerate() {
+        let k = record_key(key, record).ok_or(CollectionError::InvalidKey { index: idx })?;
+        if indexed.insert(k.clone(), record).is_some() {
+            return Err(CollectionError::DuplicateKey { key: k });
+        }
+    }
+    Ok(indexed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CollectionError, CollectionPatch, RecordChange};
+    #[cfg(feature = "diff")]
+    use jsonptr::Pointer;
+    use serde_json::{from_value, json, Value};
+    use std::collections::HashMap;
+
+    #[cfg(feature = "diff")]
+    #[test]
+    fn diff_and_apply() {
+        let key = Pointer::from_static("/meta/id");
+        let left = [
+            json!({ "meta": { "id": 1 }, "name": "a" }),
+            json!({ "meta": { "id": 2 }, "name": "b" }),
+            json!({ "meta": { "id": 3 }, "name": "c" }),
+        ];
+        let right = [
+            json!({ "meta": { "id": 4 }, "name": "d" }),
+            json!({ "meta": { "id": 3 }, "name": "c" }),
+            json!({ "meta": { "id": 1 }, "name": "x" }),
+        ];
+        let p = CollectionPatch::diff(key, &left, &right).unwrap();
+        assert_eq!(
+            serde_json::to_value(&p).unwrap(),
+            json!([
+                { "op": "patch", "key": "1", "patch": [
+                    { "op": "replace", "path": "/name", "value": "x" },
+                ] },
+                { "op": "delete", "key": "2" },
+                { "op": "insert", "key": "4", "value": { "meta": { "id": 4 }, "name": "d" } },
+            ])
+        );
+        assert_eq!(
+            from_value::<CollectionPatch>(serde_json::to_value(&p).unwrap()).unwrap(),
+            p
+        );
+
+        let mut store: HashMap<String, Value> = HashMap::new();
+        for (k, record) in [("1", &left[0]), ("2", &left[1]), ("3", &left[2])] {
+            store.insert(k.into(), record.clone());
+        }
+        p.apply(&mut store).unwrap();
+        assert_eq!(store.len(), 3);
+        assert_eq!(store["1"], right[2]);
+        assert_eq!(store["4"], right[0]);
+
+        // Applying again fails at the delete, without modifying the store
+        let before = store.clone();
+        let err = p.apply(&mut store).unwrap_err();
+        assert!(matches!(err, CollectionError::NotFound { key } if key == "2"));
+        assert_eq!(store, before);
+    }
+
+    #[cfg(feature = "diff")]
+    #[test]
+    fn invalid_keys() {
+        let key = Pointer::from_static("/id");
+        let err = CollectionPatch::diff(key, &[json!({ "id": true })], &[]).unwrap_err();
+        assert!(matches!(err, CollectionError::InvalidKey { index: 0 }));
+
+        let err = CollectionPatch::diff(key, &[], &[json!({ "id": "a" }), json!({ "id": "a" })])
+            .unwrap_err();
+        assert!(matches!(err, CollectionError::DuplicateKey { key } if key == "a"));
+    }
+
+    #[test]
+    fn apply_failures() {
+        let mut store: HashMap<String, Value> = HashMap::new();
+        store.insert("a".into(), json!({ "n": 1 }));
+
+        let p = CollectionPatch {
+            changes: vec![
+                RecordChange::Delete { key: "a".into() },
+                RecordChange::Insert {
+                    key: "a".into(),
+                    value: json!({}),
+                },
+            ],
+        };
+        let err = p.apply(&mut store).unwrap_err();
+        assert!(matches!(err, CollectionError::AlreadyExists { key } if key == "a"));
+
+        let p = CollectionPatch {
+            changes: vec![RecordChange::Patch {
+                key: "a".into(),
+                patch: from_value(json!([{ "op": "remove", "path": "/m" }])).unwrap(),
+            }],
+        };
+        let err = p.apply(&mut store).unwrap_err();
+        assert!(matches!(err, CollectionError::Patch { key, .. } if key == "a"));
+        assert_eq!(store["a"], json!({ "n": 1 }));
+    }
+}
@@ -0,0 +1,342 @@
+use crate::{
+    location_affects, strip_pointer_prefix, AddOperation, CopyOperation, Patch, PatchOperation,
+    RemoveOperation, ReplaceOperation,
+};
+use jsonptr::Pointer;
+
+/// Squash a run of patches into a single patch with the same effect.
+///
+/// Operations are concatenated, then operations whose result is overwritten by a later `replace`,
+/// `remove` or `add` before being used are dropped. The patches are assumed to apply
+/// successfully in order: the compacted patch gives the same result on every document the
+/// original patches apply to, but could also apply to documents the original patches would
+/// reject. `test` operations are always kept.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{compact, Patch};
+/// use serde_json::{from_value, json};
+///
+/// let journal: Vec<Patch> = from_value(json!([
+///   [{ "op": "add", "path": "/title", "value": "Draft" }],
+///   [{ "op": "add", "path": "/author", "value": { "name": "John" } }],
+///   [{ "op": "replace", "path": "/title", "value": "Hello!" }],
+///   [{ "op": "replace", "path": "/author/name", "value": "Andrew" }],
+/// ])).unwrap();
+///
+/// assert_eq!(compact(&journal), from_value::<Patch>(json!([
+///   { "op": "add", "path": "/author", "value": { "name": "John" } },
+///   { "op": "add", "path": "/title", "value": "Hello!" },
+///   { "op": "replace", "path": "/author/name", "value": "Andrew" },
+/// ])).unwrap());
+/// ```
+pub fn compact(patches: &[Patch]) -> Patch {
+    let mut ops: Vec<PatchOperation> = patches.iter().flat_map(|p| p.iter().cloned()).collect();
+    'outer: loop {
+        for later in (0..ops.len()).rev() {
+            for earlier in (0..later).rev() {
+                if let Some(replacement) = overwrite(&ops[earlier..=later]) {
+                    if let Some(replacement) = replacement {
+                        ops[later] = replacement;
+                    }
+                    ops.remove(earlier);
+                    continue 'outer;
+                }
+            }
+        }
+        return Patch(ops);
+    }
+}
+
+/// Squash a journal of patches, keeping the versions listed in `checkpoints` reconstructable.
+///
+/// Version `n` is the document after applying the first `n` patches of the journal. Returns the
+/// list of `(version, patch)` pairs, where each patch is a [`compact`]ed run taking the document
+/// from the previous version (or the initial document) to the given one. The last pair is always
+/// the final version of the journal. Checkpoints which are `0` or not less than the length of the
+/// journal are ignored.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{compact_journal, patch, Patch};
+/// use serde_json::{from_value, json};
+///
+/// let journal: Vec<Patch> = from_value(json!([
+///   [{ "op": "add", "path": "/title", "value": "Draft" }],
+///   [{ "op": "replace", "path": "/title", "value": "Review" }],
+///   [{ "op": "replace", "path": "/title", "value": "Hello!" }],
+/// ])).unwrap();
+///
+/// let compacted = compact_journal(&journal, &[2]);
+/// assert_eq!(compacted.len(), 2);
+/// assert_eq!(compacted[0].0, 2);
+/// assert_eq!(compacted[1].0, 3);
+///
+/// let mut doc = json!({});
+/// patch(&mut doc, &compacted[0].1).unwrap();
+/// assert_eq!(doc, json!({ "title": "Review" }));
+/// ```
+pub fn compact_journal(journal: &[Patch], checkpoints: &[usize]) -> Vec<(usize, Patch)> {
+    let mut versions: Vec<usize> = checkpoints
+        .iter()
+        .copied()
+        .filter(|&version| version > 0 && version < journal.len())
+        .collect();
+    versions.sort_unstable();
+    versions.dedup();
+    if !journal.is_empty() {
+        versions.push(journal.len());
+    }
+
+    let mut start = 0;
+    versions
+        .into_iter()
+        .map(|version| {
+            let patch = compact(&journal[start..version]);
+            start = version;
+            (version, patch)
+        })
+        .collect()
+}
+
+impl Patch {
+    /// Returns the indices of the operations whose result is guaranteed to be overwritten by a
+    /// later `replace`, `remove` or `add` operation before being used, the same operations
+    /// [`compact`] drops. Such operations usually point to a bug in the code producing the patch.
+    /// `test` and `move` operations are never reported.
+    ///
+    /// Reported operations have no effect on the patched document, but an operation creating the
+    /// location a later `replace` overwrites is still needed for that `replace` to succeed;
+    /// [`compact`] turns such `replace` into an `add` when dropping the operation.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::Patch;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "replace", "path": "/a", "value": 1 },
+    ///   { "op": "add", "path": "/b/c", "value": 2 },
+    ///   { "op": "replace", "path": "/a", "value": 3 },
+    ///   { "op": "copy", "from": "/b/c", "path": "/d" },
+    ///   { "op": "replace", "path": "/b", "value": {} },
+    /// ])).unwrap();
+    ///
+    /// assert_eq!(p.shadowed_ops(), [0]);
+    /// ```
+    pub fn shadowed_ops(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|&earlier| {
+                (earlier + 1..self.len()).any(|later| overwrite(&self[earlier..=later]).is_some())
+            })
+            .collect()
+    }
+}
+
+// Checks if the first operation of the run is overwritten by the last one and can be dropped.
+// Returns the operation to put in place of the last one if it needs to change as well.
+fn overwrite(run: &[PatchOperation]) -> Option<Option<PatchOperation>> {
+    let (earlier, rest) = run.split_first()?;
+    let (later, between) = rest.split_last()?;
+
+    let (location, creates) = match earlier {
+        PatchOperation::Add(AddOperation { path, .. })
+        | PatchOperation::Copy(CopyOperation { path, .. }) => (path.as_ptr(), true),
+        PatchOperation::Replace(ReplaceOperation { path, .. }) => (path.as_ptr(), false),
+        PatchOperation::Remove(op) => (op.path.as_ptr(), true),
+        PatchOperation::Move(_) | PatchOperation::Test(_) => return None,
+    };
+    let replacement = match later {
+        PatchOperation::Replace(ReplaceOperation { path, value }) if path == location => {
+            if !creates {
+                None
+            } else if matches!(earlier, PatchOperation::Remove(_)) || is_index(path) {
+                return None;
+            } else {
+                // The location might be created by the dropped operation
+                Some(PatchOperation::Add(AddOperation {
+                    path: path.clone(),
+                    value: value.clone(),
+                }))
+            }
+        }
+        PatchOperation::Add(AddOperation { path, .. }) if path == location && !is_index(path) => {
+            None
+        }
+        PatchOperation::Replace(ReplaceOperation { path, .. })
+        | PatchOperation::Remove(RemoveOperation { path })
+            if path != location && strip_pointer_prefix(location, path).is_some() =>
+        {
+            None
+        }
+        PatchOperation::Add(AddOperation { path, .. })
+            if path != location
+                && !is_index(path)
+                && strip_pointer_prefix(location, path).is_some() =>
+        {
+            None
+        }
+        _ => return None,
+    };
+
+    if between.iter().any(|op| interferes(earlier, op)) {
+        return None;
+    }
+    Some(replacement)
+}
+
+// Checks if the last token of the pointer could be an array index, so `add` would insert a new
+// element rather than overwrite the existing one.
+fn is_index(path: &Pointer) -> bool {
+    path.last().is_some_and(|token| token.to_index().is_ok())
+}
+
+// Checks if the operations could depend on each other: one of them modifies the location the
+// other one reads or modifies, or shifts it by inserting or removing an array element.
+fn interferes(left: &PatchOperation, right: &PatchOperation) -> bool {
+    fn affects(op: &PatchOperation, other: &PatchOperation) -> bool {
+        let shifts = !matches!(op, PatchOperation::Replace(_));
+        op.modified_paths().any(|location| {
+            locations(other).any(|pointer| location_affects(location, shifts, pointer))
+        })
+    }
+    fn locations(op: &PatchOperation) -> impl Iterator<Item = &Pointer> {
+        std::iter::once(op.path()).chain(op.from())
+    }
+    affects(left, right) || affects(right, left)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compact, compact_journal};
+    use crate::{patch, Patch};
+    use serde_json::{from_value, json, Value};
+
+    fn journal(value: Value) -> Vec<Patch> {
+        from_value(value).unwrap()
+    }
+
+    fn apply(doc: &Value, patches: &[Patch]) -> Value {
+        let mut doc = doc.clone();
+        for p in patches {
+            patch(&mut doc, p).unwrap();
+        }
+        doc
+    }
+
+    #[test]
+    fn shadowed_ops() {
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "/a", "value": 1 },
+            { "op": "replace", "path": "/a", "value": 2 },
+            { "op": "add", "path": "/list/0", "value": 1 },
+            { "op": "add", "path": "/list/0", "value": 2 },
+            { "op": "replace", "path": "/b/c", "value": 3 },
+            { "op": "test", "path": "/b", "value": { "c": 3 } },
+            { "op": "remove", "path": "/b" },
+            { "op": "replace", "path": "/d/0/e", "value": 4 },
+            { "op": "remove", "path": "/d/0" },
+            { "op": "remove", "path": "/f/g" },
+            { "op": "replace", "path": "/a", "value": 5 },
+            { "op": "add", "path": "/f", "value": {} },
+        ]))
+        .unwrap();
+        assert_eq!(p.shadowed_ops(), [0, 1, 7, 9]);
+        assert!(Patch::default().shadowed_ops().is_empty());
+
+        // Inserting an element shifts the one modified earlier instead of overwriting it
+        let p: Patch = from_value(json!([
+            { "op": "replace", "path": "/list/0/x", "value": 2 },
+            { "op": "add", "path": "/list/0", "value": "new" },
+        ]))
+        .unwrap();
+        assert!(p.shadowed_ops().is_empty());
+    }
+
+    #[test]
+    fn drops_overwritten_operations() {
+        let doc = json!({ "a": { "b": 1 }, "list": [1, 2, 3] });
+        let patches = journal(json!([
+            [{ "op": "replace", "path": "/a/b", "value": 2 }],
+            [{ "op": "add", "path": "/a/c", "value": 3 }],
+            [{ "op": "replace", "path": "/a", "value": { "d": 4 } }],
+            [{ "op": "add", "path": "/x", "value": 1 }, { "op": "remove", "path": "/list/0" }],
+            [{ "op": "replace", "path": "/x", "value": 2 }],
+        ]));
+        let compacted = compact(&patches);
+        assert_eq!(
+            compacted,
+            from_value::<Patch>(json!([
+                { "op": "replace", "path": "/a", "value": { "d": 4 } },
+                { "op": "remove", "path": "/list/0" },
+                { "op": "add", "path": "/x", "value": 2 },
+            ]))
+            .unwrap()
+        );
+        assert_eq!(apply(&doc, &[compacted]), apply(&doc, &patches));
+    }
+
+    #[test]
+    fn keeps_operations_used_later() {
+        let patches = journal(json!([
+            [{ "op": "add", "path": "/list/0", "value": 1 }],
+            [{ "op": "replace", "path": "/list/0", "value": 2 }],
+            [{ "op": "add", "path": "/a", "value": 1 }],
+            [{ "op": "copy", "from": "/a", "path": "/b" }],
+            [{ "op": "replace", "path": "/a", "value": 2 }],
+            [{ "op": "add", "path": "/c", "value": 1 }],
+            [{ "op": "remove", "path": "/c" }],
+            [{ "op": "add", "path": "/list/0", "value": 0 }],
+            [{ "op": "replace", "path": "/list/1", "value": 3 }],
+        ]));
+        let compacted = compact(&patches);
+        // The `add` inserting the list item is needed by the `replace`; the `add` of `/a` is read
+        // by the `copy`; the `add` of `/c` might have overwritten an existing value.
+        assert_eq!(compacted.len(), patches.len());
+
+        let doc = json!({ "list": [], "c": 0 });
+        assert_eq!(apply(&doc, &[compacted]), apply(&doc, &patches));
+    }
+
+    #[test]
+    fn keeps_operations_under_inserted_elements() {
+        let doc = json!({ "list": [{ "x": 1 }] });
+        let patches = journal(json!([
+            [{ "op": "replace", "path": "/list/0/x", "value": 2 }],
+            [{ "op": "add", "path": "/list/0", "value": "new" }],
+        ]));
+        let compacted = compact(&patches);
+        assert_eq!(compacted.len(), 2);
+        assert_eq!(
+            apply(&doc, &[compacted]),
+            json!({ "list": ["new", { "x": 2 }] })
+        );
+    }
+
+    #[test]
+    fn checkpoints() {
+        let doc = json!({});
+        let patches = journal(json!([
+            [{ "op": "add", "path": "/v", "value": 1 }],
+            [{ "op": "replace", "path": "/v", "value": 2 }],
+            [{ "op": "replace", "path": "/v", "value": 3 }],
+            [{ "op": "replace", "path": "/v", "value": 4 }],
+            [{ "op": "replace", "path": "/v", "value": 5 }],
+        ]));
+        let compacted = compact_journal(&patches, &[3, 0, 1, 3, 7]);
+        let versions: Vec<usize> = compacted.iter().map(|(version, _)| *version).collect();
+        assert_eq!(versions, vec![1, 3, 5]);
+
+        let mut current = doc.clone();
+        for (version, p) in &compacted {
+            assert!(p.len() <= 2);
+            current = apply(&current, std::slice::from_ref(p));
+            assert_eq!(current, apply(&doc, &patches[..*version]));
+        }
+
+        assert!(compact_journal(&[], &[1]).is_empty());
+    }
+}
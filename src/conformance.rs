@@ -0,0 +1,137 @@
+//! Conformance test suite of [json-patch-tests](https://github.com/json-patch/json-patch-tests).
+//!
+//! The same test vectors are used to test this crate. They are exposed so that wrappers and
+//! alternative implementations can check RFC 6902 compliance against the same corpus.
+//!
+//! # Example
+//!
+//! ```rust
+//! json_patch::conformance::run_all().unwrap();
+//!
+//! // Test alternative implementation
+//! let result = json_patch::conformance::run_with(|doc, patch| {
+//!     let patch: json_patch::Patch = serde_json::from_value(patch.clone())
+//!         .map_err(|err| err.to_string())?;
+//!     json_patch::patch(doc, &patch).map_err(|err| err.to_string())
+//! });
+//! assert!(result.is_ok());
+//! ```
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt::{self, Display, Formatter};
+
+/// Single test case of the suite.
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct TestCase {
+    /// Description of the test case.
+    pub comment: Option<String>,
+    /// Document to patch.
+    pub doc: Value,
+    /// Patch to apply, not necessarily a valid one.
+    pub patch: Value,
+    /// Expected result, if patch is expected to succeed.
+    pub expected: Option<Value>,
+    /// Description of the expected error, if patch is expected to fail.
+    pub error: Option<String>,
+    /// If set, the test case should be skipped.
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+/// Test case that did not pass.
+#[derive(Clone, Debug)]
+pub struct Failure {
+    /// Failed test case.
+    pub case: TestCase,
+    /// Why the test case failed.
+    pub reason: String,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let comment = self.case.comment.as_deref().unwrap_or("(no comment)");
+        write!(f, "{}: {}", comment, self.reason)
+    }
+}
+
+/// Returns all the test cases of the suite, including disabled ones.
+pub fn cases() -> Vec<TestCase> {
+    [
+        include_str!("../specs/tests.json"),
+        include_str!("../specs/spec_tests.json"),
+    ]
+    .into_iter()
+    .flat_map(|cases| {
+        serde_json::from_str::<Vec<TestCase>>(cases).expect("test cases are valid JSON")
+    })
+    .collect()
+}
+
+/// Runs the suite against [`patch`](crate::patch) of this crate.
+pub fn run_all() -> Result<(), Vec<Failure>> {
+    run_with(|doc, patch| {
+        let patch: crate::Patch =
+            serde_json::from_value(patch.clone()).map_err(|err| err.to_string())?;
+        crate::patch(doc, &patch).map_err(|err| err.to_string())
+    })
+}
+
+/// Runs the suite against the given implementation, which is expected to parse the patch and
+/// apply it to the document in place. On error, the document must be left unchanged.
+pub fn run_with<F>(mut apply: F) -> Result<(), Vec<Failure>>
+where
+    F: FnMut(&mut Value, &Value) -> Result<(), String>,
+{
+    let failures: Vec<Failure> = cases()
+        .into_iter()
+        .filter(|case| !case.disabled)
+        .filter_map(|case| {
+            let reason = check(&case, &mut apply)?;
+            Some(Failure { case, reason })
+        })
+        .collect();
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures)
+    }
+}
+
+fn check<F>(case: &TestCase, apply: &mut F) -> Option<String>
+where
+    F: FnMut(&mut Value, &Value) -> Result<(), String>,
+{
+    let mut actual = case.doc.clone();
+    match (apply(&mut actual, &case.patch), &case.error) {
+        (Ok(()), Some(error)) => Some(format!(
+            "expected to fail with an error: {error}, got document {actual}"
+        )),
+        (Ok(()), None) => {
+            let expected = case.expected.as_ref().unwrap_or(&case.doc);
+            (*expected != actual).then(|| format!("expected {expected}, got {actual}"))
+        }
+        (Err(_), Some(_)) if actual != case.doc => Some(format!(
+            "document was changed by the failed patch to {actual}"
+        )),
+        (Err(_), Some(_)) => None,
+        (Err(err), None) => Some(format!("expected to succeed, but failed with: {err}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn run_all() {
+        if let Err(failures) = super::run_all() {
+            panic!("{}", failures[0]);
+        }
+    }
+
+    #[test]
+    fn detects_failures() {
+        let failures = super::run_with(|_doc, _patch| Ok(())).unwrap_err();
+        assert!(failures.iter().any(|failure| failure.case.error.is_some()));
+        assert!(failures.len() < super::cases().len());
+    }
+}
@@ -0,0 +1,423 @@
+use crate::{
+    AddOperation, CopyOperation, MoveOperation, OpKind, Patch, PatchOperation, RemoveOperation,
+    ReplaceOperation, TestOperation,
+};
+use jsonptr::PointerBuf;
+use serde::de::{Deserialize, Deserializer, Error, SeqAccess, Visitor};
+use serde_json::{Map, Value};
+use std::fmt::{self, Display, Formatter};
+
+const OPERATIONS: &[&str] = &["add", "remove", "replace", "move", "copy", "test"];
+
+/// Why an element of a patch could not be deserialized.
+#[derive(Debug)]
+pub(crate) enum InvalidOperation {
+    NotObject,
+    Missing(&'static str),
+    Invalid(&'static str, String),
+    UnknownOp(Value),
+}
+
+/// Error reported for an element of a patch, with its index.
+pub(crate) struct OperationError {
+    pub(crate) operation: usize,
+    pub(crate) error: InvalidOperation,
+}
+
+impl Display for OperationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let operation = self.operation;
+        match &self.error {
+            InvalidOperation::NotObject => write!(f, "operation '/{operation}' is not an object"),
+            InvalidOperation::Missing(field) => {
+                write!(f, "operation '/{operation}' is missing field '{field}'")
+            }
+            InvalidOperation::Invalid(field, reason) => {
+                write!(
+                    f,
+                    "operation '/{operation}' has invalid field '{field}': {reason}"
+                )
+            }
+            InvalidOperation::UnknownOp(value) => {
+                let op = value["op"].as_str().unwrap_or_default();
+                write!(
+                    f,
+                    "operation '/{operation}' has invalid field 'op': unknown operation '{op}', \
+                     expected one of {}",
+                    OPERATIONS.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// Operation with an unknown `op` value, kept as is by [`TolerantPatch`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnknownOperation {
+    /// Index of the operation in the original patch.
+    pub index: usize,
+    /// Operation as it was given.
+    pub value: Value,
+}
+
+/// JSON Patch parsed in a tolerant mode, where operations with an unknown `op` value (like vendor
+/// extensions) are collected instead of failing the whole patch.
+///
+/// Known operations are parsed as usual and must be valid.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{Patch, TolerantPatch};
+/// use serde_json::{from_value, json};
+///
+/// let p: TolerantPatch = from_value(json!([
+///   { "op": "add", "path": "/a", "value": 1 },
+///   { "op": "x-increment", "path": "/b", "by": 2 },
+/// ])).unwrap();
+///
+/// assert_eq!(p.patch, from_value::<Patch>(json!([
+///   { "op": "add", "path": "/a", "value": 1 },
+/// ])).unwrap());
+/// assert_eq!(p.unknown[0].index, 1);
+/// assert_eq!(p.unknown[0].value["by"], 2);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TolerantPatch {
+    /// Known operations, in order.
+    pub patch: Patch,
+    /// Operations with unknown `op` values, in order.
+    pub unknown: Vec<UnknownOperation>,
+}
+
+impl TolerantPatch {
+    /// Returns `true` if all the operations are known.
+    pub fn is_complete(&self) -> bool {
+        self.unknown.is_empty()
+    }
+}
+
+/// JSON Patch parsed in a compatibility mode, accepting operations in the shapes emitted by
+/// other ecosystems and normalizing them into the standard representation.
+///
+/// On top of the standard form, the following is accepted:
+///
+/// * `op` values and the `op`, `path`, `from` and `value` member names in any casing, like
+///   `{ "Op": "Add", "Path": "/a", "Value": 1 }`;
+/// * externally tagged operations, like `{ "Add": { "path": "/a", "value": 1 } }`;
+/// * adjacently tagged operations, where the members other than `op` are nested in a single
+///   object, like `{ "op": "add", "args": { "path": "/a", "value": 1 } }`.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{CompatPatch, Patch};
+/// use serde_json::{from_value, json};
+///
+/// let CompatPatch(p) = from_value(json!([
+///   { "Op": "Replace", "Path": "/a", "Value": 1 },
+///   { "Remove": { "path": "/b" } },
+///   { "op": "ADD", "content": { "path": "/c", "value": 2 } },
+/// ])).unwrap();
+///
+/// assert_eq!(p, from_value::<Patch>(json!([
+///   { "op": "replace", "path": "/a", "value": 1 },
+///   { "op": "remove", "path": "/b" },
+///   { "op": "add", "path": "/c", "value": 2 },
+/// ])).unwrap());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompatPatch(pub Patch);
+
+impl From<CompatPatch> for Patch {
+    fn from(patch: CompatPatch) -> Patch {
+        patch.0
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Strict,
+    Tolerant,
+    Compat,
+}
+
+struct PatchVisitor {
+    mode: Mode,
+}
+
+impl<'de> Visitor<'de> for PatchVisitor {
+    type Value = TolerantPatch;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence of patch operations")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<TolerantPatch, A::Error> {
+        let mut result = TolerantPatch::default();
+        let mut index = 0;
+        while let Some(mut value) = seq.next_element::<Value>()? {
+            if self.mode == Mode::Compat {
+                value = normalize(value);
+            }
+            match parse_operation(value) {
+                Ok(op) => result.patch.0.push(op),
+                Err(InvalidOperation::UnknownOp(value)) if self.mode == Mode::Tolerant => {
+                    result.unknown.push(UnknownOperation { index, value })
+                }
+                Err(error) => {
+                    return Err(A::Error::custom(OperationError {
+                        operation: index,
+                        error,
+                    }))
+                }
+            }
+            index += 1;
+        }
+        Ok(result)
+    }
+}
+
+impl<'de> Deserialize<'de> for Patch {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let result = deserializer.deserialize_seq(PatchVisitor { mode: Mode::Strict })?;
+        Ok(result.patch)
+    }
+}
+
+impl<'de> Deserialize<'de> for TolerantPatch {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(PatchVisitor {
+            mode: Mode::Tolerant,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for CompatPatch {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let result = deserializer.deserialize_seq(PatchVisitor { mode: Mode::Compat })?;
+        Ok(CompatPatch(result.patch))
+    }
+}
+
+/// Normalizes an operation accepted by [`CompatPatch`] into the standard representation. Values
+/// which are not recognized are returned as is, to be reported by [`parse_operation`].
+fn normalize(value: Value) -> Value {
+    let Value::Object(obj) = value else {
+        return value;
+    };
+    let mut obj = normalize_members(obj);
+
+    // Externally tagged, `{ "Add": { ... } }`
+    if obj.len() == 1 {
+        let (key, value) = obj.iter().next().unwrap();
+        if is_operation(key) && value.is_object() {
+            let op = Value::String(key.to_ascii_lowercase());
+            let Some(Value::Object(inner)) = obj.into_values().next() else {
+                unreachable!()
+            };
+            let mut obj = normalize_members(inner);
+            obj.insert("op".into(), op);
+            return Value::Object(obj);
+        }
+    }
+
+    // Adjacently tagged, `{ "op": "add", "args": { ... } }`
+    if obj.len() == 2 && obj.contains_key("op") && !obj.contains_key("path") {
+        let content = obj.keys().find(|key| *key != "op").cloned().unwrap();
+        if obj[&content].is_object() {
+            let Some(Value::Object(inner)) = obj.remove(&content) else {
+                unreachable!()
+            };
+            let op = obj.remove("op").unwrap();
+            obj = normalize_members(inner);
+            obj.insert("op".into(), op);
+        }
+    }
+
+    if let Some(Value::String(op)) = obj.get_mut("op") {
+        if is_operation(op) {
+            op.make_ascii_lowercase();
+        }
+    }
+    Value::Object(obj)
+}
+
+/// Renames the members of an operation to their standard casing. Members with the standard name
+/// take precedence over differently cased ones.
+fn normalize_members(obj: Map<String, Value>) -> Map<String, Value> {
+    let mut normalized = Map::new();
+    for (key, value) in obj {
+        let lowercase = key.to_ascii_lowercase();
+        match ["op", "path", "from", "value"]
+            .iter()
+            .find(|name| **name == lowercase)
+        {
+            Some(name) if key == *name => {
+                normalized.insert(key, value);
+            }
+            Some(name) => {
+                normalized.entry(*name).or_insert(value);
+            }
+            None => {
+                normalized.insert(key, value);
+            }
+        }
+    }
+    normalized
+}
+
+fn is_operation(op: &str) -> bool {
+    OPERATIONS.iter().any(|name| name.eq_ignore_ascii_case(op))
+}
+
+/// Parses a single operation, reporting the field which is missing or invalid. Members are moved
+/// out of the operation rather than copied.
+pub(crate) fn parse_operation(value: Value) -> Result<PatchOperation, InvalidOperation> {
+    let Value::Object(mut obj) = value else {
+        return Err(InvalidOperation::NotObject);
+    };
+    let op = match obj.get("op") {
+        None => return Err(InvalidOperation::Missing("op")),
+        Some(Value::String(op)) => op.as_str(),
+        Some(_) => return Err(invalid_string("op")),
+    };
+    let op = match op {
+        "add" => OpKind::Add,
+        "remove" => OpKind::Remove,
+        "replace" => OpKind::Replace,
+        "move" => OpKind::Move,
+        "copy" => OpKind::Copy,
+        "test" => OpKind::Test,
+        _ => return Err(InvalidOperation::UnknownOp(Value::Object(obj))),
+    };
+    let obj = &mut obj;
+    Ok(match op {
+        OpKind::Add => PatchOperation::Add(AddOperation {
+            path: pointer(obj, "path")?,
+            value: member(obj, "value")?,
+        }),
+        OpKind::Remove => PatchOperation::Remove(RemoveOperation {
+            path: pointer(obj, "path")?,
+        }),
+        OpKind::Replace => PatchOperation::Replace(ReplaceOperation {
+            path: pointer(obj, "path")?,
+            value: member(obj, "value")?,
+        }),
+        OpKind::Move => PatchOperation::Move(MoveOperation {
+            from: pointer(obj, "from")?,
+            path: pointer(obj, "path")?,
+        }),
+        OpKind::Copy => PatchOperation::Copy(CopyOperation {
+            from: pointer(obj, "from")?,
+            path: pointer(obj, "path")?,
+        }),
+        OpKind::Test => PatchOperation::Test(TestOperation {
+            path: pointer(obj, "path")?,
+            value: member(obj, "value")?,
+        }),
+    })
+}
+
+fn member(obj: &mut Map<String, Value>, field: &'static str) -> Result<Value, InvalidOperation> {
+    obj.remove(field).ok_or(InvalidOperation::Missing(field))
+}
+
+fn invalid_string(field: &'static str) -> InvalidOperation {
+    InvalidOperation::Invalid(field, "expected a string".into())
+}
+
+fn pointer(
+    obj: &mut Map<String, Value>,
+    field: &'static str,
+) -> Result<PointerBuf, InvalidOperation> {
+    let Value::String(ptr) = member(obj, field)? else {
+        return Err(invalid_string(field));
+    };
+    PointerBuf::try_from(ptr).map_err(|err| InvalidOperation::Invalid(field, err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CompatPatch, Patch, TolerantPatch};
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn operation_errors() {
+        let err = |value| from_value::<Patch>(value).unwrap_err().to_string();
+
+        assert_eq!(
+            err(json!([{ "op": "remove", "path": "/a" }, "add"])),
+            "operation '/1' is not an object"
+        );
+        assert_eq!(
+            err(json!([{ "path": "/a" }])),
+            "operation '/0' is missing field 'op'"
+        );
+        assert_eq!(
+            err(json!([{ "op": "append", "path": "/a" }])),
+            "operation '/0' has invalid field 'op': unknown operation 'append', expected one of \
+             add, remove, replace, move, copy, test"
+        );
+        assert_eq!(
+            err(json!([{ "op": "add", "path": "/a" }])),
+            "operation '/0' is missing field 'value'"
+        );
+        assert_eq!(
+            err(json!([{ "op": "copy", "from": 1, "path": "/a" }])),
+            "operation '/0' has invalid field 'from': expected a string"
+        );
+        assert_eq!(
+            err(json!({ "op": "remove", "path": "/a" })),
+            "invalid type: map, expected a sequence of patch operations"
+        );
+    }
+
+    #[test]
+    fn tolerant() {
+        let p: TolerantPatch = from_value(json!([
+            { "op": "x-merge", "path": "/a" },
+            { "op": "remove", "path": "/a" },
+            { "op": "x-merge", "path": "/b" },
+        ]))
+        .unwrap();
+        assert!(!p.is_complete());
+        assert_eq!(p.patch.len(), 1);
+        let unknown: Vec<_> = p.unknown.iter().map(|op| op.index).collect();
+        assert_eq!(unknown, [0, 2]);
+
+        // Known operations must still be valid
+        let err = from_value::<TolerantPatch>(json!([
+            { "op": "x-merge", "path": "/a" },
+            { "op": "add", "path": "/a" },
+        ]))
+        .unwrap_err();
+        assert_eq!(err.to_string(), "operation '/1' is missing field 'value'");
+    }
+
+    #[test]
+    fn compat() {
+        let CompatPatch(p) = from_value(json!([
+            { "OP": "move", "FROM": "/a", "path": "/b" },
+            { "Copy": { "From": "/b", "Path": "/c" } },
+            { "op": "Test", "c": { "path": "/c", "value": { "Path": 1 } } },
+            { "op": "add", "Path": "/x", "path": "/d", "value": null },
+        ]))
+        .unwrap();
+        assert_eq!(
+            p,
+            from_value::<Patch>(json!([
+                { "op": "move", "from": "/a", "path": "/b" },
+                { "op": "copy", "from": "/b", "path": "/c" },
+                { "op": "test", "path": "/c", "value": { "Path": 1 } },
+                { "op": "add", "path": "/d", "value": null },
+            ]))
+            .unwrap()
+        );
+
+        let err = from_value::<CompatPatch>(json!([{ "Op": "Add", "Path": "/a" }])).unwrap_err();
+        assert_eq!(err.to_string(), "operation '/0' is missing field 'value'");
+        let err = from_value::<CompatPatch>(json!([{ "Append": { "path": "/a" } }])).unwrap_err();
+        assert_eq!(err.to_string(), "operation '/0' is missing field 'op'");
+    }
+}
@@ -0,0 +1,71 @@
+//! Storing patches in PostgreSQL JSON columns with [Diesel](https://diesel.rs).
+//!
+//! [`Patch`] and [`MergePatch`] can be used as values of `Json` and `Jsonb` columns directly,
+//! without converting them to `serde_json::Value` first.
+use crate::{MergePatch, Patch};
+use diesel::{
+    deserialize::{self, FromSql},
+    pg::{Pg, PgValue},
+    serialize::{self, IsNull, Output, ToSql},
+    sql_types::{Json, Jsonb},
+};
+use std::io::Write;
+
+macro_rules! impl_diesel {
+    ($name:ident) => {
+        impl FromSql<Json, Pg> for $name {
+            fn from_sql(value: PgValue<'_>) -> deserialize::Result<Self> {
+                let value = <serde_json::Value as FromSql<Json, Pg>>::from_sql(value)?;
+                Ok(serde_json::from_value(value)?)
+            }
+        }
+
+        impl ToSql<Json, Pg> for $name {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                serde_json::to_writer(out, self)?;
+                Ok(IsNull::No)
+            }
+        }
+
+        impl FromSql<Jsonb, Pg> for $name {
+            fn from_sql(value: PgValue<'_>) -> deserialize::Result<Self> {
+                let value = <serde_json::Value as FromSql<Jsonb, Pg>>::from_sql(value)?;
+                Ok(serde_json::from_value(value)?)
+            }
+        }
+
+        impl ToSql<Jsonb, Pg> for $name {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                // `jsonb` binary format version
+                out.write_all(&[1])?;
+                serde_json::to_writer(out, self)?;
+                Ok(IsNull::No)
+            }
+        }
+    };
+}
+
+impl_diesel!(Patch);
+impl_diesel!(MergePatch);
+
+#[cfg(test)]
+mod tests {
+    use crate::{MergePatch, Patch};
+    use diesel::{debug_query, dsl::select, pg::Pg, sql_types::Jsonb, IntoSql};
+    use serde_json::json;
+
+    #[test]
+    fn bind_patch() {
+        let query = select(Patch::default().into_sql::<Jsonb>());
+        assert_eq!(
+            debug_query::<Pg, _>(&query).to_string(),
+            "SELECT $1 -- binds: [Patch([])]"
+        );
+
+        let query = select(MergePatch(json!({})).into_sql::<Jsonb>());
+        assert_eq!(
+            debug_query::<Pg, _>(&query).to_string(),
+            "SELECT $1 -- binds: [MergePatch(Object {})]"
+        );
+    }
+}
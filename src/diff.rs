@@ -1,18 +1,82 @@
-use crate::Patch;
+use crate::{NumberEquality, Patch};
 use jsonptr::PointerBuf;
 use serde_json::{Map, Value};
 
-fn diff_impl(left: &Value, right: &Value, pointer: &mut PointerBuf, patch: &mut super::Patch) {
+/// Options controlling [`diff_with`].
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct DiffOptions {
+    /// Algorithm used to compare arrays.
+    pub array_diff: ArrayDiff,
+    /// How numbers are compared. Numbers considered equal are not replaced.
+    pub number_equality: NumberEquality,
+    /// How a value is changed to a value of a different type, like an object to an array.
+    pub type_change: TypeChange,
+}
+
+/// Operations used when the type of a value changes, see [`DiffOptions::type_change`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum TypeChange {
+    /// Replace the whole value with a single `replace` operation.
+    #[default]
+    Replace,
+    /// Remove the old value, then add the new one at the same location, for consumers which do
+    /// not allow `replace` to change the type of a value. The whole document is still replaced,
+    /// since it cannot be removed.
+    RemoveThenAdd,
+}
+
+/// Algorithm used to compare arrays, see [`DiffOptions::array_diff`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ArrayDiff {
+    /// Compare elements at the same index, then add or remove the tail. Fast, but inserting or
+    /// removing a single element in the middle of an array changes every following element.
+    #[default]
+    Positional,
+    /// Find the longest common subsequence of elements using the linear space variant of the
+    /// Myers algorithm and only add or remove the elements that differ. Memory use is
+    /// proportional to the total length of both arrays; time grows with the number of
+    /// differences between them.
+    Myers,
+}
+
+pub(crate) fn diff_impl(
+    left: &Value,
+    right: &Value,
+    pointer: &mut PointerBuf,
+    patch: &mut super::Patch,
+    options: &DiffOptions,
+) {
     match (left, right) {
         (Value::Object(ref left_obj), Value::Object(ref right_obj)) => {
-            diff_object(left_obj, right_obj, pointer, patch);
+            diff_object(left_obj, right_obj, pointer, patch, options);
         }
-        (Value::Array(ref left_array), Value::Array(ref ref_array)) => {
-            diff_array(left_array, ref_array, pointer, patch);
-        }
-        (_, _) if left == right => {
+        (Value::Array(ref left_array), Value::Array(ref ref_array)) => match options.array_diff {
+            ArrayDiff::Positional => diff_array(left_array, ref_array, pointer, patch, options),
+            ArrayDiff::Myers => diff_array_myers(left_array, ref_array, pointer, patch, options),
+        },
+        (_, _) if options.number_equality.values_equal(left, right) => {
             // Nothing to do
         }
+        (_, _)
+            if options.type_change == TypeChange::RemoveThenAdd
+                && !pointer.is_root()
+                && std::mem::discriminant(left) != std::mem::discriminant(right) =>
+        {
+            patch
+                .0
+                .push(super::PatchOperation::Remove(super::RemoveOperation {
+                    path: pointer.clone(),
+                }));
+            patch
+                .0
+                .push(super::PatchOperation::Add(super::AddOperation {
+                    path: pointer.clone(),
+                    value: right.clone(),
+                }));
+        }
         (_, _) => {
             // Values are different, replace the value at the path
             patch
@@ -25,7 +89,13 @@ fn diff_impl(left: &Value, right: &Value, pointer: &mut PointerBuf, patch: &mut
     }
 }
 
-fn diff_array(left: &[Value], right: &[Value], pointer: &mut PointerBuf, patch: &mut Patch) {
+fn diff_array(
+    left: &[Value],
+    right: &[Value],
+    pointer: &mut PointerBuf,
+    patch: &mut Patch,
+    options: &DiffOptions,
+) {
     let len = left.len().max(right.len());
     let mut shift = 0usize;
     for idx in 0..len {
@@ -33,7 +103,7 @@ fn diff_array(left: &[Value], right: &[Value], pointer: &mut PointerBuf, patch:
         match (left.get(idx), right.get(idx)) {
             (Some(left), Some(right)) => {
                 // Both array have an element at this index
-                diff_impl(left, right, pointer, patch);
+                diff_impl(left, right, pointer, patch, options);
             }
             (Some(_left), None) => {
                 // The left array has an element at this index, but not the right
@@ -66,13 +136,14 @@ fn diff_object(
     right: &Map<String, Value>,
     pointer: &mut PointerBuf,
     patch: &mut Patch,
+    options: &DiffOptions,
 ) {
     // Add or replace keys in the right object
     for (key, right_value) in right {
         pointer.push_back(key);
         match left.get(key) {
             Some(left_value) => {
-                diff_impl(left_value, right_value, pointer, patch);
+                diff_impl(left_value, right_value, pointer, patch, options);
             }
             None => {
                 patch
@@ -100,8 +171,180 @@ fn diff_object(
     }
 }
 
+fn diff_array_myers(
+    left: &[Value],
+    right: &[Value],
+    pointer: &mut PointerBuf,
+    patch: &mut Patch,
+    options: &DiffOptions,
+) {
+    let mut matches = Vec::new();
+    common_subsequence(left, right, 0, 0, options.number_equality, &mut matches);
+    matches.push((left.len(), right.len()));
+
+    // Index in the array being patched
+    let mut idx = 0;
+    let (mut left_idx, mut right_idx) = (0, 0);
+    for (left_end, right_end) in matches {
+        let removed = &left[left_idx..left_end];
+        let added = &right[right_idx..right_end];
+        let common = removed.len().min(added.len());
+
+        // Elements replaced in the same position are compared recursively
+        for (left, right) in removed.iter().zip(added) {
+            pointer.push_back(idx);
+            diff_impl(left, right, pointer, patch, options);
+            pointer.pop_back();
+            idx += 1;
+        }
+        for _ in common..removed.len() {
+            pointer.push_back(idx);
+            patch
+                .0
+                .push(super::PatchOperation::Remove(super::RemoveOperation {
+                    path: pointer.clone(),
+                }));
+            pointer.pop_back();
+        }
+        for value in &added[common..] {
+            pointer.push_back(idx);
+            patch
+                .0
+                .push(super::PatchOperation::Add(super::AddOperation {
+                    path: pointer.clone(),
+                    value: value.clone(),
+                }));
+            pointer.pop_back();
+            idx += 1;
+        }
+
+        // Skip the matching element
+        idx += 1;
+        left_idx = left_end + 1;
+        right_idx = right_end + 1;
+    }
+}
+
+// Collects indices of matching elements of the longest common subsequence, in order.
+fn common_subsequence(
+    left: &[Value],
+    right: &[Value],
+    left_offset: usize,
+    right_offset: usize,
+    eq: NumberEquality,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    let prefix = left
+        .iter()
+        .zip(right)
+        .take_while(|(left, right)| eq.values_equal(left, right))
+        .count();
+    matches.extend((0..prefix).map(|idx| (left_offset + idx, right_offset + idx)));
+    let (left, right) = (&left[prefix..], &right[prefix..]);
+    let (left_offset, right_offset) = (left_offset + prefix, right_offset + prefix);
+
+    let suffix = left
+        .iter()
+        .rev()
+        .zip(right.iter().rev())
+        .take_while(|(left, right)| eq.values_equal(left, right))
+        .count();
+    let (left, right) = (&left[..left.len() - suffix], &right[..right.len() - suffix]);
+
+    if !left.is_empty() && !right.is_empty() {
+        if let Some((x, y)) = middle_snake(left, right, eq) {
+            common_subsequence(
+                &left[..x],
+                &right[..y],
+                left_offset,
+                right_offset,
+                eq,
+                matches,
+            );
+            common_subsequence(
+                &left[x..],
+                &right[y..],
+                left_offset + x,
+                right_offset + y,
+                eq,
+                matches,
+            );
+        }
+    }
+
+    let (left_end, right_end) = (left_offset + left.len(), right_offset + right.len());
+    matches.extend((0..suffix).map(|idx| (left_end + idx, right_end + idx)));
+}
+
+// Finds a point on the shortest edit path splitting it into two halves, as described in
+// "An O(ND) Difference Algorithm and Its Variations" by Eugene W. Myers. Both arrays are non-empty
+// and differ in the first and in the last element.
+fn middle_snake(left: &[Value], right: &[Value], eq: NumberEquality) -> Option<(usize, usize)> {
+    let (n, m) = (left.len() as isize, right.len() as isize);
+    let delta = n - m;
+    let odd = delta & 1 == 1;
+    let max = (n + m + 1) / 2 + 1;
+    // Furthest reaching x on each diagonal k, stored at `k + offset`
+    let offset = max + 1;
+    let mut forward = vec![0isize; 2 * offset as usize + 1];
+    let mut backward = vec![0isize; 2 * offset as usize + 1];
+    let at = |k: isize| (k + offset) as usize;
+
+    for d in 0..max {
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let start = (x, y);
+            while x < n && y < m && eq.values_equal(&left[x as usize], &right[y as usize]) {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x;
+            if odd && (k - delta).abs() < d && x + backward[at(delta - k)] >= n {
+                return split_point(start, n, m);
+            }
+        }
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n
+                && y < m
+                && eq.values_equal(&left[(n - x - 1) as usize], &right[(m - y - 1) as usize])
+            {
+                x += 1;
+                y += 1;
+            }
+            backward[at(k)] = x;
+            if !odd && (k - delta).abs() <= d && x + forward[at(delta - k)] >= n {
+                return split_point((n - x, m - y), n, m);
+            }
+        }
+    }
+    None
+}
+
+// Split point that does not make progress means there is nothing in common
+fn split_point((x, y): (isize, isize), n: isize, m: isize) -> Option<(usize, usize)> {
+    if (x, y) == (0, 0) || (x, y) == (n, m) {
+        None
+    } else {
+        Some((x as usize, y as usize))
+    }
+}
+
 /// Diff two JSON documents and generate a JSON Patch (RFC 6902).
 ///
+/// Strings are compared as a whole: a changed string, like a base64 encoded attachment, is
+/// always replaced with a single `replace` operation carrying the new value.
+///
 /// # Example
 /// Diff two JSONs:
 ///
@@ -146,9 +389,32 @@ fn diff_object(
 /// # }
 /// ```
 pub fn diff(left: &Value, right: &Value) -> super::Patch {
+    diff_with(left, right, &DiffOptions::default())
+}
+
+/// Diff two JSON documents and generate a JSON Patch (RFC 6902), same as [`diff`], but using the
+/// given options.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{diff_with, ArrayDiff, DiffOptions, Patch};
+/// use serde_json::{from_value, json};
+///
+/// let left = json!({ "tags": ["a", "b", "c"] });
+/// let right = json!({ "tags": ["a", "x", "b", "c"] });
+///
+/// let mut options = DiffOptions::default();
+/// options.array_diff = ArrayDiff::Myers;
+/// let p = diff_with(&left, &right, &options);
+/// assert_eq!(p, from_value::<Patch>(json!([
+///   { "op": "add", "path": "/tags/1", "value": "x" },
+/// ])).unwrap());
+/// ```
+pub fn diff_with(left: &Value, right: &Value, options: &DiffOptions) -> super::Patch {
     let mut patch = super::Patch::default();
     let mut path = PointerBuf::new();
-    diff_impl(left, right, &mut path, &mut patch);
+    diff_impl(left, right, &mut path, &mut patch, options);
     patch
 }
 
@@ -170,6 +436,20 @@ mod tests {
         crate::patch(&mut left, &patch).unwrap();
     }
 
+    #[test]
+    pub fn replace_string() {
+        let left = json!({ "attachment": "SGVsbG8sIHdvcmxkIQ==" });
+        let right = json!({ "attachment": "SGVsbG8sIHdvcmxkPw==" });
+        let patch = super::diff(&left, &right);
+        assert_eq!(
+            patch,
+            serde_json::from_value(json!([
+                { "op": "replace", "path": "/attachment", "value": "SGVsbG8sIHdvcmxkPw==" },
+            ]))
+            .unwrap()
+        );
+    }
+
     #[test]
     pub fn diff_empty_key() {
         let mut left = json!({"title": "Something", "": "Hello!"});
@@ -322,6 +602,54 @@ mod tests {
         assert_eq!(left, right);
     }
 
+    #[test]
+    pub fn type_change_remove_then_add() {
+        use crate::{DiffOptions, TypeChange};
+
+        let left = json!({ "list": [[1], { "a": 1 }, "x", 1, null, { "a": 1 }] });
+        let right = json!({ "list": [{ "a": 1 }, [1], 1, "x", false, { "a": 2 }] });
+        let options = DiffOptions {
+            type_change: TypeChange::RemoveThenAdd,
+            ..DiffOptions::default()
+        };
+        let patch = crate::diff_with(&left, &right, &options);
+        assert_eq!(
+            patch,
+            serde_json::from_value(json!([
+                { "op": "remove", "path": "/list/0" },
+                { "op": "add", "path": "/list/0", "value": { "a": 1 } },
+                { "op": "remove", "path": "/list/1" },
+                { "op": "add", "path": "/list/1", "value": [1] },
+                { "op": "remove", "path": "/list/2" },
+                { "op": "add", "path": "/list/2", "value": 1 },
+                { "op": "remove", "path": "/list/3" },
+                { "op": "add", "path": "/list/3", "value": "x" },
+                { "op": "remove", "path": "/list/4" },
+                { "op": "add", "path": "/list/4", "value": false },
+                { "op": "replace", "path": "/list/5/a", "value": 2 },
+            ]))
+            .unwrap()
+        );
+
+        // Both representations apply cleanly, including the whole document changing type
+        for type_change in [TypeChange::Replace, TypeChange::RemoveThenAdd] {
+            let options = DiffOptions {
+                type_change,
+                ..DiffOptions::default()
+            };
+            for (from, to) in [
+                (&left, &right),
+                (&right, &left),
+                (&left, &json!({ "list": { "a": 1 } })),
+                (&left, &json!([1])),
+            ] {
+                let mut doc = from.clone();
+                crate::patch(&mut doc, &crate::diff_with(from, to, &options)).unwrap();
+                assert_eq!(&doc, to);
+            }
+        }
+    }
+
     #[test]
     pub fn remove_keys() {
         let mut left = json!({"first": 1, "second": 2, "third": 3});
@@ -355,4 +683,74 @@ mod tests {
         crate::patch(&mut left, &patch).unwrap();
         assert_eq!(left, right);
     }
+
+    #[test]
+    pub fn myers_array() {
+        let mut left = json!({ "a": [1, { "b": 1 }, 4, 5, 6] });
+        let right = json!({ "a": [0, 1, { "b": 2 }, 4, 6, 7] });
+        let options = super::DiffOptions {
+            array_diff: super::ArrayDiff::Myers,
+            ..Default::default()
+        };
+        let patch = super::diff_with(&left, &right, &options);
+        assert_eq!(
+            patch,
+            serde_json::from_value(json!([
+                { "op": "add", "path": "/a/0", "value": 0 },
+                { "op": "replace", "path": "/a/2/b", "value": 2 },
+                { "op": "remove", "path": "/a/4" },
+                { "op": "add", "path": "/a/5", "value": 7 },
+            ]))
+            .unwrap()
+        );
+
+        crate::patch(&mut left, &patch).unwrap();
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    pub fn myers_common_subsequence() {
+        fn lcs_len(left: &[Value], right: &[Value]) -> usize {
+            let mut table = vec![vec![0; right.len() + 1]; left.len() + 1];
+            for i in 0..left.len() {
+                for j in 0..right.len() {
+                    table[i + 1][j + 1] = if left[i] == right[j] {
+                        table[i][j] + 1
+                    } else {
+                        table[i][j + 1].max(table[i + 1][j])
+                    };
+                }
+            }
+            table[left.len()][right.len()]
+        }
+
+        let mut seed = 42u64;
+        let mut next = |bound: u64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (seed >> 33) % bound
+        };
+        for _ in 0..500 {
+            let left: Vec<Value> = (0..next(12)).map(|_| json!(next(4))).collect();
+            let right: Vec<Value> = (0..next(12)).map(|_| json!(next(4))).collect();
+
+            let mut matches = Vec::new();
+            super::common_subsequence(&left, &right, 0, 0, Default::default(), &mut matches);
+            assert_eq!(matches.len(), lcs_len(&left, &right), "{left:?} {right:?}");
+            for window in matches.windows(2) {
+                assert!(window[0].0 < window[1].0 && window[0].1 < window[1].1);
+            }
+            for &(i, j) in &matches {
+                assert_eq!(left[i], right[j]);
+            }
+
+            let options = super::DiffOptions {
+                array_diff: super::ArrayDiff::Myers,
+                ..Default::default()
+            };
+            let (mut doc, right) = (Value::Array(left), Value::Array(right));
+            let patch = super::diff_with(&doc, &right, &options);
+            crate::patch(&mut doc, &patch).unwrap();
+            assert_eq!(doc, right);
+        }
+    }
 }
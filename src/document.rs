@@ -0,0 +1,194 @@
+//! Application of JSON Patch to documents in formats other than JSON.
+use crate::{
+    strip_pointer_prefix, translate_error, validate_operation, PatchError, PatchErrorKind,
+    PatchOperation,
+};
+use jsonptr::{Pointer, Token};
+use serde_json::Value;
+#[cfg(feature = "diff")]
+use {crate::Patch, jsonptr::PointerBuf};
+
+/// JSON-like document which can be navigated with JSON Pointers.
+pub(crate) trait Document: Clone + PartialEq {
+    /// Converts a value carried by a patch operation.
+    fn from_json(value: &Value) -> Result<Self, PatchErrorKind>;
+
+    /// Returns a member of an object or an element of an array.
+    fn child(&self, token: &Token) -> Option<&Self>;
+
+    /// Returns a mutable member of an object or an element of an array.
+    fn child_mut(&mut self, token: &Token) -> Option<&mut Self>;
+
+    /// Inserts a member into an object (replacing the existing one) or an element into an array,
+    /// as done by the `add` operation.
+    fn insert(&mut self, token: &Token, value: Self) -> Result<(), PatchErrorKind>;
+
+    /// Removes a member of an object or an element of an array.
+    fn remove(&mut self, token: &Token) -> Option<Self>;
+
+    /// Error returned when a value cannot be represented as JSON.
+    #[cfg(feature = "diff")]
+    type JsonError;
+
+    /// Converts a value to be carried by a patch operation.
+    #[cfg(feature = "diff")]
+    fn to_json(&self) -> Result<Value, Self::JsonError>;
+
+    /// Returns the structure of the value to walk when diffing.
+    #[cfg(feature = "diff")]
+    fn node(&self) -> Node<'_, Self>;
+}
+
+/// Structure of a document value, as seen by [`diff`].
+#[cfg(feature = "diff")]
+pub(crate) enum Node<'a, D> {
+    /// Object with all the member names representable in JSON Pointers.
+    Object(Vec<(String, &'a D)>),
+    /// Array.
+    Array(&'a [D]),
+    /// Tagged value, diffed as the inner value if both sides have the same tag.
+    #[cfg_attr(not(any(feature = "cbor", feature = "yaml")), allow(dead_code))]
+    Tagged(String, &'a D),
+    /// Any other value, replaced as a whole when it changes.
+    Other,
+}
+
+/// Patch the document in place. Operations are applied to a copy of the document, so it is left
+/// unchanged if any of them fails.
+pub(crate) fn patch<D: Document>(doc: &mut D, patch: &[PatchOperation]) -> Result<(), PatchError> {
+    let mut patched = doc.clone();
+    for (operation, op) in patch.iter().enumerate() {
+        apply(&mut patched, op).map_err(|kind| translate_error(kind, operation, op))?;
+    }
+    *doc = patched;
+    Ok(())
+}
+
+/// Diff two documents and generate a JSON Patch, same as [`crate::diff`].
+#[cfg(feature = "diff")]
+pub(crate) fn diff<D: Document>(left: &D, right: &D) -> Result<Patch, D::JsonError> {
+    let mut patch = Patch::default();
+    diff_impl(left, right, &mut PointerBuf::new(), &mut patch)?;
+    Ok(patch)
+}
+
+#[cfg(feature = "diff")]
+fn diff_impl<D: Document>(
+    left: &D,
+    right: &D,
+    path: &mut PointerBuf,
+    patch: &mut Patch,
+) -> Result<(), D::JsonError> {
+    match (left.node(), right.node()) {
+        (Node::Tagged(left_tag, left), Node::Tagged(right_tag, right)) if left_tag == right_tag => {
+            diff_impl(left, right, path, patch)?;
+        }
+        (Node::Object(members), Node::Object(right_members)) => {
+            let mut removed = Vec::new();
+            for (key, left) in members {
+                path.push_back(key.as_str());
+                match right.child(&Token::new(key)) {
+                    Some(right) => diff_impl(left, right, path, patch)?,
+                    None => removed.push(PatchOperation::remove(path.clone())),
+                }
+                path.pop_back();
+            }
+            patch.0.extend(removed);
+            for (key, right) in right_members {
+                if left.child(&Token::new(key.as_str())).is_none() {
+                    path.push_back(key);
+                    patch
+                        .0
+                        .push(PatchOperation::add(path.clone(), right.to_json()?));
+                    path.pop_back();
+                }
+            }
+        }
+        (Node::Array(left), Node::Array(right)) => {
+            for (idx, (left, right)) in left.iter().zip(right).enumerate() {
+                path.push_back(idx);
+                diff_impl(left, right, path, patch)?;
+                path.pop_back();
+            }
+            for idx in (right.len()..left.len()).rev() {
+                path.push_back(idx);
+                patch.0.push(PatchOperation::remove(path.clone()));
+                path.pop_back();
+            }
+            for value in right.iter().skip(left.len()) {
+                path.push_back("-");
+                patch
+                    .0
+                    .push(PatchOperation::add(path.clone(), value.to_json()?));
+                path.pop_back();
+            }
+        }
+        _ if left == right => {}
+        _ => patch
+            .0
+            .push(PatchOperation::replace(path.clone(), right.to_json()?)),
+    }
+    Ok(())
+}
+
+fn apply<D: Document>(doc: &mut D, op: &PatchOperation) -> Result<(), PatchErrorKind> {
+    if let Some(kind) = validate_operation(op) {
+        return Err(kind);
+    }
+    match op {
+        PatchOperation::Add(op) => add(doc, &op.path, D::from_json(&op.value)?),
+        PatchOperation::Remove(op) => remove(doc, &op.path).map(drop),
+        PatchOperation::Replace(op) => {
+            let value = D::from_json(&op.value)?;
+            *resolve_mut(doc, &op.path).ok_or(PatchErrorKind::InvalidPointer)? = value;
+            Ok(())
+        }
+        PatchOperation::Move(op) => {
+            if strip_pointer_prefix(&op.path, &op.from).is_some_and(|rest| !rest.is_root()) {
+                return Err(PatchErrorKind::CannotMoveInsideItself);
+            }
+            let value = remove(doc, &op.from).map_err(|_| PatchErrorKind::InvalidFromPointer)?;
+            add(doc, &op.path, value)
+        }
+        PatchOperation::Copy(op) => {
+            let value = resolve(doc, &op.from)
+                .ok_or(PatchErrorKind::InvalidFromPointer)?
+                .clone();
+            add(doc, &op.path, value)
+        }
+        PatchOperation::Test(op) => {
+            let expected = D::from_json(&op.value)?;
+            match resolve(doc, &op.path) {
+                Some(target) if *target == expected => Ok(()),
+                Some(_) => Err(PatchErrorKind::TestFailed),
+                None => Err(PatchErrorKind::InvalidPointer),
+            }
+        }
+    }
+}
+
+fn resolve<'a, D: Document>(doc: &'a D, path: &Pointer) -> Option<&'a D> {
+    path.tokens().try_fold(doc, |doc, token| doc.child(&token))
+}
+
+fn resolve_mut<'a, D: Document>(doc: &'a mut D, path: &Pointer) -> Option<&'a mut D> {
+    path.tokens()
+        .try_fold(doc, |doc, token| doc.child_mut(&token))
+}
+
+fn add<D: Document>(doc: &mut D, path: &Pointer, value: D) -> Result<(), PatchErrorKind> {
+    let Some((parent, last)) = path.split_back() else {
+        *doc = value;
+        return Ok(());
+    };
+    resolve_mut(doc, parent)
+        .ok_or(PatchErrorKind::InvalidPointer)?
+        .insert(&last, value)
+}
+
+fn remove<D: Document>(doc: &mut D, path: &Pointer) -> Result<D, PatchErrorKind> {
+    let (parent, last) = path.split_back().ok_or(PatchErrorKind::InvalidPointer)?;
+    resolve_mut(doc, parent)
+        .and_then(|parent| parent.remove(&last))
+        .ok_or(PatchErrorKind::InvalidPointer)
+}
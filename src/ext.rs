@@ -0,0 +1,506 @@
+//! Extensions to RFC 6902 operations.
+//!
+//! [`ExtOperation`] is either a regular patch operation or one of the extension operations,
+//! serialized with its own `op` name:
+//!
+//! * `group` ([`GroupOperation`]) applies nested operations as a unit. If the group is
+//!   `optional`, its failure is ignored and the rest of the patch continues with all changes
+//!   made by the group reverted.
+//! * `test-exists` and `test-not-exists` ([`ExistsOperation`]) check that there is (or there is
+//!   not) a value at `path`, regardless of the value. They fail with
+//!   [`PatchErrorKind::TestFailed`](crate::PatchErrorKind::TestFailed).
+//! * `remove` with a `value` member ([`TestRemoveOperation`]) removes the value at `path` only
+//!   if it is equal to the given one, and fails with
+//!   [`PatchErrorKind::TestFailed`](crate::PatchErrorKind::TestFailed) otherwise. Note that
+//!   [`Patch`](crate::Patch) ignores the `value` member of `remove` operations.
+//! * `string-append` and `string-prepend` ([`StringOperation`]) add the given string to the end
+//!   (or the beginning) of the string at `path`. They fail with
+//!   [`PatchErrorKind::UnexpectedType`](crate::PatchErrorKind::UnexpectedType) if the value is
+//!   not a string.
+//! * `add-unique` and `remove-value` ([`SetOperation`]) treat the array at `path` as a set:
+//!   `add-unique` appends the value unless the array already contains an equal one, and
+//!   `remove-value` removes all elements equal to the value, if any. They fail with
+//!   [`PatchErrorKind::UnexpectedType`](crate::PatchErrorKind::UnexpectedType) if the value is
+//!   not an array.
+//!
+//! Extended patches are applied with [`patch`]. Like RFC 6902 patches, they are applied
+//! atomically.
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::ext::{self, ExtOperation};
+//! use serde_json::{from_value, json};
+//!
+//! let mut doc = json!({ "version": 1 });
+//! let ops: Vec<ExtOperation> = from_value(json!([
+//!   { "op": "replace", "path": "/version", "value": 2 },
+//!   { "op": "group", "optional": true, "ops": [
+//!     { "op": "add", "path": "/settings", "value": {} },
+//!     { "op": "move", "from": "/legacy", "path": "/settings/legacy" },
+//!   ] },
+//! ])).unwrap();
+//!
+//! ext::patch(&mut doc, &ops).unwrap();
+//! assert_eq!(doc, json!({ "version": 2 }));
+//! ```
+use crate::{
+    apply_patches, operation_error, undo_patches, OpKind, PatchError, PatchErrorKind,
+    PatchOperation, PatchOptions, UndoStack,
+};
+use jsonptr::PointerBuf;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// Regular patch operation or an extension operation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExtOperation {
+    /// RFC 6902 operation.
+    Standard(PatchOperation),
+    /// `group` operation.
+    Group(GroupOperation),
+    /// `test-exists` operation.
+    TestExists(ExistsOperation),
+    /// `test-not-exists` operation.
+    TestNotExists(ExistsOperation),
+    /// `remove` operation with a `value`.
+    TestRemove(TestRemoveOperation),
+    /// `string-append` operation.
+    StringAppend(StringOperation),
+    /// `string-prepend` operation.
+    StringPrepend(StringOperation),
+    /// `add-unique` operation.
+    AddUnique(SetOperation),
+    /// `remove-value` operation.
+    RemoveValue(SetOperation),
+}
+
+/// Extension operation applying nested operations as a unit.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct GroupOperation {
+    /// If set, failure of the group is ignored: all changes made by the group are reverted and
+    /// the rest of the patch is applied.
+    #[serde(default)]
+    pub optional: bool,
+    /// Nested operations.
+    pub ops: Vec<ExtOperation>,
+}
+
+/// Extension operation checking whether there is a value at the given location.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ExistsOperation {
+    /// Location to check.
+    pub path: PointerBuf,
+}
+
+/// Extension of the `remove` operation, removing the value only if it is equal to the given one.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct TestRemoveOperation {
+    /// Location of the value to remove.
+    pub path: PointerBuf,
+    /// Expected value at `path`.
+    pub value: Value,
+}
+
+/// Extension operation adding a string to the string value at the given location.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct StringOperation {
+    /// Location of the string to modify.
+    pub path: PointerBuf,
+    /// String to add.
+    pub value: String,
+}
+
+/// Extension operation adding a value to (or removing it from) the array at the given location,
+/// treated as a set.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct SetOperation {
+    /// Location of the array to modify.
+    pub path: PointerBuf,
+    /// Value to add or remove.
+    pub value: Value,
+}
+
+impl From<PatchOperation> for ExtOperation {
+    fn from(op: PatchOperation) -> Self {
+        ExtOperation::Standard(op)
+    }
+}
+
+impl From<GroupOperation> for ExtOperation {
+    fn from(op: GroupOperation) -> Self {
+        ExtOperation::Group(op)
+    }
+}
+
+impl Serialize for ExtOperation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Tagged<'a, T> {
+            op: &'static str,
+            #[serde(flatten)]
+            operation: &'a T,
+        }
+
+        match self {
+            ExtOperation::Standard(op) => op.serialize(serializer),
+            ExtOperation::Group(operation) => Tagged {
+                op: "group",
+                operation,
+            }
+            .serialize(serializer),
+            ExtOperation::TestExists(operation) => Tagged {
+                op: "test-exists",
+                operation,
+            }
+            .serialize(serializer),
+            ExtOperation::TestNotExists(operation) => Tagged {
+                op: "test-not-exists",
+                operation,
+            }
+            .serialize(serializer),
+            ExtOperation::TestRemove(operation) => Tagged {
+                op: "remove",
+                operation,
+            }
+            .serialize(serializer),
+            ExtOperation::StringAppend(operation) => Tagged {
+                op: "string-append",
+                operation,
+            }
+            .serialize(serializer),
+            ExtOperation::StringPrepend(operation) => Tagged {
+                op: "string-prepend",
+                operation,
+            }
+            .serialize(serializer),
+            ExtOperation::AddUnique(operation) => Tagged {
+                op: "add-unique",
+                operation,
+            }
+            .serialize(serializer),
+            ExtOperation::RemoveValue(operation) => Tagged {
+                op: "remove-value",
+                operation,
+            }
+            .serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtOperation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let result = match value.get("op").and_then(Value::as_str) {
+            Some("group") => serde_json::from_value(value).map(ExtOperation::Group),
+            Some("test-exists") => serde_json::from_value(value).map(ExtOperation::TestExists),
+            Some("test-not-exists") => {
+                serde_json::from_value(value).map(ExtOperation::TestNotExists)
+            }
+            Some("string-append") => serde_json::from_value(value).map(ExtOperation::StringAppend),
+            Some("string-prepend") => {
+                serde_json::from_value(value).map(ExtOperation::StringPrepend)
+            }
+            Some("add-unique") => serde_json::from_value(value).map(ExtOperation::AddUnique),
+            Some("remove-value") => serde_json::from_value(value).map(ExtOperation::RemoveValue),
+            Some("remove") if value.get("value").is_some() => {
+                serde_json::from_value(value).map(ExtOperation::TestRemove)
+            }
+            _ => serde_json::from_value(value).map(ExtOperation::Standard),
+        };
+        result.map_err(D::Error::custom)
+    }
+}
+
+/// Patch provided JSON document in-place with the extended operations. If any of the operations
+/// (outside of optional groups) fails, all previous operations are reverted.
+///
+/// `operation` of the returned error is the index of the top-level operation, which is the
+/// enclosing group for errors in nested operations.
+pub fn patch(doc: &mut Value, ops: &[ExtOperation]) -> Result<(), PatchError> {
+    let mut undo_stack = UndoStack::with_capacity(ops.len());
+    if let Err(e) = apply(doc, ops, None, &mut undo_stack) {
+        if let Err(e) = undo_patches(doc, &undo_stack) {
+            unreachable!("unable to undo applied patches: {e}")
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn apply(
+    doc: &mut Value,
+    ops: &[ExtOperation],
+    group: Option<usize>,
+    undo_stack: &mut UndoStack,
+) -> Result<(), PatchError> {
+    for (idx, op) in ops.iter().enumerate() {
+        let operation = group.unwrap_or(idx);
+        match op {
+            ExtOperation::Standard(op) => {
+                let options = PatchOptions::default();
+                apply_patches(doc, std::slice::from_ref(op), Some(undo_stack), &options)
+                    .map_err(|e| PatchError { operation, ..e })?;
+            }
+            ExtOperation::Group(GroupOperation { optional, ops }) => {
+                let mut nested = UndoStack::with_capacity(ops.len());
+                match apply(doc, ops, Some(operation), &mut nested) {
+                    Ok(()) => undo_stack.extend(nested),
+                    Err(e) => {
+                        if let Err(e) = undo_patches(doc, &nested) {
+                            unreachable!("unable to undo applied patches: {e}")
+                        }
+                        if !optional {
+                            return Err(e);
+                        }
+                    }
+                }
+            }
+            ExtOperation::TestExists(ExistsOperation { path })
+            | ExtOperation::TestNotExists(ExistsOperation { path }) => {
+                let expected = matches!(op, ExtOperation::TestExists(_));
+                if doc.pointer(path.as_str()).is_some() != expected {
+                    let kind = PatchErrorKind::TestFailed;
+                    return Err(operation_error(kind, operation, OpKind::Test, path, None));
+                }
+            }
+            ExtOperation::TestRemove(TestRemoveOperation { path, value }) => {
+                let options = PatchOptions::default();
+                crate::test(doc, path.as_str(), value, &options)
+                    .map_err(|kind| operation_error(kind, operation, OpKind::Remove, path, None))?;
+                let remove = PatchOperation::remove(path.clone());
+                apply_patches(doc, &[remove], Some(undo_stack), &options)
+                    .map_err(|e| PatchError { operation, ..e })?;
+            }
+            ExtOperation::StringAppend(StringOperation { path, value })
+            | ExtOperation::StringPrepend(StringOperation { path, value }) => {
+                let error = |kind| operation_error(kind, operation, OpKind::Replace, path, None);
+                let target = doc
+                    .pointer_mut(path.as_str())
+                    .ok_or_else(|| error(PatchErrorKind::InvalidPointer))?;
+                let Value::String(target) = target else {
+                    return Err(error(PatchErrorKind::UnexpectedType));
+                };
+                undo_stack.push(PatchOperation::replace(path.clone(), target.clone()));
+                if matches!(op, ExtOperation::StringAppend(_)) {
+                    target.push_str(value);
+                } else {
+                    target.insert_str(0, value);
+                }
+            }
+            ExtOperation::AddUnique(SetOperation { path, value })
+            | ExtOperation::RemoveValue(SetOperation { path, value }) => {
+                let error = |kind| operation_error(kind, operation, OpKind::Replace, path, None);
+                let target = doc
+                    .pointer_mut(path.as_str())
+                    .ok_or_else(|| error(PatchErrorKind::InvalidPointer))?;
+                let Value::Array(target) = target else {
+                    return Err(error(PatchErrorKind::UnexpectedType));
+                };
+                if matches!(op, ExtOperation::AddUnique(_)) {
+                    if !target.contains(value) {
+                        let mut element = path.clone();
+                        element.push_back(target.len());
+                        undo_stack.push(PatchOperation::remove(element));
+                        target.push(value.clone());
+                    }
+                } else {
+                    // Undo operations are applied in reverse, so the elements are restored
+                    // starting from the lowest index
+                    for idx in (0..target.len()).rev() {
+                        if target[idx] == *value {
+                            let mut element = path.clone();
+                            element.push_back(idx);
+                            undo_stack.push(PatchOperation::add(element, target.remove(idx)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{patch, ExtOperation};
+    use crate::{OpKind, PatchErrorKind};
+    use serde_json::{from_value, json, Value};
+
+    fn ops(value: Value) -> Vec<ExtOperation> {
+        from_value(value).unwrap()
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        let value = json!([
+            { "op": "add", "path": "/a", "value": 1 },
+            { "op": "group", "optional": false, "ops": [
+                { "op": "remove", "path": "/a" },
+                { "op": "group", "optional": true, "ops": [] },
+            ] },
+            { "op": "test-exists", "path": "/a" },
+            { "op": "test-not-exists", "path": "/b" },
+            { "op": "remove", "path": "/a", "value": 1 },
+            { "op": "string-append", "path": "/log", "value": "a" },
+            { "op": "string-prepend", "path": "/log", "value": "b" },
+            { "op": "add-unique", "path": "/tags", "value": "a" },
+            { "op": "remove-value", "path": "/tags", "value": { "b": 1 } },
+        ]);
+        assert_eq!(serde_json::to_value(ops(value.clone())).unwrap(), value);
+
+        let err = from_value::<Vec<ExtOperation>>(json!([{ "op": "group" }])).unwrap_err();
+        assert_eq!(err.to_string(), "missing field `ops`");
+    }
+
+    #[test]
+    fn nested_groups() {
+        let mut doc = json!({ "list": [1, 2] });
+        let p = ops(json!([
+            { "op": "add", "path": "/list/-", "value": 3 },
+            { "op": "group", "ops": [
+                { "op": "remove", "path": "/list/0" },
+                { "op": "group", "optional": true, "ops": [
+                    { "op": "add", "path": "/list/0", "value": 0 },
+                    { "op": "test", "path": "/list/0", "value": 2 },
+                ] },
+                { "op": "add", "path": "/done", "value": true },
+            ] },
+        ]));
+        patch(&mut doc, &p).unwrap();
+        assert_eq!(doc, json!({ "list": [2, 3], "done": true }));
+    }
+
+    #[test]
+    fn failed_group() {
+        let original = json!({ "a": 1 });
+        let mut doc = original.clone();
+        let p = ops(json!([
+            { "op": "replace", "path": "/a", "value": 2 },
+            { "op": "group", "ops": [
+                { "op": "add", "path": "/b", "value": 1 },
+                { "op": "group", "optional": true, "ops": [
+                    { "op": "remove", "path": "/missing" },
+                ] },
+                { "op": "test", "path": "/a", "value": 1 },
+            ] },
+        ]));
+        let err = patch(&mut doc, &p).unwrap_err();
+        assert_eq!(err.operation, 1);
+        assert!(matches!(err.kind, PatchErrorKind::TestFailed));
+        assert_eq!(doc, original);
+    }
+
+    #[test]
+    fn existence_tests() {
+        let mut doc = json!({ "a": null, "b": [1] });
+        let p = ops(json!([
+            { "op": "test-exists", "path": "/a" },
+            { "op": "test-not-exists", "path": "/b/1" },
+            { "op": "group", "optional": true, "ops": [
+                { "op": "test-not-exists", "path": "/c" },
+                { "op": "add", "path": "/c", "value": 1 },
+            ] },
+            { "op": "group", "optional": true, "ops": [
+                { "op": "test-not-exists", "path": "/c" },
+                { "op": "add", "path": "/c", "value": 2 },
+            ] },
+        ]));
+        patch(&mut doc, &p).unwrap();
+        assert_eq!(doc, json!({ "a": null, "b": [1], "c": 1 }));
+
+        let p = ops(json!([
+            { "op": "remove", "path": "/a" },
+            { "op": "test-exists", "path": "/a" },
+        ]));
+        let err = patch(&mut doc, &p).unwrap_err();
+        assert_eq!(err.operation, 1);
+        assert_eq!(err.op, OpKind::Test);
+        assert!(matches!(err.kind, PatchErrorKind::TestFailed));
+        assert_eq!(doc, json!({ "a": null, "b": [1], "c": 1 }));
+    }
+
+    #[test]
+    fn test_remove() {
+        let original = json!({ "a": { "x": 1 }, "b": [1, 2] });
+        let mut doc = original.clone();
+        let p = ops(json!([
+            { "op": "remove", "path": "/b/0", "value": 1 },
+            { "op": "remove", "path": "/a", "value": { "x": 1 } },
+        ]));
+        assert!(matches!(p[0], ExtOperation::TestRemove(_)));
+        patch(&mut doc, &p).unwrap();
+        assert_eq!(doc, json!({ "b": [2] }));
+
+        let mut doc = original.clone();
+        let p = ops(json!([
+            { "op": "remove", "path": "/b/0" },
+            { "op": "remove", "path": "/a", "value": { "x": 2 } },
+        ]));
+        assert!(matches!(p[0], ExtOperation::Standard(_)));
+        let err = patch(&mut doc, &p).unwrap_err();
+        assert_eq!(err.operation, 1);
+        assert_eq!(err.op, OpKind::Remove);
+        assert!(matches!(err.kind, PatchErrorKind::TestFailed));
+        assert_eq!(doc, original);
+
+        let p = ops(json!([{ "op": "remove", "path": "/c", "value": null }]));
+        let err = patch(&mut doc, &p).unwrap_err();
+        assert!(matches!(err.kind, PatchErrorKind::InvalidPointer));
+    }
+
+    #[test]
+    fn string_operations() {
+        let original = json!({ "log": "b", "n": 1 });
+        let mut doc = original.clone();
+        let p = ops(json!([
+            { "op": "string-append", "path": "/log", "value": "c" },
+            { "op": "string-prepend", "path": "/log", "value": "a" },
+        ]));
+        patch(&mut doc, &p).unwrap();
+        assert_eq!(doc, json!({ "log": "abc", "n": 1 }));
+
+        let mut doc = original.clone();
+        let p = ops(json!([
+            { "op": "string-append", "path": "/log", "value": "c" },
+            { "op": "string-append", "path": "/n", "value": "c" },
+        ]));
+        let err = patch(&mut doc, &p).unwrap_err();
+        assert_eq!(err.operation, 1);
+        assert!(matches!(err.kind, PatchErrorKind::UnexpectedType));
+        assert_eq!(doc, original);
+
+        let p = ops(json!([{ "op": "string-prepend", "path": "/missing", "value": "" }]));
+        let err = patch(&mut doc, &p).unwrap_err();
+        assert!(matches!(err.kind, PatchErrorKind::InvalidPointer));
+    }
+
+    #[test]
+    fn set_operations() {
+        let original = json!({ "tags": ["a", "b", "a", "c"], "n": 1 });
+        let mut doc = original.clone();
+        let p = ops(json!([
+            { "op": "add-unique", "path": "/tags", "value": "b" },
+            { "op": "add-unique", "path": "/tags", "value": "d" },
+            { "op": "remove-value", "path": "/tags", "value": "a" },
+            { "op": "remove-value", "path": "/tags", "value": "e" },
+        ]));
+        patch(&mut doc, &p).unwrap();
+        assert_eq!(doc, json!({ "tags": ["b", "c", "d"], "n": 1 }));
+
+        let mut doc = original.clone();
+        let p = ops(json!([
+            { "op": "add-unique", "path": "/tags", "value": "d" },
+            { "op": "remove-value", "path": "/tags", "value": "a" },
+            { "op": "add-unique", "path": "/n", "value": 1 },
+        ]));
+        let err = patch(&mut doc, &p).unwrap_err();
+        assert_eq!(err.operation, 2);
+        assert!(matches!(err.kind, PatchErrorKind::UnexpectedType));
+        assert_eq!(doc, original);
+
+        let p = ops(json!([{ "op": "remove-value", "path": "/missing", "value": 1 }]));
+        let err = patch(&mut doc, &p).unwrap_err();
+        assert!(matches!(err.kind, PatchErrorKind::InvalidPointer));
+    }
+}
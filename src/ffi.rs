@@ -0,0 +1,200 @@
+//! C ABI for applying, generating and merging patches over UTF-8 JSON strings, so the same
+//! implementation can be used from other languages.
+//!
+//! Build a shared library (`libjson_patch.so`, `libjson_patch.dylib` or `json_patch.dll` in the
+//! target directory) with:
+//!
+//! ```sh
+//! cargo rustc --release --features ffi --crate-type cdylib
+//! ```
+//!
+//! and declare the functions as follows:
+//!
+//! ```c
+//! char *json_patch_apply(const char *doc, const char *patch, char **error);
+//! char *json_patch_diff(const char *left, const char *right, char **error);
+//! char *json_patch_merge(const char *doc, const char *patch, char **error);
+//! void json_patch_free_string(char *str);
+//! ```
+//!
+//! All functions return a newly allocated NUL-terminated JSON string on success. On failure, they
+//! return `NULL` and, if `error` is not `NULL`, store a newly allocated error message into it.
+//! Strings returned by these functions must be released with `json_patch_free_string`.
+//!
+//! `json_patch_diff` is only exported with the `diff` feature, which is enabled by default.
+use serde_json::Value;
+use std::ffi::{c_char, CStr, CString};
+
+unsafe fn parse<T: serde::de::DeserializeOwned>(input: *const c_char) -> Result<T, String> {
+    if input.is_null() {
+        return Err("input is NULL".to_owned());
+    }
+    let input = CStr::from_ptr(input)
+        .to_str()
+        .map_err(|err| format!("input is not valid UTF-8: {err}"))?;
+    serde_json::from_str(input).map_err(|err| format!("input is not valid JSON: {err}"))
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    // JSON and error messages produced here never contain NUL characters: they are escaped in
+    // JSON strings and cannot appear in the input.
+    CString::new(s).map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+unsafe fn finish(result: Result<Value, String>, error: *mut *mut c_char) -> *mut c_char {
+    let message = match result {
+        Ok(value) => return into_c_string(value.to_string()),
+        Err(message) => message,
+    };
+    if !error.is_null() {
+        *error = into_c_string(message);
+    }
+    std::ptr::null_mut()
+}
+
+/// Apply JSON Patch (RFC 6902) to the document, returning the patched document.
+///
+/// # Safety
+///
+/// `doc` and `patch` must be valid NUL-terminated strings; `error` must be either `NULL` or a
+/// valid pointer to write to.
+#[no_mangle]
+pub unsafe extern "C" fn json_patch_apply(
+    doc: *const c_char,
+    patch: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    let result = parse::<Value>(doc).and_then(|mut doc| {
+        let patch: crate::Patch = parse(patch)?;
+        crate::patch(&mut doc, &patch).map_err(|err| err.to_string())?;
+        Ok(doc)
+    });
+    finish(result, error)
+}
+
+/// Diff two documents, returning JSON Patch (RFC 6902). Requires the `diff` feature.
+///
+/// # Safety
+///
+/// `left` and `right` must be valid NUL-terminated strings; `error` must be either `NULL` or a
+/// valid pointer to write to.
+#[cfg(feature = "diff")]
+#[no_mangle]
+pub unsafe extern "C" fn json_patch_diff(
+    left: *const c_char,
+    right: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    let result = parse::<Value>(left).and_then(|left| {
+        let right: Value = parse(right)?;
+        Ok(serde_json::to_value(crate::diff(&left, &right)).unwrap())
+    });
+    finish(result, error)
+}
+
+/// Apply JSON Merge Patch (RFC 7396) to the document, returning the merged document.
+///
+/// # Safety
+///
+/// `doc` and `patch` must be valid NUL-terminated strings; `error` must be either `NULL` or a
+/// valid pointer to write to.
+#[no_mangle]
+pub unsafe extern "C" fn json_patch_merge(
+    doc: *const c_char,
+    patch: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    let result = parse::<Value>(doc).and_then(|mut doc| {
+        let patch: Value = parse(patch)?;
+        crate::merge(&mut doc, &patch);
+        Ok(doc)
+    });
+    finish(result, error)
+}
+
+/// Release a string returned by any of the functions in this module.
+///
+/// # Safety
+///
+/// `str` must be either `NULL` or a string returned by one of the functions in this module that
+/// has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn json_patch_free_string(str: *mut c_char) {
+    if !str.is_null() {
+        drop(CString::from_raw(str));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(str: *mut c_char) -> Option<String> {
+        if str.is_null() {
+            return None;
+        }
+        let result = CStr::from_ptr(str).to_str().unwrap().to_owned();
+        json_patch_free_string(str);
+        Some(result)
+    }
+
+    #[test]
+    fn apply() {
+        let doc = CString::new(r#"{"a":1}"#).unwrap();
+        let patch = CString::new(r#"[{"op":"add","path":"/b","value":2}]"#).unwrap();
+        unsafe {
+            let mut error = std::ptr::null_mut();
+            let result = json_patch_apply(doc.as_ptr(), patch.as_ptr(), &mut error);
+            assert_eq!(take(result).as_deref(), Some(r#"{"a":1,"b":2}"#));
+            assert!(error.is_null());
+        }
+    }
+
+    #[test]
+    fn apply_error() {
+        let doc = CString::new(r#"{"a":1}"#).unwrap();
+        let patch = CString::new(r#"[{"op":"remove","path":"/b"}]"#).unwrap();
+        unsafe {
+            let mut error = std::ptr::null_mut();
+            let result = json_patch_apply(doc.as_ptr(), patch.as_ptr(), &mut error);
+            assert!(result.is_null());
+            assert_eq!(
+                take(error).as_deref(),
+                Some("operation '/0' failed at path '/b': path is invalid")
+            );
+
+            let result = json_patch_apply(std::ptr::null(), patch.as_ptr(), std::ptr::null_mut());
+            assert!(result.is_null());
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let doc = CString::new(r#"{"a":1,"b":2}"#).unwrap();
+        let patch = CString::new(r#"{"a":null}"#).unwrap();
+        unsafe {
+            let result = json_patch_merge(doc.as_ptr(), patch.as_ptr(), std::ptr::null_mut());
+            assert_eq!(take(result).as_deref(), Some(r#"{"b":2}"#));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "diff")]
+    fn diff() {
+        let left = CString::new(r#"{"a":1,"b":[1,2]}"#).unwrap();
+        let right = CString::new(r#"{"b":[1]}"#).unwrap();
+        let invalid = CString::new("{").unwrap();
+        unsafe {
+            let result = json_patch_diff(left.as_ptr(), right.as_ptr(), std::ptr::null_mut());
+            assert_eq!(
+                take(result).as_deref(),
+                Some(r#"[{"op":"remove","path":"/b/1"},{"op":"remove","path":"/a"}]"#)
+            );
+
+            let mut error = std::ptr::null_mut();
+            let result = json_patch_diff(left.as_ptr(), invalid.as_ptr(), &mut error);
+            assert!(result.is_null());
+            assert!(take(error).unwrap().starts_with("input is not valid JSON"));
+        }
+    }
+}
@@ -0,0 +1,308 @@
+//! Conversion between [`google.protobuf.FieldMask`][field-mask] paths and JSON patches.
+//!
+//! Field mask paths are dot-separated member names, like `author.given_name`. Members with a dot
+//! in their name are quoted with backticks, like ``labels.`app.kubernetes.io/name` ``. Names are
+//! matched literally against the members of JSON documents (no conversion between `snake_case`
+//! and `lowerCamelCase` is done), and field masks cannot refer to array elements.
+//!
+//! [field-mask]: https://protobuf.dev/reference/protobuf/google.protobuf/#field-mask
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::{field_mask, Patch};
+//! use serde_json::{from_value, json};
+//!
+//! let doc = json!({ "title": "Goodbye!", "author": { "givenName": "John", "familyName": "Doe" } });
+//! let update = json!({ "title": "Hello!", "author": { "givenName": "Jane" } });
+//!
+//! let p = field_mask::to_patch(&["title", "author.familyName"], &doc, &update);
+//! assert_eq!(p, from_value::<Patch>(json!([
+//!   { "op": "remove", "path": "/author/familyName" },
+//!   { "op": "replace", "path": "/title", "value": "Hello!" },
+//! ])).unwrap());
+//!
+//! assert_eq!(field_mask::from_patch(&p, &doc), ["author.familyName", "title"]);
+//! ```
+use crate::{MergePatch, Patch, PatchOperation};
+use jsonptr::PointerBuf;
+use serde_json::{Map, Value};
+
+/// Build a JSON Merge Patch (RFC 7396) setting the masked members to their values in `source`
+/// and removing the masked members which are missing from `source`.
+///
+/// Note that merge patches merge objects instead of replacing them, so a masked member which is
+/// an object keeps the members it does not have in `source`. Use [`to_patch`] to replace masked
+/// members entirely.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{field_mask, MergePatch};
+/// use serde_json::json;
+///
+/// let update = json!({ "title": "Hello!", "author": { "givenName": "Jane" } });
+/// let p = field_mask::to_merge_patch(&["title", "author.familyName"], &update);
+/// assert_eq!(p, MergePatch(json!({ "title": "Hello!", "author": { "familyName": null } })));
+/// ```
+pub fn to_merge_patch<S: AsRef<str>>(mask: &[S], source: &Value) -> MergePatch {
+    let mut patch = Value::Object(Map::new());
+    for path in canonical(mask) {
+        let value = get(source, &path).cloned().unwrap_or(Value::Null);
+        // Paths do not overlap, so all the parents are objects created here
+        let mut target = &mut patch;
+        for name in &path {
+            target = target
+                .as_object_mut()
+                .unwrap()
+                .entry(name.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+        *target = value;
+    }
+    MergePatch(patch)
+}
+
+/// Build a JSON Patch (RFC 6902) transforming `doc` so that masked members are the same as in
+/// `source`: masked members are replaced with their values from `source`, added if they are
+/// missing from `doc` (along with their missing parents) or removed if they are missing from
+/// `source`. Masked members are skipped when `doc` is not an object.
+pub fn to_patch<S: AsRef<str>>(mask: &[S], doc: &Value, source: &Value) -> Patch {
+    let mut current = doc.clone();
+    let mut patch = Patch::default();
+    for path in canonical(mask) {
+        let op = match (get(&current, &path), get(source, &path)) {
+            (Some(current), Some(value)) if current != value => {
+                PatchOperation::replace(pointer(&path), value.clone())
+            }
+            (Some(_), None) => PatchOperation::remove(pointer(&path)),
+            (None, Some(value)) => {
+                // Find the closest ancestor which is an object and add the missing parents to it
+                let Some(existing) = (0..path.len())
+                    .rev()
+                    .find(|&len| get(&current, &path[..len]).is_some_and(Value::is_object))
+                else {
+                    // Members cannot be added to a document which is not an object
+                    continue;
+                };
+                let value = path[existing + 1..]
+                    .iter()
+                    .rev()
+                    .fold(value.clone(), |value, name| {
+                        Value::Object(Map::from_iter([(name.clone(), value)]))
+                    });
+                let path = &path[..=existing];
+                match get(&current, path) {
+                    Some(_) => PatchOperation::replace(pointer(path), value),
+                    None => PatchOperation::add(pointer(path), value),
+                }
+            }
+            _ => continue,
+        };
+        crate::patch(&mut current, std::slice::from_ref(&op)).unwrap();
+        patch.0.push(op);
+    }
+    patch
+}
+
+/// Collect paths of the members set or removed by the merge patch, in the canonical form
+/// (sorted, without paths covered by other paths).
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{field_mask, MergePatch};
+/// use serde_json::json;
+///
+/// let p = MergePatch(json!({ "title": "Hello!", "author": { "familyName": null } }));
+/// assert_eq!(field_mask::from_merge_patch(&p), ["author.familyName", "title"]);
+/// ```
+pub fn from_merge_patch(patch: &MergePatch) -> Vec<String> {
+    fn collect(patch: &Map<String, Value>, path: &mut Vec<String>, mask: &mut Vec<Vec<String>>) {
+        for (name, value) in patch {
+            path.push(name.clone());
+            match value {
+                Value::Object(obj) if !obj.is_empty() => collect(obj, path, mask),
+                _ => mask.push(path.clone()),
+            }
+            path.pop();
+        }
+    }
+
+    let mut mask = Vec::new();
+    match patch.0 {
+        Value::Object(ref obj) => collect(obj, &mut Vec::new(), &mut mask),
+        // Replaces the whole document
+        _ => mask.push(Vec::new()),
+    }
+    format(mask)
+}
+
+/// Collect paths of the members modified by the patch when applied to `doc`, in the canonical
+/// form (sorted, without paths covered by other paths). Modifications of array elements are
+/// attributed to the member containing the array.
+pub fn from_patch(patch: &[PatchOperation], doc: &Value) -> Vec<String> {
+    let mut mask = Vec::new();
+    let mut current = doc.clone();
+    for op in patch {
+        for path in op.modified_paths() {
+            let mut names = Vec::new();
+            let mut target = Some(&current);
+            for token in path.tokens() {
+                // Stop at the first token which is not a member name
+                if target.is_some_and(|target| !target.is_object()) {
+                    break;
+                }
+                target = target.and_then(|target| target.get(token.decoded().as_ref()));
+                names.push(token.decoded().into_owned());
+            }
+            mask.push(names);
+        }
+        // Applying the patch will fail anyway, ignore the rest
+        if crate::patch(&mut current, std::slice::from_ref(op)).is_err() {
+            break;
+        }
+    }
+    format(mask)
+}
+
+fn get<'a>(doc: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(doc, |doc, name| doc.as_object()?.get(name))
+}
+
+fn pointer(path: &[String]) -> PointerBuf {
+    PointerBuf::from_tokens(path)
+}
+
+// Parses the paths, sorts them and removes the ones covered by other paths.
+fn canonical<S: AsRef<str>>(mask: &[S]) -> Vec<Vec<String>> {
+    let mut paths: Vec<_> = mask.iter().map(|path| parse(path.as_ref())).collect();
+    paths.sort();
+    paths.dedup_by(|path, prefix| path.starts_with(prefix));
+    paths
+}
+
+fn parse(path: &str) -> Vec<String> {
+    if path.is_empty() {
+        return Vec::new();
+    }
+    let mut names = vec![String::new()];
+    let mut quoted = false;
+    for c in path.chars() {
+        match c {
+            '`' => quoted = !quoted,
+            '.' if !quoted => names.push(String::new()),
+            c => names.last_mut().unwrap().push(c),
+        }
+    }
+    names
+}
+
+fn format(mask: Vec<Vec<String>>) -> Vec<String> {
+    let mut mask = mask;
+    mask.sort();
+    mask.dedup_by(|path, prefix| path.starts_with(prefix));
+    mask.into_iter()
+        .map(|path| {
+            let names: Vec<_> = path
+                .into_iter()
+                .map(|name| match name.contains('.') {
+                    true => format!("`{name}`"),
+                    false => name,
+                })
+                .collect();
+            names.join(".")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn parse_format() {
+        assert_eq!(parse("a.`b.c`.d"), ["a", "b.c", "d"]);
+        assert_eq!(parse(""), Vec::<String>::new());
+        assert_eq!(
+            format(vec![
+                vec!["b".into()],
+                vec!["a".into(), "x.y".into()],
+                vec!["b".into(), "c".into()],
+                vec!["a".into(), "x.y".into()],
+            ]),
+            ["a.`x.y`", "b"]
+        );
+        assert_eq!(canonical(&["b.c", "a", "b", "a.d"]), [vec!["a"], vec!["b"]]);
+    }
+
+    #[test]
+    fn missing_parents() {
+        let doc = json!({ "a": 1, "b": { "c": 2 } });
+        let source = json!({ "a": { "x": { "y": 1, "z": 2 } }, "b": { "d": { "e": 3 } } });
+        let p = to_patch(&["a.x.y", "a.x.z", "b.d.e", "c"], &doc, &source);
+        assert_eq!(
+            p,
+            from_value::<Patch>(json!([
+                { "op": "replace", "path": "/a", "value": { "x": { "y": 1 } } },
+                { "op": "add", "path": "/a/x/z", "value": 2 },
+                { "op": "add", "path": "/b/d", "value": { "e": 3 } },
+            ]))
+            .unwrap()
+        );
+
+        let mut patched = doc.clone();
+        crate::patch(&mut patched, &p).unwrap();
+        assert_eq!(
+            patched,
+            json!({ "a": { "x": { "y": 1, "z": 2 } }, "b": { "c": 2, "d": { "e": 3 } } })
+        );
+    }
+
+    #[test]
+    fn non_object_document() {
+        let p = to_patch(
+            &["a", "b.c"],
+            &json!([1]),
+            &json!({ "a": 1, "b": { "c": 2 } }),
+        );
+        assert_eq!(p, Patch::default());
+
+        let p = to_patch(&["a"], &json!({ "a": 1 }), &json!([1]));
+        assert_eq!(
+            p,
+            from_value::<Patch>(json!([{ "op": "remove", "path": "/a" }])).unwrap()
+        );
+    }
+
+    #[test]
+    fn masks_from_patches() {
+        let doc = json!({ "a": [{ "b": 1 }], "c": { "d.e": 1 } });
+        let p: Patch = from_value(json!([
+            { "op": "replace", "path": "/a/0/b", "value": 2 },
+            { "op": "add", "path": "/x", "value": {} },
+            { "op": "add", "path": "/x/y", "value": 1 },
+            { "op": "move", "from": "/c/d.e", "path": "/c/f" },
+        ]))
+        .unwrap();
+        assert_eq!(from_patch(&p, &doc), ["a", "c.`d.e`", "c.f", "x"]);
+
+        assert_eq!(from_merge_patch(&MergePatch(json!([]))), [""]);
+        assert_eq!(
+            from_merge_patch(&MergePatch(json!({ "a": {}, "b": { "c": [] } }))),
+            ["a", "b.c"]
+        );
+    }
+
+    #[test]
+    fn merge_patch_roundtrip() {
+        let mut doc = json!({ "a": { "b": 1, "c": 2 }, "d": 3 });
+        let source = json!({ "a": { "b": 4 }, "e": [5] });
+        let p = to_merge_patch(&["a.b", "a.c", "d", "e"], &source);
+        assert_eq!(from_merge_patch(&p), ["a.b", "a.c", "d", "e"]);
+        crate::merge(&mut doc, &p.0);
+        assert_eq!(doc, json!({ "a": { "b": 4 }, "e": [5] }));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{PatchError, PatchOperation};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+/// Error that can occur while patching a [`GuardedDocument`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum GuardError {
+    /// Patch was made against a different version of the document.
+    #[error("version mismatch: document is at version {actual}")]
+    VersionMismatch {
+        /// Version the patch expected, `None` if given as an `If-Match` header value which
+        /// is not a version of this document.
+        expected: Option<u64>,
+        /// Current version of the document.
+        actual: u64,
+    },
+    /// Patch could not be applied.
+    #[error(transparent)]
+    Patch(#[from] PatchError),
+}
+
+/// JSON document with a version, which is bumped by every patch applied to it.
+///
+/// Patches carry the version of the document they were made against and fail with
+/// [`GuardError::VersionMismatch`] if the document has changed since then, like HTTP `PATCH`
+/// requests with an `If-Match` header. The version is exposed as an entity tag with
+/// [`GuardedDocument::etag`].
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{GuardError, GuardedDocument, Patch};
+/// use serde_json::{from_value, json};
+///
+/// let mut doc = GuardedDocument::new(json!({ "title": "Hello" }));
+/// let p: Patch = from_value(json!([
+///   { "op": "replace", "path": "/title", "value": "Hello!" },
+/// ])).unwrap();
+///
+/// assert_eq!(doc.etag(), "\"0\"");
+/// doc.apply_if_match("\"0\"", &p).unwrap();
+/// assert_eq!(doc.version(), 1);
+/// assert_eq!(doc.value(), &json!({ "title": "Hello!" }));
+///
+/// // Concurrent change made against the previous version
+/// let err = doc.apply(0, &p).unwrap_err();
+/// assert!(matches!(err, GuardError::VersionMismatch { expected: Some(0), actual: 1 }));
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct GuardedDocument {
+    value: Value,
+    version: u64,
+}
+
+impl GuardedDocument {
+    /// Creates a document at version `0`.
+    pub fn new(value: Value) -> Self {
+        Self::with_version(value, 0)
+    }
+
+    /// Creates a document at the given version, like the one loaded from a storage.
+    pub fn with_version(value: Value, version: u64) -> Self {
+        GuardedDocument { value, version }
+    }
+
+    /// Returns the document.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Returns the version of the document.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the strong entity tag of the current version, to be used as a value of `ETag`
+    /// header.
+    pub fn etag(&self) -> String {
+        format!("\"{}\"", self.version)
+    }
+
+    /// Returns the document, discarding the version.
+    pub fn into_value(self) -> Value {
+        self.value
+    }
+
+    /// Applies the patch made against the given version of the document and returns the new
+    /// version. If the versions do not match or the patch fails, the document is left unchanged.
+    pub fn apply(&mut self, expected: u64, patch: &[PatchOperation]) -> Result<u64, GuardError> {
+        if expected != self.version {
+            return Err(GuardError::VersionMismatch {
+                expected: Some(expected),
+                actual: self.version,
+            });
+        }
+        crate::patch(&mut self.value, patch)?;
+        self.version += 1;
+        Ok(self.version)
+    }
+
+    /// Applies the patch if the `If-Match` header value matches the current entity tag (or is
+    /// `*`), same as [`GuardedDocument::apply`]. Weak entity tags never match.
+    pub fn apply_if_match(
+        &mut self,
+        if_match: &str,
+        patch: &[PatchOperation],
+    ) -> Result<u64, GuardError> {
+        let etag = self.etag();
+        let matches = if_match
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag == etag);
+        if !matches {
+            let expected = if_match
+                .trim()
+                .strip_prefix('"')
+                .and_then(|tag| tag.strip_suffix('"'))
+                .and_then(|tag| tag.parse().ok());
+            return Err(GuardError::VersionMismatch {
+                expected,
+                actual: self.version,
+            });
+        }
+        self.apply(self.version, patch)
+    }
+}
+
+impl From<Value> for GuardedDocument {
+    fn from(value: Value) -> Self {
+        GuardedDocument::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GuardError, GuardedDocument};
+    use crate::Patch;
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn if_match() {
+        let mut doc = GuardedDocument::with_version(json!({ "a": 1 }), 7);
+        let p: Patch = from_value(json!([{ "op": "replace", "path": "/a", "value": 2 }])).unwrap();
+
+        assert_eq!(doc.apply_if_match("\"3\", \"7\"", &p).unwrap(), 8);
+        assert_eq!(doc.apply_if_match("*", &p).unwrap(), 9);
+
+        let err = doc.apply_if_match("W/\"9\"", &p).unwrap_err();
+        assert!(matches!(
+            err,
+            GuardError::VersionMismatch {
+                expected: None,
+                actual: 9
+            }
+        ));
+        let err = doc.apply_if_match("\"8\"", &p).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "version mismatch: document is at version 9"
+        );
+    }
+
+    #[test]
+    fn failed_patch() {
+        let mut doc = GuardedDocument::new(json!({ "a": 1 }));
+        let p: Patch = from_value(json!([
+            { "op": "replace", "path": "/a", "value": 2 },
+            { "op": "remove", "path": "/b" },
+        ]))
+        .unwrap();
+
+        let err = doc.apply(0, &p).unwrap_err();
+        assert!(matches!(err, GuardError::Patch(_)));
+        assert_eq!(doc.version(), 0);
+        assert_eq!(doc.value(), &json!({ "a": 1 }));
+    }
+}
@@ -0,0 +1,250 @@
+//! Patch operations targeting multiple values selected by a JSONPath ([RFC 9535]) query.
+//!
+//! [`PathOperation`] is a regular patch operation with an optional `select` member holding a
+//! JSONPath query. When present, the operation is applied once for every value matched by the
+//! query, with its `path` (and `from`) pointers taken relative to the matched value. Queries are
+//! evaluated against the document as modified by the preceding operations.
+//!
+//! Matches are processed in reverse document order, so removing several elements of the same
+//! array does not shift the elements which are yet to be removed.
+//!
+//! [RFC 9535]: https://www.rfc-editor.org/rfc/rfc9535
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::jsonpath::{patch, JsonPathOptions, PathOperation};
+//! use serde_json::{from_value, json};
+//!
+//! let mut doc = json!({
+//!   "services": [
+//!     { "name": "api", "enabled": true },
+//!     { "name": "worker" },
+//!     { "name": "legacy", "deprecated": true },
+//!   ]
+//! });
+//!
+//! let ops: Vec<PathOperation> = from_value(json!([
+//!   { "op": "remove", "select": "$.services[?@.deprecated]", "path": "" },
+//!   { "op": "add", "select": "$.services[*]", "path": "/enabled", "value": false },
+//! ])).unwrap();
+//!
+//! patch(&mut doc, &ops, &JsonPathOptions::default()).unwrap();
+//! assert_eq!(doc, json!({
+//!   "services": [
+//!     { "name": "api", "enabled": false },
+//!     { "name": "worker", "enabled": false },
+//!   ]
+//! }));
+//! ```
+use crate::{Patch, PatchError, PatchOperation};
+use jsonptr::PointerBuf;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use thiserror::Error;
+
+/// Patch operation which is applied to every value matched by the `select` query.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PathOperation {
+    /// JSONPath query selecting the values to apply the operation to. If not set, the operation
+    /// is applied to the document as usual.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub select: Option<JsonPath>,
+    /// Operation to apply, with `path` and `from` relative to each selected value.
+    #[serde(flatten)]
+    pub operation: PatchOperation,
+}
+
+impl From<PatchOperation> for PathOperation {
+    fn from(operation: PatchOperation) -> Self {
+        PathOperation {
+            select: None,
+            operation,
+        }
+    }
+}
+
+/// Options for [`expand`] and [`patch`].
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct JsonPathOptions {
+    /// Fail if the `select` query of any operation does not match anything. By default, such
+    /// operations are skipped.
+    pub require_match: bool,
+}
+
+/// Error that can occur while applying JSONPath-targeted operations.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum JsonPathError {
+    /// Query of the operation did not match any value, while
+    /// [`require_match`](JsonPathOptions::require_match) is set.
+    #[error("operation '/{operation}' does not match any value")]
+    NoMatch {
+        /// Index of the operation.
+        operation: usize,
+    },
+    /// Concrete operation failed to apply. `operation` is the index of the original operation,
+    /// `path` and `from` are the concrete pointers.
+    #[error(transparent)]
+    Patch(#[from] PatchError),
+}
+
+/// Expand JSONPath-targeted operations into a regular patch with concrete pointers, as applied
+/// to the given document.
+///
+/// The document is not modified, but the operations are applied to a copy of it, since every
+/// query is evaluated against the result of the preceding operations.
+pub fn expand(
+    doc: &Value,
+    ops: &[PathOperation],
+    options: &JsonPathOptions,
+) -> Result<Patch, JsonPathError> {
+    let (_, expanded) = apply(doc, ops, options)?;
+    Ok(Patch(expanded))
+}
+
+/// Apply JSONPath-targeted operations to the document.
+///
+/// Operations are applied atomically: if any of them fails, the document is left unchanged.
+pub fn patch(
+    doc: &mut Value,
+    ops: &[PathOperation],
+    options: &JsonPathOptions,
+) -> Result<(), JsonPathError> {
+    let (result, _) = apply(doc, ops, options)?;
+    *doc = result;
+    Ok(())
+}
+
+fn apply(
+    doc: &Value,
+    ops: &[PathOperation],
+    options: &JsonPathOptions,
+) -> Result<(Value, Vec<PatchOperation>), JsonPathError> {
+    let mut doc = doc.clone();
+    let mut expanded = Vec::new();
+    for (idx, op) in ops.iter().enumerate() {
+        let start = expanded.len();
+        expand_operation(&doc, idx, op, options, &mut expanded)?;
+        crate::patch(&mut doc, &expanded[start..]).map_err(|err| PatchError {
+            operation: idx,
+            ..err
+        })?;
+    }
+    Ok((doc, expanded))
+}
+
+fn expand_operation(
+    doc: &Value,
+    idx: usize,
+    op: &PathOperation,
+    options: &JsonPathOptions,
+    expanded: &mut Vec<PatchOperation>,
+) -> Result<(), JsonPathError> {
+    let Some(select) = &op.select else {
+        expanded.push(op.operation.clone());
+        return Ok(());
+    };
+    let matches = select.query_located(doc);
+    if matches.is_empty() && options.require_match {
+        return Err(JsonPathError::NoMatch { operation: idx });
+    }
+    for node in matches.locations().rev() {
+        let prefix =
+            PointerBuf::parse(&node.to_json_pointer()).expect("normalized path is a valid pointer");
+        let concrete = op
+            .operation
+            .map_pointers(|ptr| Some(prefix.concat(ptr)))
+            .expect("prefixing never fails");
+        expanded.push(concrete);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand, patch, JsonPathError, JsonPathOptions, PathOperation};
+    use crate::Patch;
+    use serde_json::{from_value, json, Value};
+
+    fn ops(value: Value) -> Vec<PathOperation> {
+        from_value(value).unwrap()
+    }
+
+    #[test]
+    fn expand_to_pointers() {
+        let doc = json!({ "list": [1, 2, 3, 4], "a": { "x": 1 } });
+        let p = expand(
+            &doc,
+            &ops(json!([
+                { "op": "remove", "select": "$.list[?@ > 1]", "path": "" },
+                { "op": "add", "select": "$.list[*]", "path": "", "value": 0 },
+                { "op": "replace", "path": "/a/x", "value": 2 },
+            ])),
+            &JsonPathOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            p,
+            from_value::<Patch>(json!([
+                { "op": "remove", "path": "/list/3" },
+                { "op": "remove", "path": "/list/2" },
+                { "op": "remove", "path": "/list/1" },
+                { "op": "add", "path": "/list/0", "value": 0 },
+                { "op": "replace", "path": "/a/x", "value": 2 },
+            ]))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn relative_from() {
+        let mut doc = json!({ "users": [{ "name": "a" }, { "name": "b" }] });
+        let ops = ops(json!([
+            { "op": "copy", "select": "$.users[*]", "from": "/name", "path": "/login" },
+        ]));
+        patch(&mut doc, &ops, &JsonPathOptions::default()).unwrap();
+        assert_eq!(
+            doc,
+            json!({ "users": [{ "name": "a", "login": "a" }, { "name": "b", "login": "b" }] })
+        );
+    }
+
+    #[test]
+    fn require_match() {
+        let mut doc = json!({ "list": [] });
+        let ops = ops(json!([
+            { "op": "add", "path": "/x", "value": 1 },
+            { "op": "remove", "select": "$.list[*]", "path": "" },
+        ]));
+        patch(&mut doc, &ops, &JsonPathOptions::default()).unwrap();
+        assert_eq!(doc, json!({ "list": [], "x": 1 }));
+
+        let options = JsonPathOptions {
+            require_match: true,
+        };
+        let mut doc = json!({ "list": [] });
+        let err = patch(&mut doc, &ops, &options).unwrap_err();
+        assert!(matches!(err, JsonPathError::NoMatch { operation: 1 }));
+        assert_eq!(doc, json!({ "list": [] }));
+    }
+
+    #[test]
+    fn error_operation_index() {
+        let mut doc = json!({ "list": [{}, { "a": 1 }] });
+        let ops = ops(json!([
+            { "op": "add", "path": "/x", "value": 1 },
+            { "op": "replace", "select": "$.list[*]", "path": "/a", "value": 2 },
+        ]));
+        let JsonPathError::Patch(err) =
+            patch(&mut doc, &ops, &JsonPathOptions::default()).unwrap_err()
+        else {
+            panic!("expected patch error");
+        };
+        assert_eq!(err.operation, 1);
+        assert_eq!(err.path.as_str(), "/list/0/a");
+        assert_eq!(doc, json!({ "list": [{}, { "a": 1 }] }));
+    }
+}
@@ -78,20 +78,126 @@
 //! ```
 #![warn(missing_docs)]
 
-use jsonptr::{Pointer, PointerBuf};
+use jsonptr::{index::Index, Pointer, PointerBuf};
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use std::{
-    borrow::Cow,
-    fmt::{self, Display, Formatter},
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt::{self, Debug, Display, Formatter},
+    sync::Arc,
 };
 use thiserror::Error;
 
+mod annotated;
+mod anonymize;
+#[cfg(feature = "axum")]
+pub mod axum;
+mod batch;
+pub mod binary;
+#[cfg(feature = "borrowed")]
+pub mod borrowed;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+mod change_tree;
+#[cfg(feature = "changeset")]
+pub mod changeset;
+pub mod collection;
+mod compact;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+mod de;
+#[cfg(feature = "diesel")]
+mod diesel_types;
 #[cfg(feature = "diff")]
 mod diff;
+#[cfg(any(
+    feature = "cbor",
+    feature = "shared",
+    feature = "toml",
+    feature = "yaml"
+))]
+mod document;
+pub mod ext;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod field_mask;
+mod guarded;
+#[cfg(feature = "jsonpath")]
+pub mod jsonpath;
+mod merge3;
+pub mod migration;
+pub mod mongo;
+mod moves;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+#[cfg(feature = "diff")]
+mod observe;
+pub mod ot;
+mod placeholder;
+pub mod pointer;
+#[cfg(feature = "python")]
+pub mod python;
+mod reader;
+mod redact;
+mod repr;
+mod reversible;
+#[cfg(feature = "diff")]
+mod ser;
+#[cfg(feature = "shared")]
+pub mod shared;
+pub mod sql;
+#[cfg(feature = "sqlx")]
+mod sqlx_types;
+mod step;
+pub mod template;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "toml")]
+mod toml;
+mod truncate;
+mod typed;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod watch;
+mod writer;
+#[cfg(feature = "yaml")]
+mod yaml;
 
+pub use self::annotated::{AnnotatedOperation, AnnotatedPatch, WithMetadata};
+pub use self::anonymize::Anonymizer;
+pub use self::batch::patch_many;
+#[cfg(feature = "rayon")]
+pub use self::batch::patch_many_par;
+pub use self::change_tree::{ChangeStatus, ChangeTree};
+pub use self::compact::{compact, compact_journal};
+pub use self::de::{CompatPatch, TolerantPatch, UnknownOperation};
+#[cfg(feature = "diff")]
+pub use self::diff::{diff, diff_with, ArrayDiff, DiffOptions, TypeChange};
+pub use self::guarded::{GuardError, GuardedDocument};
+pub use self::merge3::{merge3, MergeConflict, ThreeWayMerge};
+#[cfg(feature = "diff")]
+pub use self::observe::{observe, Observer};
+pub use self::placeholder::Placeholders;
+pub use self::reader::{PatchReader, ReadError};
+pub use self::redact::Redacted;
+pub use self::repr::{AdjacentlyTagged, ExternallyTagged};
+pub use self::reversible::{ReversibleOperation, ReversiblePatch};
 #[cfg(feature = "diff")]
-pub use self::diff::diff;
+pub use self::ser::{diff_serialize, DiffSerializeError};
+pub use self::step::{Applied, ApplyIter};
+#[cfg(all(feature = "toml", feature = "diff"))]
+pub use self::toml::diff_toml;
+#[cfg(feature = "toml")]
+pub use self::toml::{merge_toml, patch_toml};
+pub use self::truncate::Truncated;
+pub use self::typed::{patch_struct, InvalidValueError, PatchStructError};
+pub use self::watch::Watchers;
+pub use self::writer::PatchWriter;
+#[cfg(all(feature = "yaml", feature = "diff"))]
+pub use self::yaml::diff_yaml;
+#[cfg(feature = "yaml")]
+pub use self::yaml::{merge_yaml, patch_yaml};
 
 struct WriteAdapter<'a>(&'a mut dyn fmt::Write);
 
@@ -109,6 +215,50 @@ impl<'a> std::io::Write for WriteAdapter<'a> {
     }
 }
 
+/// Writer that only counts the bytes written into it.
+#[derive(Default)]
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
+
+/// Serializes the value with members of all objects sorted by key.
+struct Canonical<'a>(&'a Value);
+
+impl Serialize for Canonical<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{SerializeMap, SerializeSeq};
+
+        match self.0 {
+            Value::Array(arr) => {
+                let mut seq = serializer.serialize_seq(Some(arr.len()))?;
+                for item in arr {
+                    seq.serialize_element(&Canonical(item))?;
+                }
+                seq.end()
+            }
+            Value::Object(obj) => {
+                let mut entries: Vec<_> = obj.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, &Canonical(value))?;
+                }
+                map.end()
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
 macro_rules! impl_display {
     ($name:ident) => {
         impl Display for $name {
@@ -127,8 +277,14 @@ macro_rules! impl_display {
 }
 
 /// Representation of JSON Patch (list of patch operations)
-#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(
+    feature = "diesel",
+    derive(::diesel::expression::AsExpression, ::diesel::deserialize::FromSqlRow),
+    diesel(sql_type = ::diesel::sql_types::Json, sql_type = ::diesel::sql_types::Jsonb)
+)]
 pub struct Patch(pub Vec<PatchOperation>);
 
 impl_display!(Patch);
@@ -141,13 +297,558 @@ impl std::ops::Deref for Patch {
     }
 }
 
+impl IntoIterator for Patch {
+    type Item = PatchOperation;
+    type IntoIter = std::vec::IntoIter<PatchOperation>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Patch {
+    type Item = &'a PatchOperation;
+    type IntoIter = std::slice::Iter<'a, PatchOperation>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<PatchOperation> for Patch {
+    fn from_iter<T: IntoIterator<Item = PatchOperation>>(iter: T) -> Self {
+        Patch(iter.into_iter().collect())
+    }
+}
+
+impl Extend<PatchOperation> for Patch {
+    fn extend<T: IntoIterator<Item = PatchOperation>>(&mut self, iter: T) {
+        self.0.extend(iter)
+    }
+}
+
+impl From<Vec<PatchOperation>> for Patch {
+    fn from(ops: Vec<PatchOperation>) -> Patch {
+        Patch(ops)
+    }
+}
+
+impl std::str::FromStr for Patch {
+    type Err = ParsePatchError;
+
+    /// Parse JSON Patch document from its JSON text.
+    fn from_str(s: &str) -> Result<Patch, ParsePatchError> {
+        Ok(serde_json::from_str(s)?)
+    }
+}
+
+impl TryFrom<&str> for Patch {
+    type Error = ParsePatchError;
+
+    fn try_from(s: &str) -> Result<Patch, ParsePatchError> {
+        s.parse()
+    }
+}
+
+impl TryFrom<Value> for Patch {
+    type Error = ParsePatchError;
+
+    fn try_from(value: Value) -> Result<Patch, ParsePatchError> {
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl Patch {
+    /// Media type of JSON Patch documents, to be used as a `Content-Type` of requests.
+    pub const CONTENT_TYPE: &'static str = "application/json-patch+json";
+
+    /// Creates a patch with a single 'add' operation.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::{patch, Patch, PatchOperation};
+    /// use jsonptr::Pointer;
+    /// use serde_json::json;
+    ///
+    /// let mut doc = json!({ "title": "Hello!" });
+    /// let mut p = Patch::add(Pointer::from_static("/author").to_buf(), "John");
+    /// p.push(PatchOperation::remove(Pointer::from_static("/title").to_buf()));
+    /// patch(&mut doc, &p).unwrap();
+    /// assert_eq!(doc, json!({ "author": "John" }));
+    /// ```
+    pub fn add(path: impl Into<PointerBuf>, value: impl Into<Value>) -> Self {
+        Patch(vec![PatchOperation::add(path, value)])
+    }
+
+    /// Creates a patch with a single 'remove' operation.
+    pub fn remove(path: impl Into<PointerBuf>) -> Self {
+        Patch(vec![PatchOperation::remove(path)])
+    }
+
+    /// Creates a patch with a single 'replace' operation.
+    pub fn replace(path: impl Into<PointerBuf>, value: impl Into<Value>) -> Self {
+        Patch(vec![PatchOperation::replace(path, value)])
+    }
+
+    /// Creates a patch with a single 'move' operation (`move` itself is a reserved keyword).
+    pub fn mov(from: impl Into<PointerBuf>, path: impl Into<PointerBuf>) -> Self {
+        Patch(vec![PatchOperation::mov(from, path)])
+    }
+
+    /// Creates a patch with a single 'copy' operation.
+    pub fn copy(from: impl Into<PointerBuf>, path: impl Into<PointerBuf>) -> Self {
+        Patch(vec![PatchOperation::copy(from, path)])
+    }
+
+    /// Creates a patch with a single 'test' operation.
+    pub fn test(path: impl Into<PointerBuf>, value: impl Into<Value>) -> Self {
+        Patch(vec![PatchOperation::test(path, value)])
+    }
+
+    /// Creates a patch replacing the value at `path` with `new` only if it is equal to
+    /// `expected`: a 'test' operation followed by a 'replace' operation. See also
+    /// [`compare_and_swap`].
+    pub fn compare_and_swap(
+        path: impl Into<PointerBuf>,
+        expected: impl Into<Value>,
+        new: impl Into<Value>,
+    ) -> Self {
+        let path = path.into();
+        Patch(vec![
+            PatchOperation::test(path.clone(), expected),
+            PatchOperation::replace(path, new),
+        ])
+    }
+
+    /// Appends the operation to the end of the patch.
+    pub fn push(&mut self, op: PatchOperation) {
+        self.0.push(op);
+    }
+
+    /// Returns a copy of this patch with every `path` and `from` pointer re-rooted under the
+    /// given `prefix`. Useful when a patch computed against a sub-document needs to be applied
+    /// to a larger document embedding it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::Patch;
+    /// use jsonptr::Pointer;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "add", "path": "/name", "value": "Andrew" },
+    /// ])).unwrap();
+    ///
+    /// let p = p.with_prefix(Pointer::from_static("/users/0"));
+    /// assert_eq!(p, from_value(json!([
+    ///   { "op": "add", "path": "/users/0/name", "value": "Andrew" },
+    /// ])).unwrap());
+    /// ```
+    pub fn with_prefix(&self, prefix: &Pointer) -> Patch {
+        self.iter()
+            .map(|op| op.map_pointers(|ptr| Some(prefix.concat(ptr))).unwrap())
+            .collect()
+    }
+
+    /// Returns a copy of this patch with the given `prefix` removed from every `path` and `from`
+    /// pointer. Returns `None` if any of the pointers is not located at or under `prefix`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::Patch;
+    /// use jsonptr::Pointer;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "add", "path": "/users/0/name", "value": "Andrew" },
+    /// ])).unwrap();
+    ///
+    /// let stripped = p.strip_prefix(Pointer::from_static("/users/0")).unwrap();
+    /// assert_eq!(stripped, from_value(json!([
+    ///   { "op": "add", "path": "/name", "value": "Andrew" },
+    /// ])).unwrap());
+    ///
+    /// assert!(p.strip_prefix(Pointer::from_static("/users/1")).is_none());
+    /// ```
+    pub fn strip_prefix(&self, prefix: &Pointer) -> Option<Patch> {
+        self.iter()
+            .map(|op| op.map_pointers(|ptr| strip_pointer_prefix(ptr, prefix)))
+            .collect()
+    }
+
+    /// Returns the size in bytes of the compact JSON representation of this patch (as produced
+    /// by `serde_json::to_vec` or by `Display`). The size is computed without allocating the
+    /// serialized representation. Sizes of individual operations are available via
+    /// [`PatchOperation::estimated_size`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::Patch;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "add", "path": "/0/happy", "value": true },
+    ///   { "op": "remove", "path": "/1" },
+    /// ])).unwrap();
+    ///
+    /// assert_eq!(p.estimated_size(), p.to_string().len());
+    /// let sizes: Vec<usize> = p.iter().map(|op| op.estimated_size()).collect();
+    /// assert_eq!(sizes, [43, 27]);
+    /// ```
+    pub fn estimated_size(&self) -> usize {
+        let separators = self.len().saturating_sub(1);
+        let operations: usize = self.iter().map(PatchOperation::estimated_size).sum();
+        "[]".len() + separators + operations
+    }
+
+    /// Serializes the patch into a canonical JSON string: members of all objects (both operations
+    /// and values inside them) are sorted by key and no insignificant whitespace is emitted. Equal
+    /// patches always produce identical strings, regardless of the order in which members of
+    /// their values were inserted, so the output is suitable for hashing and signing.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::Patch;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let p: Patch = from_value(json!([
+    ///   { "path": "/a", "value": { "b": 1, "a": [{ "d": null, "c": true }] }, "op": "add" },
+    /// ])).unwrap();
+    ///
+    /// assert_eq!(
+    ///   p.to_canonical_string(),
+    ///   r#"[{"op":"add","path":"/a","value":{"a":[{"c":true,"d":null}],"b":1}}]"#
+    /// );
+    /// ```
+    pub fn to_canonical_string(&self) -> String {
+        let value = serde_json::to_value(self).expect("patch is always serializable");
+        serde_json::to_string(&Canonical(&value)).expect("value is always serializable")
+    }
+
+    /// Checks if applying this patch could change the value at or under the given pointer.
+    ///
+    /// The check is conservative since it does not have access to the document: the value is
+    /// considered affected if any of the operations modifies a location at, under or above the
+    /// pointer. Operations that insert or remove array elements (`add`, `remove`, `move`, `copy`)
+    /// are also considered to affect all the following elements of the same array, since their
+    /// indices shift. Any token that looks like an array index is treated as such.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::Patch;
+    /// use jsonptr::Pointer;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "remove", "path": "/users/1" },
+    ///   { "op": "replace", "path": "/settings", "value": {} },
+    /// ])).unwrap();
+    ///
+    /// assert!(p.affects(Pointer::from_static("/users/2/name")));
+    /// assert!(p.affects(Pointer::from_static("/settings/theme")));
+    /// assert!(p.affects(Pointer::from_static("")));
+    /// assert!(!p.affects(Pointer::from_static("/users/0/name")));
+    /// ```
+    pub fn affects(&self, pointer: &Pointer) -> bool {
+        patch_affects(self, pointer)
+    }
+
+    /// Checks that the patch is well-formed, independent of any document, and returns all the
+    /// problems found. Pointer syntax is already enforced when a patch is deserialized; this
+    /// additionally checks that:
+    ///
+    /// * `-` (the index past the end of an array) is only used as the last token of the `path`
+    ///   of `add`, `move` and `copy` operations;
+    /// * the whole document (empty pointer) is not removed or moved;
+    /// * values are not moved into their own children.
+    ///
+    /// Problems which depend on the document (like a missing location or an array index being
+    /// out of bounds) are only detected when the patch is applied.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::{Patch, PatchErrorKind};
+    /// use serde_json::{from_value, json};
+    ///
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "remove", "path": "/tags/-" },
+    ///   { "op": "add", "path": "/tags/-", "value": "hello" },
+    ///   { "op": "move", "from": "/a", "path": "/a/b" },
+    /// ])).unwrap();
+    ///
+    /// let errors = p.validate().unwrap_err();
+    /// assert_eq!(errors.len(), 2);
+    /// assert_eq!(errors[0].operation, 0);
+    /// assert!(matches!(errors[0].kind, PatchErrorKind::InvalidPointer));
+    /// assert_eq!(errors[1].operation, 2);
+    /// assert!(matches!(errors[1].kind, PatchErrorKind::CannotMoveInsideItself));
+    /// ```
+    pub fn validate(&self) -> Result<(), Vec<PatchError>> {
+        let errors: Vec<PatchError> = self
+            .iter()
+            .enumerate()
+            .filter_map(|(operation, op)| {
+                validate_operation(op).map(|kind| translate_error(kind, operation, op))
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Converts this patch into an equivalent JSON Merge Patch (RFC 7396), if possible.
+    ///
+    /// Conversion succeeds only if the patch consists of `add`, `replace` and `remove`
+    /// operations on object members: array operations (any token that looks like an array
+    /// index), `move`, `copy` and `test` operations, operations on the whole document and
+    /// operations that set a `null` value cannot be expressed as a merge patch. Neither can
+    /// operations that set an object value, since merge patches merge objects into the existing
+    /// ones rather than replacing them. Note that merge patches do not verify that the target
+    /// exists.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::{MergePatch, Patch};
+    /// use serde_json::{from_value, json};
+    ///
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "replace", "path": "/title", "value": "Hello!" },
+    ///   { "op": "remove", "path": "/author/familyName" },
+    /// ])).unwrap();
+    ///
+    /// assert_eq!(p.try_into_merge_patch(), Some(MergePatch(json!({
+    ///   "title": "Hello!",
+    ///   "author": { "familyName": null }
+    /// }))));
+    /// ```
+    pub fn try_into_merge_patch(&self) -> Option<MergePatch> {
+        let mut result = Map::new();
+        for op in self.iter() {
+            let value = match op {
+                PatchOperation::Add(AddOperation { value, .. })
+                | PatchOperation::Replace(ReplaceOperation { value, .. })
+                    if !value.is_null() && !value.is_object() =>
+                {
+                    value.clone()
+                }
+                PatchOperation::Remove(_) => Value::Null,
+                _ => return None,
+            };
+
+            let (parent, last) = op.path().split_back()?;
+            let mut target = &mut result;
+            for token in parent.tokens().chain(Some(last.clone())) {
+                if token.to_index().is_ok() {
+                    return None;
+                }
+            }
+            for token in parent.tokens() {
+                target = target
+                    .entry(token.decoded())
+                    .or_insert_with(|| Value::Object(Map::new()))
+                    .as_object_mut()?;
+            }
+            target.insert(last.decoded().into_owned(), value);
+        }
+        Some(MergePatch(Value::Object(result)))
+    }
+
+    /// Returns an iterator over the locations modified by this patch: `path` of all operations
+    /// except `test`, and `from` of `move` operations.
+    pub fn affected_paths(&self) -> impl Iterator<Item = &Pointer> {
+        self.iter().flat_map(PatchOperation::modified_paths)
+    }
+
+    /// Counts the operations of this patch grouped by the first `depth` tokens of their `path`.
+    /// Operations with shorter paths are counted under their whole path, so the root is only a
+    /// key if some operation targets the whole document (or `depth` is zero).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::Patch;
+    /// use jsonptr::Pointer;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "replace", "path": "/spec/replicas", "value": 3 },
+    ///   { "op": "add", "path": "/spec/template/labels/app", "value": "web" },
+    ///   { "op": "remove", "path": "/metadata/annotations" },
+    /// ])).unwrap();
+    ///
+    /// let stats = p.stats_by_prefix(1);
+    /// assert_eq!(stats.len(), 2);
+    /// assert_eq!(stats[Pointer::from_static("/spec")], 2);
+    /// assert_eq!(stats[Pointer::from_static("/metadata")], 1);
+    /// ```
+    pub fn stats_by_prefix(&self, depth: usize) -> BTreeMap<PointerBuf, usize> {
+        let mut stats = BTreeMap::new();
+        for op in self.iter() {
+            let prefix = PointerBuf::from_tokens(op.path().tokens().take(depth));
+            *stats.entry(prefix).or_insert(0) += 1;
+        }
+        stats
+    }
+
+    /// Finds patterns which are valid, but are usually mistakes: duplicate operations, `remove`
+    /// followed by `add` at the same location, operations overwritten by later operations before
+    /// their result is used, and `test` operations checking values modified by the patch itself.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::{LintKind, Patch};
+    /// use serde_json::{from_value, json};
+    ///
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "add", "path": "/user/name", "value": "Andrew" },
+    ///   { "op": "replace", "path": "/user", "value": {} },
+    /// ])).unwrap();
+    ///
+    /// let warnings = p.lint();
+    /// assert_eq!(warnings.len(), 1);
+    /// assert_eq!(warnings[0].operation, 0);
+    /// assert_eq!(warnings[0].kind, LintKind::Overwritten { by: 1 });
+    /// ```
+    pub fn lint(&self) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+        for (idx, op) in self.iter().enumerate() {
+            let previous = &self.0[..idx];
+            if let Some(of) = previous.iter().position(|prev| prev == op) {
+                warnings.push(LintWarning::new(idx, LintKind::Duplicate { of }));
+                continue;
+            }
+
+            match op {
+                PatchOperation::Add(add) => {
+                    let removed = previous.iter().rposition(|prev| {
+                        matches!(prev, PatchOperation::Remove(remove) if remove.path == add.path)
+                    });
+                    if let Some(remove) = removed {
+                        let between = &previous[remove + 1..];
+                        if !between.iter().any(|op| op.touches(&add.path)) {
+                            warnings
+                                .push(LintWarning::new(idx, LintKind::RemoveThenAdd { remove }));
+                        }
+                    }
+                }
+                PatchOperation::Test(test) => {
+                    let mutation = previous.iter().rposition(|prev| {
+                        let shifts = !matches!(prev, PatchOperation::Replace(_));
+                        prev.modified_paths()
+                            .any(|location| location_affects(location, shifts, &test.path))
+                    });
+                    if let Some(mutation) = mutation {
+                        warnings.push(LintWarning::new(
+                            idx,
+                            LintKind::TestAfterMutation { mutation },
+                        ));
+                    }
+                }
+                PatchOperation::Replace(ReplaceOperation { path, .. })
+                | PatchOperation::Remove(RemoveOperation { path }) => {
+                    for (prev_idx, prev) in previous.iter().enumerate() {
+                        let Some(target) = prev.target() else {
+                            continue;
+                        };
+                        if strip_pointer_prefix(target, path).is_none() {
+                            continue;
+                        }
+                        // Not overwritten if the result is used before
+                        let between = &previous[prev_idx + 1..];
+                        if !between.iter().any(|op| op.reads(target)) {
+                            let kind = LintKind::Overwritten { by: idx };
+                            warnings.push(LintWarning::new(prev_idx, kind));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        warnings.sort_by_key(|warning| warning.operation);
+        warnings
+    }
+}
+
+/// Suspicious pattern in a patch found by [`Patch::lint`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LintWarning {
+    /// Index of the suspicious operation.
+    pub operation: usize,
+    /// What is suspicious about the operation.
+    pub kind: LintKind,
+}
+
+impl LintWarning {
+    fn new(operation: usize, kind: LintKind) -> LintWarning {
+        LintWarning { operation, kind }
+    }
+}
+
+impl Display for LintWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "operation '/{}' ", self.operation)?;
+        match self.kind {
+            LintKind::Duplicate { of } => write!(f, "duplicates operation '/{of}'"),
+            LintKind::RemoveThenAdd { remove } => {
+                write!(f, "adds the value removed by operation '/{remove}'")
+            }
+            LintKind::Overwritten { by } => write!(f, "is overwritten by operation '/{by}'"),
+            LintKind::TestAfterMutation { mutation } => {
+                write!(f, "tests the value modified by operation '/{mutation}'")
+            }
+        }
+    }
+}
+
+/// Kind of the suspicious pattern, see [`LintWarning`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum LintKind {
+    /// Operation is identical to an earlier operation.
+    Duplicate {
+        /// Index of the earlier operation.
+        of: usize,
+    },
+    /// `add` operation at the location removed by an earlier `remove`, which is usually meant to
+    /// be a `replace`.
+    RemoveThenAdd {
+        /// Index of the `remove` operation.
+        remove: usize,
+    },
+    /// Result of the operation is replaced or removed by a later operation on the same location
+    /// (or its parent), without being used in between.
+    Overwritten {
+        /// Index of the later operation.
+        by: usize,
+    },
+    /// `test` operation checks the value modified by an earlier operation of the same patch,
+    /// rather than the original document.
+    TestAfterMutation {
+        /// Index of the latest operation modifying the tested value.
+        mutation: usize,
+    },
+}
+
 /// JSON Patch 'add' operation representation
-#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AddOperation {
     /// JSON-Pointer value [RFC6901](https://tools.ietf.org/html/rfc6901) that references a location
     /// within the target document where the operation is performed.
     #[cfg_attr(feature = "utoipa", schema(value_type = String))]
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "pointer_schema"))]
     pub path: PointerBuf,
     /// Value to add to the target location.
     pub value: Value,
@@ -155,25 +856,46 @@ pub struct AddOperation {
 
 impl_display!(AddOperation);
 
+impl AddOperation {
+    /// Creates an 'add' operation.
+    pub fn new(path: impl Into<PointerBuf>, value: impl Into<Value>) -> Self {
+        AddOperation {
+            path: path.into(),
+            value: value.into(),
+        }
+    }
+}
+
 /// JSON Patch 'remove' operation representation
-#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct RemoveOperation {
     /// JSON-Pointer value [RFC6901](https://tools.ietf.org/html/rfc6901) that references a location
     /// within the target document where the operation is performed.
     #[cfg_attr(feature = "utoipa", schema(value_type = String))]
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "pointer_schema"))]
     pub path: PointerBuf,
 }
 
 impl_display!(RemoveOperation);
 
+impl RemoveOperation {
+    /// Creates a 'remove' operation.
+    pub fn new(path: impl Into<PointerBuf>) -> Self {
+        RemoveOperation { path: path.into() }
+    }
+}
+
 /// JSON Patch 'replace' operation representation
-#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ReplaceOperation {
     /// JSON-Pointer value [RFC6901](https://tools.ietf.org/html/rfc6901) that references a location
     /// within the target document where the operation is performed.
     #[cfg_attr(feature = "utoipa", schema(value_type = String))]
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "pointer_schema"))]
     pub path: PointerBuf,
     /// Value to replace with.
     pub value: Value,
@@ -181,45 +903,83 @@ pub struct ReplaceOperation {
 
 impl_display!(ReplaceOperation);
 
+impl ReplaceOperation {
+    /// Creates a 'replace' operation.
+    pub fn new(path: impl Into<PointerBuf>, value: impl Into<Value>) -> Self {
+        ReplaceOperation {
+            path: path.into(),
+            value: value.into(),
+        }
+    }
+}
+
 /// JSON Patch 'move' operation representation
-#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct MoveOperation {
     /// JSON-Pointer value [RFC6901](https://tools.ietf.org/html/rfc6901) that references a location
     /// to move value from.
     #[cfg_attr(feature = "utoipa", schema(value_type = String))]
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "pointer_schema"))]
     pub from: PointerBuf,
     /// JSON-Pointer value [RFC6901](https://tools.ietf.org/html/rfc6901) that references a location
     /// within the target document where the operation is performed.
     #[cfg_attr(feature = "utoipa", schema(value_type = String))]
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "pointer_schema"))]
     pub path: PointerBuf,
 }
 
 impl_display!(MoveOperation);
 
+impl MoveOperation {
+    /// Creates a 'move' operation.
+    pub fn new(from: impl Into<PointerBuf>, path: impl Into<PointerBuf>) -> Self {
+        MoveOperation {
+            from: from.into(),
+            path: path.into(),
+        }
+    }
+}
+
 /// JSON Patch 'copy' operation representation
-#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CopyOperation {
     /// JSON-Pointer value [RFC6901](https://tools.ietf.org/html/rfc6901) that references a location
     /// to copy value from.
     #[cfg_attr(feature = "utoipa", schema(value_type = String))]
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "pointer_schema"))]
     pub from: PointerBuf,
     /// JSON-Pointer value [RFC6901](https://tools.ietf.org/html/rfc6901) that references a location
     /// within the target document where the operation is performed.
     #[cfg_attr(feature = "utoipa", schema(value_type = String))]
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "pointer_schema"))]
     pub path: PointerBuf,
 }
 
 impl_display!(CopyOperation);
 
+impl CopyOperation {
+    /// Creates a 'copy' operation.
+    pub fn new(from: impl Into<PointerBuf>, path: impl Into<PointerBuf>) -> Self {
+        CopyOperation {
+            from: from.into(),
+            path: path.into(),
+        }
+    }
+}
+
 /// JSON Patch 'test' operation representation
-#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct TestOperation {
     /// JSON-Pointer value [RFC6901](https://tools.ietf.org/html/rfc6901) that references a location
     /// within the target document where the operation is performed.
     #[cfg_attr(feature = "utoipa", schema(value_type = String))]
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "pointer_schema"))]
     pub path: PointerBuf,
     /// Value to test against.
     pub value: Value,
@@ -227,9 +987,20 @@ pub struct TestOperation {
 
 impl_display!(TestOperation);
 
+impl TestOperation {
+    /// Creates a 'test' operation.
+    pub fn new(path: impl Into<PointerBuf>, value: impl Into<Value>) -> Self {
+        TestOperation {
+            path: path.into(),
+            value: value.into(),
+        }
+    }
+}
+
 /// JSON Patch single patch operation
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(tag = "op")]
 #[serde(rename_all = "lowercase")]
 pub enum PatchOperation {
@@ -250,6 +1021,36 @@ pub enum PatchOperation {
 impl_display!(PatchOperation);
 
 impl PatchOperation {
+    /// Creates an 'add' operation.
+    pub fn add(path: impl Into<PointerBuf>, value: impl Into<Value>) -> Self {
+        Self::Add(AddOperation::new(path, value))
+    }
+
+    /// Creates a 'remove' operation.
+    pub fn remove(path: impl Into<PointerBuf>) -> Self {
+        Self::Remove(RemoveOperation::new(path))
+    }
+
+    /// Creates a 'replace' operation.
+    pub fn replace(path: impl Into<PointerBuf>, value: impl Into<Value>) -> Self {
+        Self::Replace(ReplaceOperation::new(path, value))
+    }
+
+    /// Creates a 'move' operation (`move` itself is a reserved keyword).
+    pub fn mov(from: impl Into<PointerBuf>, path: impl Into<PointerBuf>) -> Self {
+        Self::Move(MoveOperation::new(from, path))
+    }
+
+    /// Creates a 'copy' operation.
+    pub fn copy(from: impl Into<PointerBuf>, path: impl Into<PointerBuf>) -> Self {
+        Self::Copy(CopyOperation::new(from, path))
+    }
+
+    /// Creates a 'test' operation.
+    pub fn test(path: impl Into<PointerBuf>, value: impl Into<Value>) -> Self {
+        Self::Test(TestOperation::new(path, value))
+    }
+
     /// Returns a reference to the path the operation applies to.
     pub fn path(&self) -> &Pointer {
         match self {
@@ -261,6 +1062,120 @@ impl PatchOperation {
             Self::Test(op) => &op.path,
         }
     }
+
+    /// Returns a reference to the value carried by 'add', 'replace' and 'test' operations.
+    pub fn value(&self) -> Option<&Value> {
+        match self {
+            Self::Add(op) => Some(&op.value),
+            Self::Replace(op) => Some(&op.value),
+            Self::Test(op) => Some(&op.value),
+            Self::Remove(_) | Self::Move(_) | Self::Copy(_) => None,
+        }
+    }
+
+    /// Returns a reference to the `from` path of 'move' and 'copy' operations.
+    pub fn from(&self) -> Option<&Pointer> {
+        match self {
+            Self::Move(op) => Some(&op.from),
+            Self::Copy(op) => Some(&op.from),
+            Self::Add(_) | Self::Remove(_) | Self::Replace(_) | Self::Test(_) => None,
+        }
+    }
+
+    /// Returns the kind of the operation.
+    pub fn kind(&self) -> OpKind {
+        match self {
+            Self::Add(_) => OpKind::Add,
+            Self::Remove(_) => OpKind::Remove,
+            Self::Replace(_) => OpKind::Replace,
+            Self::Move(_) => OpKind::Move,
+            Self::Copy(_) => OpKind::Copy,
+            Self::Test(_) => OpKind::Test,
+        }
+    }
+
+    /// Returns the name of the operation as it appears in the `op` member.
+    pub fn op_name(&self) -> &'static str {
+        self.kind().name()
+    }
+
+    /// Returns the size in bytes of the compact JSON representation of this operation.
+    pub fn estimated_size(&self) -> usize {
+        let mut counter = ByteCounter::default();
+        serde_json::to_writer(&mut counter, self).expect("byte counter never fails");
+        counter.0
+    }
+
+    /// Returns the location where the operation puts the value, if any.
+    fn target(&self) -> Option<&Pointer> {
+        match self {
+            Self::Add(AddOperation { path, .. })
+            | Self::Replace(ReplaceOperation { path, .. })
+            | Self::Move(MoveOperation { path, .. })
+            | Self::Copy(CopyOperation { path, .. }) => Some(path),
+            Self::Remove(_) | Self::Test(_) => None,
+        }
+    }
+
+    /// Checks if the operation reads the value at the location (or its parts).
+    fn reads(&self, location: &Pointer) -> bool {
+        let read = match self {
+            Self::Test(TestOperation { path: read, .. })
+            | Self::Move(MoveOperation { from: read, .. })
+            | Self::Copy(CopyOperation { from: read, .. }) => read,
+            _ => return false,
+        };
+        location_affects(location, false, read)
+    }
+
+    /// Checks if the operation reads or modifies the value at the location (or its parts).
+    fn touches(&self, location: &Pointer) -> bool {
+        self.reads(location)
+            || self
+                .modified_paths()
+                .any(|path| location_affects(path, false, location))
+    }
+
+    /// Returns locations which are modified when this operation is applied.
+    fn modified_paths(&self) -> impl Iterator<Item = &Pointer> {
+        let (first, second) = match self {
+            Self::Add(op) => (Some(&op.path), None),
+            Self::Remove(op) => (Some(&op.path), None),
+            Self::Replace(op) => (Some(&op.path), None),
+            Self::Move(op) => (Some(&op.from), Some(&op.path)),
+            Self::Copy(op) => (Some(&op.path), None),
+            Self::Test(_) => (None, None),
+        };
+        first.into_iter().chain(second).map(|path| path.as_ptr())
+    }
+
+    /// Rebuilds the operation with every `path` and `from` pointer passed through `f`. Returns
+    /// `None` if `f` returns `None` for any of them.
+    fn map_pointers(&self, f: impl Fn(&Pointer) -> Option<PointerBuf>) -> Option<PatchOperation> {
+        Some(match self {
+            Self::Add(op) => Self::Add(AddOperation {
+                path: f(&op.path)?,
+                value: op.value.clone(),
+            }),
+            Self::Remove(op) => Self::Remove(RemoveOperation { path: f(&op.path)? }),
+            Self::Replace(op) => Self::Replace(ReplaceOperation {
+                path: f(&op.path)?,
+                value: op.value.clone(),
+            }),
+            Self::Move(op) => Self::Move(MoveOperation {
+                from: f(&op.from)?,
+                path: f(&op.path)?,
+            }),
+            Self::Copy(op) => Self::Copy(CopyOperation {
+                from: f(&op.from)?,
+                path: f(&op.path)?,
+            }),
+            Self::Test(op) => Self::Test(TestOperation {
+                path: f(&op.path)?,
+                value: op.value.clone(),
+            }),
+        })
+    }
 }
 
 impl Default for PatchOperation {
@@ -269,8 +1184,189 @@ impl Default for PatchOperation {
     }
 }
 
+/// Operations are totally ordered: first by `path`, then by the kind of the operation (in the
+/// order of [`OpKind`] variants), then by `from` and finally by `value`. Values are compared by
+/// type first (`null` < booleans < numbers < strings < arrays < objects), then by their
+/// content; objects are compared as lists of entries sorted by key.
+impl Ord for PatchOperation {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.path()
+            .cmp(other.path())
+            .then_with(|| self.kind().cmp(&other.kind()))
+            .then_with(|| self.from().cmp(&other.from()))
+            .then_with(|| match (self.value(), other.value()) {
+                (Some(left), Some(right)) => cmp_values(left, right),
+                (left, right) => left.is_some().cmp(&right.is_some()),
+            })
+    }
+}
+
+impl PartialOrd for PatchOperation {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Total order over JSON values, consistent with their equality.
+fn cmp_values(left: &Value, right: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+
+    match (left, right) {
+        (Value::Bool(left), Value::Bool(right)) => left.cmp(right),
+        (Value::Number(left), Value::Number(right)) => cmp_numbers(left, right),
+        (Value::String(left), Value::String(right)) => left.cmp(right),
+        (Value::Array(left), Value::Array(right)) => left
+            .iter()
+            .zip(right)
+            .map(|(left, right)| cmp_values(left, right))
+            .find(|ord| ord.is_ne())
+            .unwrap_or_else(|| left.len().cmp(&right.len())),
+        (Value::Object(left), Value::Object(right)) => {
+            let mut left: Vec<_> = left.iter().collect();
+            let mut right: Vec<_> = right.iter().collect();
+            left.sort_by(|a, b| a.0.cmp(b.0));
+            right.sort_by(|a, b| a.0.cmp(b.0));
+            left.iter()
+                .zip(&right)
+                .map(|((lk, lv), (rk, rv))| lk.cmp(rk).then_with(|| cmp_values(lv, rv)))
+                .find(|ord| ord.is_ne())
+                .unwrap_or_else(|| left.len().cmp(&right.len()))
+        }
+        (left, right) => rank(left).cmp(&rank(right)),
+    }
+}
+
+fn cmp_numbers(left: &serde_json::Number, right: &serde_json::Number) -> Ordering {
+    if left == right {
+        return Ordering::Equal;
+    }
+    // Numerically equal numbers with different representation (like `1` and `1.0`) are not
+    // equal as JSON values, so they need to be ordered somehow.
+    cmp_numeric(left, right).then_with(|| left.to_string().cmp(&right.to_string()))
+}
+
+fn cmp_numeric(left: &serde_json::Number, right: &serde_json::Number) -> Ordering {
+    if let (Some(left), Some(right)) = (left.as_u64(), right.as_u64()) {
+        left.cmp(&right)
+    } else if let (Some(left), Some(right)) = (left.as_i64(), right.as_i64()) {
+        left.cmp(&right)
+    } else {
+        let left = left.as_f64().unwrap_or(f64::NAN);
+        let right = right.as_f64().unwrap_or(f64::NAN);
+        left.partial_cmp(&right).unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Policy of comparing JSON numbers, used by `test` operations and by diff.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub enum NumberEquality {
+    /// Numbers are equal if they are equal as `serde_json` values: `1` and `1.0` are different,
+    /// and with `arbitrary_precision` feature of `serde_json`, `1.0` and `1e0` are different, too.
+    #[default]
+    Exact,
+    /// Numbers are equal if they have the same numeric value, regardless of the representation.
+    Numeric,
+    /// Numbers are equal if they differ by no more than the given value.
+    Epsilon(f64),
+}
+
+impl NumberEquality {
+    /// Compare two JSON values, recursively, using this policy for numbers.
+    pub fn values_equal(self, left: &Value, right: &Value) -> bool {
+        if self == NumberEquality::Exact {
+            return left == right;
+        }
+        match (left, right) {
+            (Value::Number(left), Value::Number(right)) => self.numbers_equal(left, right),
+            (Value::Array(left), Value::Array(right)) => {
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .zip(right)
+                        .all(|(left, right)| self.values_equal(left, right))
+            }
+            (Value::Object(left), Value::Object(right)) => {
+                left.len() == right.len()
+                    && left.iter().all(|(key, left)| {
+                        right
+                            .get(key)
+                            .is_some_and(|right| self.values_equal(left, right))
+                    })
+            }
+            (left, right) => left == right,
+        }
+    }
+
+    fn numbers_equal(self, left: &serde_json::Number, right: &serde_json::Number) -> bool {
+        if left == right {
+            return true;
+        }
+        match self {
+            NumberEquality::Exact => false,
+            NumberEquality::Numeric => cmp_numeric(left, right) == Ordering::Equal,
+            NumberEquality::Epsilon(epsilon) => {
+                cmp_numeric(left, right) == Ordering::Equal
+                    || matches!((left.as_f64(), right.as_f64()),
+                        (Some(left), Some(right)) if (left - right).abs() <= epsilon)
+            }
+        }
+    }
+}
+
+/// Kind of a JSON Patch operation, without its arguments.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum OpKind {
+    /// 'add' operation
+    Add,
+    /// 'remove' operation
+    Remove,
+    /// 'replace' operation
+    Replace,
+    /// 'move' operation
+    Move,
+    /// 'copy' operation
+    Copy,
+    /// 'test' operation
+    Test,
+}
+
+impl OpKind {
+    /// Returns the name of the operation as it appears in the `op` member.
+    pub fn name(self) -> &'static str {
+        match self {
+            OpKind::Add => "add",
+            OpKind::Remove => "remove",
+            OpKind::Replace => "replace",
+            OpKind::Move => "move",
+            OpKind::Copy => "copy",
+            OpKind::Test => "test",
+        }
+    }
+}
+
+impl Display for OpKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 /// This type represents all possible errors that can occur when applying JSON patch
-#[derive(Debug, Error)]
+#[derive(Debug, Error, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[non_exhaustive]
 pub enum PatchErrorKind {
     /// `test` operation failed because values did not match.
@@ -285,53 +1381,148 @@ pub enum PatchErrorKind {
     /// `move` operation failed because target is inside the `from` location.
     #[error("cannot move the value inside itself")]
     CannotMoveInsideItself,
+    /// Patched document was rejected by [`PatchOptions::validator`].
+    #[error("document is invalid")]
+    ValidationFailed,
+    /// Value of the operation cannot be represented in the patched document, for example, `null`
+    /// in a TOML document.
+    #[error("value is not supported by the document format")]
+    UnsupportedValue,
+    /// Value at `path` is of a type the operation cannot be applied to, for example, appending
+    /// to a string in the [`ext`] operations when the value is a number.
+    #[error("value has unexpected type")]
+    UnexpectedType,
+    /// Value contains a placeholder with no resolver in [`PatchOptions::placeholders`]. `path`
+    /// is the location of the placeholder.
+    #[error("unknown placeholder")]
+    UnknownPlaceholder,
 }
 
 /// This type represents all possible errors that can occur when applying JSON patch
-#[derive(Debug, Error)]
+#[derive(Debug, Error, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[error("operation '/{operation}' failed at path '{path}': {kind}")]
 #[non_exhaustive]
 pub struct PatchError {
     /// Index of the operation that has failed.
     pub operation: usize,
+    /// Kind of the operation that has failed.
+    pub op: OpKind,
     /// `path` of the operation.
+    #[cfg_attr(feature = "utoipa", schema(value_type = String))]
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "pointer_schema"))]
     pub path: PointerBuf,
+    /// `from` of the operation, for `move` and `copy` operations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "utoipa", schema(value_type = Option<String>))]
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(default, schema_with = "pointer_schema")
+    )]
+    pub from: Option<PointerBuf>,
     /// Kind of the error.
     pub kind: PatchErrorKind,
 }
 
-fn translate_error(kind: PatchErrorKind, operation: usize, path: &Pointer) -> PatchError {
-    PatchError {
+impl PatchError {
+    /// Returns the pointer that was invalid: `from` for [`PatchErrorKind::InvalidFromPointer`]
+    /// and `path` for [`PatchErrorKind::InvalidPointer`] errors.
+    pub fn invalid_pointer(&self) -> Option<&Pointer> {
+        match self.kind {
+            PatchErrorKind::InvalidPointer => Some(&self.path),
+            PatchErrorKind::InvalidFromPointer => self.from.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// Schema of a JSON Pointer: a string in the RFC 6901 format.
+#[cfg(feature = "schemars")]
+fn pointer_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+    schemars::schema::SchemaObject {
+        instance_type: Some(schemars::schema::InstanceType::String.into()),
+        format: Some("json-pointer".to_owned()),
+        ..Default::default()
+    }
+    .into()
+}
+
+fn translate_error(kind: PatchErrorKind, operation: usize, op: &PatchOperation) -> PatchError {
+    operation_error(kind, operation, op.kind(), op.path(), op.from())
+}
+
+fn operation_error(
+    kind: PatchErrorKind,
+    operation: usize,
+    op: OpKind,
+    path: &Pointer,
+    from: Option<&Pointer>,
+) -> PatchError {
+    PatchError {
         operation,
+        op,
         path: path.to_owned(),
+        from: from.map(Pointer::to_buf),
         kind,
     }
 }
 
-fn unescape(s: &str) -> Cow<str> {
-    if s.contains('~') {
-        Cow::Owned(s.replace("~1", "/").replace("~0", "~"))
+// Unlike `Pointer::strip_prefix`, only strips the prefix at a token boundary (`/ab` is not
+// under `/a`).
+fn strip_pointer_prefix(ptr: &Pointer, prefix: &Pointer) -> Option<PointerBuf> {
+    let rest = ptr.as_str().strip_prefix(prefix.as_str())?;
+    if rest.is_empty() || rest.starts_with('/') {
+        PointerBuf::parse(rest).ok()
     } else {
-        Cow::Borrowed(s)
+        None
     }
 }
 
-fn parse_index(str: &str, len: usize) -> Result<usize, PatchErrorKind> {
-    // RFC 6901 prohibits leading zeroes in index
-    if (str.starts_with('0') && str.len() != 1) || str.starts_with('+') {
-        return Err(PatchErrorKind::InvalidPointer);
+// Checks if applying the patch could change the value at or under the `pointer`, see
+// `Patch::affects`.
+fn patch_affects(patch: &[PatchOperation], pointer: &Pointer) -> bool {
+    patch.iter().any(|op| {
+        let shifts = !matches!(op, PatchOperation::Replace(_));
+        op.modified_paths()
+            .any(|location| location_affects(location, shifts, pointer))
+    })
+}
+
+// Checks if a modification at the `location` could affect the value at the `pointer`. If
+// `shifts` is set, the modification could insert or remove an array element, shifting the
+// elements after it.
+fn location_affects(location: &Pointer, shifts: bool, pointer: &Pointer) -> bool {
+    if strip_pointer_prefix(pointer, location).is_some()
+        || strip_pointer_prefix(location, pointer).is_some()
+    {
+        return true;
     }
-    match str.parse::<usize>() {
-        Ok(index) if index < len => Ok(index),
-        _ => Err(PatchErrorKind::InvalidPointer),
+    if !shifts {
+        return false;
+    }
+    let Some((parent, last)) = location.split_back() else {
+        return false;
+    };
+    let Some(rest) = strip_pointer_prefix(pointer, parent) else {
+        return false;
+    };
+    let Some(Ok(index)) = rest.first().map(|token| token.to_index()) else {
+        return false;
+    };
+    match (last.to_index(), index) {
+        (Ok(Index::Next), _) => true,
+        (Ok(Index::Num(shifted)), Index::Num(index)) => index >= shifted,
+        _ => false,
     }
 }
 
-fn split_pointer(pointer: &str) -> Result<(&str, &str), PatchErrorKind> {
-    pointer
-        .rfind('/')
-        .ok_or(PatchErrorKind::InvalidPointer)
-        .map(|idx| (&pointer[0..idx], &pointer[idx + 1..]))
+fn parse_index(str: &str, len: usize) -> Result<usize, PatchErrorKind> {
+    pointer::parse_index(str, len).ok_or(PatchErrorKind::InvalidPointer)
+}
+
+fn split_pointer(path: &str) -> Result<(&str, &str), PatchErrorKind> {
+    pointer::split_last(path).ok_or(PatchErrorKind::InvalidPointer)
 }
 
 fn add(doc: &mut Value, path: &str, value: Value) -> Result<Option<Value>, PatchErrorKind> {
@@ -345,7 +1536,9 @@ fn add(doc: &mut Value, path: &str, value: Value) -> Result<Option<Value>, Patch
         .ok_or(PatchErrorKind::InvalidPointer)?;
 
     match *parent {
-        Value::Object(ref mut obj) => Ok(obj.insert(unescape(last_unescaped).into_owned(), value)),
+        Value::Object(ref mut obj) => {
+            Ok(obj.insert(pointer::unescape(last_unescaped).into_owned(), value))
+        }
         Value::Array(ref mut arr) if last_unescaped == "-" => {
             arr.push(value);
             Ok(None)
@@ -359,18 +1552,27 @@ fn add(doc: &mut Value, path: &str, value: Value) -> Result<Option<Value>, Patch
     }
 }
 
-fn remove(doc: &mut Value, path: &str, allow_last: bool) -> Result<Value, PatchErrorKind> {
+// With `preserve_order`, keep the order of the remaining members.
+fn remove_member(obj: &mut Map<String, Value>, key: &str) -> Option<Value> {
+    #[cfg(feature = "preserve_order")]
+    return obj.shift_remove(key);
+    #[cfg(not(feature = "preserve_order"))]
+    return obj.remove(key);
+}
+
+fn remove(doc: &mut Value, path: &str) -> Result<Value, PatchErrorKind> {
     let (parent, last_unescaped) = split_pointer(path)?;
     let parent = doc
         .pointer_mut(parent)
         .ok_or(PatchErrorKind::InvalidPointer)?;
 
     match *parent {
-        Value::Object(ref mut obj) => match obj.remove(unescape(last_unescaped).as_ref()) {
-            None => Err(PatchErrorKind::InvalidPointer),
-            Some(val) => Ok(val),
-        },
-        Value::Array(ref mut arr) if allow_last && last_unescaped == "-" => Ok(arr.pop().unwrap()),
+        Value::Object(ref mut obj) => {
+            match remove_member(obj, pointer::unescape(last_unescaped).as_ref()) {
+                None => Err(PatchErrorKind::InvalidPointer),
+                Some(val) => Ok(val),
+            }
+        }
         Value::Array(ref mut arr) => {
             let idx = parse_index(last_unescaped, arr.len())?;
             Ok(arr.remove(idx))
@@ -386,17 +1588,12 @@ fn replace(doc: &mut Value, path: &str, value: Value) -> Result<Value, PatchErro
     Ok(std::mem::replace(target, value))
 }
 
-fn mov(
-    doc: &mut Value,
-    from: &str,
-    path: &str,
-    allow_last: bool,
-) -> Result<Option<Value>, PatchErrorKind> {
+fn mov(doc: &mut Value, from: &str, path: &str) -> Result<Option<Value>, PatchErrorKind> {
     // Check we are not moving inside own child
     if path.starts_with(from) && path[from.len()..].starts_with('/') {
         return Err(PatchErrorKind::CannotMoveInsideItself);
     }
-    let val = remove(doc, from, allow_last).map_err(|err| match err {
+    let val = remove(doc, from).map_err(|err| match err {
         PatchErrorKind::InvalidPointer => PatchErrorKind::InvalidFromPointer,
         err => err,
     })?;
@@ -411,19 +1608,59 @@ fn copy(doc: &mut Value, from: &str, path: &str) -> Result<Option<Value>, PatchE
     add(doc, path, source)
 }
 
-fn test(doc: &Value, path: &str, expected: &Value) -> Result<(), PatchErrorKind> {
+fn test(
+    doc: &Value,
+    path: &str,
+    expected: &Value,
+    options: &PatchOptions,
+) -> Result<(), PatchErrorKind> {
     let target = doc.pointer(path).ok_or(PatchErrorKind::InvalidPointer)?;
-    if *target == *expected {
+    let equal = if options.unordered_arrays {
+        values_equal_unordered(options.number_equality, target, expected)
+    } else {
+        options.number_equality.values_equal(target, expected)
+    };
+    if equal {
         Ok(())
     } else {
         Err(PatchErrorKind::TestFailed)
     }
 }
 
+// Matches every element of `left` array with a distinct equal element of `right`
+fn values_equal_unordered(equality: NumberEquality, left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Array(left), Value::Array(right)) => {
+            let mut matched = vec![false; right.len()];
+            left.len() == right.len()
+                && left.iter().all(|left| {
+                    right.iter().zip(&mut matched).any(|(right, matched)| {
+                        let found = !*matched && values_equal_unordered(equality, left, right);
+                        *matched |= found;
+                        found
+                    })
+                })
+        }
+        (Value::Object(left), Value::Object(right)) => {
+            left.len() == right.len()
+                && left.iter().all(|(key, left)| {
+                    right
+                        .get(key)
+                        .is_some_and(|right| values_equal_unordered(equality, left, right))
+                })
+        }
+        (left, right) => equality.values_equal(left, right),
+    }
+}
+
 /// Patch provided JSON document (given as `serde_json::Value`) in-place. If any of the patch is
 /// failed, all previous operations are reverted. In case of internal error resulting in panic,
 /// document might be left in inconsistent state.
 ///
+/// With the `preserve_order` feature, removing object members keeps the order of the remaining
+/// ones. Note that members restored when a failed patch is reverted are placed at the end of
+/// their object.
+///
 /// # Example
 /// Create and patch document:
 ///
@@ -452,14 +1689,7 @@ fn test(doc: &Value, path: &str, expected: &Value) -> Result<(), PatchErrorKind>
 /// # }
 /// ```
 pub fn patch(doc: &mut Value, patch: &[PatchOperation]) -> Result<(), PatchError> {
-    let mut undo_stack = Vec::with_capacity(patch.len());
-    if let Err(e) = apply_patches(doc, patch, Some(&mut undo_stack)) {
-        if let Err(e) = undo_patches(doc, &undo_stack) {
-            unreachable!("unable to undo applied patches: {e}")
-        }
-        return Err(e);
-    }
-    Ok(())
+    patch_with(doc, patch, &PatchOptions::default())
 }
 
 /// Patch provided JSON document (given as `serde_json::Value`) in-place. Different from [`patch`]
@@ -494,9 +1724,327 @@ pub fn patch(doc: &mut Value, patch: &[PatchOperation]) -> Result<(), PatchError
 /// # }
 /// ```
 pub fn patch_unsafe(doc: &mut Value, patch: &[PatchOperation]) -> Result<(), PatchError> {
-    apply_patches(doc, patch, None)
+    apply_patches(doc, patch, None, &PatchOptions::default())
+}
+
+/// Options controlling [`patch_with`].
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct PatchOptions {
+    /// How the document is restored if any of the operations fails.
+    pub rollback: RollbackStrategy,
+    /// How numbers are compared by `test` operations.
+    pub number_equality: NumberEquality,
+    /// Whether `test` operations compare arrays, at any depth, as multisets, ignoring the order
+    /// of the elements. Comparison takes time quadratic in the length of the arrays, and with
+    /// [`NumberEquality::Epsilon`] elements are matched greedily, in order.
+    pub unordered_arrays: bool,
+    /// Meaning of `-` in locations referring to existing array elements.
+    pub dash: DashSemantics,
+    /// Validator of the patched document. If it rejects the document, the patch fails with
+    /// [`PatchErrorKind::ValidationFailed`] and the document is restored.
+    pub validator: Option<Validator>,
+    /// Values substituted for the placeholders in `add` and `replace` operations. If not set,
+    /// placeholders are added to the document as is.
+    pub placeholders: Option<Placeholders>,
+}
+
+/// Validator of a patched document, see [`PatchOptions::validator`].
+///
+/// The validator is given the patched document and the locations modified by the patch (as they
+/// are written in the patch), so it can check only the touched parts of a large document. On
+/// failure, it returns the location of the invalid value, which becomes the
+/// [`PatchError::path`]. The error is attributed to the last operation modifying that location
+/// (or its parent, or any of its children), or to the last operation of the patch. Validator is
+/// not called for empty patches.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{patch_with, Patch, PatchErrorKind, PatchOptions, Validator};
+/// use jsonptr::PointerBuf;
+/// use serde_json::{from_value, json};
+///
+/// let mut options = PatchOptions::default();
+/// options.validator = Some(Validator::new(|doc, _touched| match doc["age"].as_u64() {
+///     Some(_) => Ok(()),
+///     None => Err(PointerBuf::parse("/age").unwrap()),
+/// }));
+///
+/// let mut doc = json!({ "name": "Andrew", "age": 42 });
+/// let p: Patch = from_value(json!([
+///   { "op": "replace", "path": "/name", "value": "Maxim" },
+///   { "op": "replace", "path": "/age", "value": "unknown" },
+/// ])).unwrap();
+///
+/// let err = patch_with(&mut doc, &p, &options).unwrap_err();
+/// assert!(matches!(err.kind, PatchErrorKind::ValidationFailed));
+/// assert_eq!(err.operation, 1);
+/// assert_eq!(doc, json!({ "name": "Andrew", "age": 42 }));
+/// ```
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub struct Validator(Arc<dyn Fn(&Value, &[&Pointer]) -> Result<(), PointerBuf> + Send + Sync>);
+
+impl Validator {
+    /// Create a validator from a function.
+    pub fn new(
+        f: impl Fn(&Value, &[&Pointer]) -> Result<(), PointerBuf> + Send + Sync + 'static,
+    ) -> Validator {
+        Validator(Arc::new(f))
+    }
+
+    fn validate(&self, doc: &Value, patch: &[PatchOperation]) -> Result<(), PatchError> {
+        let Some(last) = patch.len().checked_sub(1) else {
+            return Ok(());
+        };
+        let mut touched: Vec<&Pointer> = Vec::new();
+        for path in patch.iter().flat_map(PatchOperation::modified_paths) {
+            if !touched.contains(&path) {
+                touched.push(path);
+            }
+        }
+        (self.0)(doc, &touched).map_err(|path| {
+            let operation = patch
+                .iter()
+                .rposition(|op| {
+                    op.modified_paths()
+                        .any(|modified| location_affects(modified, false, &path))
+                })
+                .unwrap_or(last);
+            let op = patch[operation].kind();
+            operation_error(PatchErrorKind::ValidationFailed, operation, op, &path, None)
+        })
+    }
+}
+
+impl Debug for Validator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Validator").finish_non_exhaustive()
+    }
+}
+
+/// Meaning of `-` as the last token of a path referring to an existing array element, see
+/// [`PatchOptions::dash`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DashSemantics {
+    /// `-` is only accepted where a value is inserted (in the `path` of `add`, `move` and `copy`
+    /// operations), as required by RFC 6902. Elsewhere it fails with
+    /// [`PatchErrorKind::InvalidPointer`] (or [`PatchErrorKind::InvalidFromPointer`]).
+    #[default]
+    Strict,
+    /// `-` in `remove`, `replace` and `test` paths and in `from` of `move` and `copy` refers to
+    /// the last element of the array, like in some JavaScript implementations.
+    LastElement,
+}
+
+/// Strategy of restoring the document after a failed patch, see [`PatchOptions::rollback`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum RollbackStrategy {
+    /// Record the inverse of every applied operation and undo them on failure, same as [`patch`].
+    #[default]
+    Undo,
+    /// Clone the whole document before patching and restore the copy on failure. Cheaper than
+    /// `Undo` for small documents.
+    Snapshot,
+    /// Use `Snapshot` for documents with at most [`RollbackStrategy::AUTO_SNAPSHOT_LIMIT`]
+    /// values, and `Undo` for larger ones.
+    Auto,
 }
 
+impl RollbackStrategy {
+    /// Maximum number of values (including nested ones) in a document for which `Auto` strategy
+    /// takes a snapshot.
+    pub const AUTO_SNAPSHOT_LIMIT: usize = 64;
+}
+
+/// Patch provided JSON document in-place, same as [`patch`], but using the given options.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{patch_with, Patch, PatchOptions, RollbackStrategy};
+/// use serde_json::{from_value, json};
+///
+/// let mut doc = json!({ "name": "Andrew" });
+/// let p: Patch = from_value(json!([
+///   { "op": "add", "path": "/happy", "value": true },
+///   { "op": "test", "path": "/name", "value": "Maxim" },
+/// ])).unwrap();
+///
+/// let mut options = PatchOptions::default();
+/// options.rollback = RollbackStrategy::Snapshot;
+/// patch_with(&mut doc, &p, &options).unwrap_err();
+/// assert_eq!(doc, json!({ "name": "Andrew" }));
+/// ```
+pub fn patch_with(
+    doc: &mut Value,
+    patch: &[PatchOperation],
+    options: &PatchOptions,
+) -> Result<(), PatchError> {
+    let snapshot = match options.rollback {
+        RollbackStrategy::Undo => false,
+        RollbackStrategy::Snapshot => true,
+        RollbackStrategy::Auto => !exceeds_size(doc, RollbackStrategy::AUTO_SNAPSHOT_LIMIT),
+    };
+    if !snapshot {
+        let mut undo_stack = UndoStack::with_capacity(patch.len());
+        if let Err(e) = apply_patches(doc, patch, Some(&mut undo_stack), options)
+            .and_then(|()| validate(doc, patch, options))
+        {
+            if let Err(e) = undo_patches(doc, &undo_stack) {
+                unreachable!("unable to undo applied patches: {e}")
+            }
+            return Err(e);
+        }
+        return Ok(());
+    }
+
+    let original = doc.clone();
+    if let Err(e) =
+        apply_patches(doc, patch, None, options).and_then(|()| validate(doc, patch, options))
+    {
+        *doc = original;
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn validate(
+    doc: &Value,
+    patch: &[PatchOperation],
+    options: &PatchOptions,
+) -> Result<(), PatchError> {
+    match options.validator {
+        Some(ref validator) => validator.validate(doc, patch),
+        None => Ok(()),
+    }
+}
+
+// Checks if the value has more than `limit` values in it, stopping as soon as the limit is reached.
+fn exceeds_size(value: &Value, limit: usize) -> bool {
+    let mut count = 0;
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        count += 1;
+        if count > limit {
+            return true;
+        }
+        match value {
+            Value::Array(arr) => stack.extend(arr),
+            Value::Object(obj) => stack.extend(obj.values()),
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Compute the inverse of the patch for the given document: a patch that, applied to the result
+/// of patching `doc`, gives back the original document. The document itself is not modified.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{invert, patch, Patch};
+/// use serde_json::{from_value, json};
+///
+/// let doc = json!({ "title": "Goodbye!", "tags": ["a"] });
+/// let p: Patch = from_value(json!([
+///   { "op": "replace", "path": "/title", "value": "Hello!" },
+///   { "op": "add", "path": "/tags/-", "value": "b" },
+/// ])).unwrap();
+///
+/// let inverse = invert(&doc, &p).unwrap();
+/// assert_eq!(inverse, from_value::<Patch>(json!([
+///   { "op": "remove", "path": "/tags/1" },
+///   { "op": "replace", "path": "/title", "value": "Goodbye!" },
+/// ])).unwrap());
+///
+/// let mut patched = doc.clone();
+/// patch(&mut patched, &p).unwrap();
+/// patch(&mut patched, &inverse).unwrap();
+/// assert_eq!(patched, doc);
+/// ```
+pub fn invert(doc: &Value, patch: &[PatchOperation]) -> Result<Patch, PatchError> {
+    let mut doc = doc.clone();
+    let mut undo_stack = UndoStack::with_capacity(patch.len());
+    apply_patches(
+        &mut doc,
+        patch,
+        Some(&mut undo_stack),
+        &PatchOptions::default(),
+    )?;
+    Ok(undo_stack.into_iter().rev().collect())
+}
+
+/// Replaces the value at `path` with `new` if it is equal to `expected`, like applying
+/// [`Patch::compare_and_swap`]. Returns `false`, leaving the document unchanged, if the value
+/// is different, and fails if there is no value at `path`.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::compare_and_swap;
+/// use jsonptr::Pointer;
+/// use serde_json::json;
+///
+/// let mut doc = json!({ "version": 1, "title": "Hello" });
+/// let path = Pointer::from_static("/version");
+///
+/// assert!(compare_and_swap(&mut doc, path, &json!(1), json!(2)).unwrap());
+/// assert!(!compare_and_swap(&mut doc, path, &json!(1), json!(2)).unwrap());
+/// assert_eq!(doc, json!({ "version": 2, "title": "Hello" }));
+///
+/// let missing = Pointer::from_static("/author");
+/// assert!(compare_and_swap(&mut doc, missing, &json!(null), json!("Andrew")).is_err());
+/// ```
+pub fn compare_and_swap(
+    doc: &mut Value,
+    path: &Pointer,
+    expected: &Value,
+    new: Value,
+) -> Result<bool, PatchError> {
+    let patch = Patch::compare_and_swap(path.to_buf(), expected.clone(), new);
+    match apply_patches(doc, &patch, None, &PatchOptions::default()) {
+        Ok(()) => Ok(true),
+        // Nothing is changed if `test` fails, and `replace` cannot fail once `test` succeeds
+        Err(PatchError {
+            kind: PatchErrorKind::TestFailed,
+            ..
+        }) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+// Resolves `-` as the last token of a path into the actual index in the array: the index of the
+// last element if `last` is set (for values appended by the operation, or with
+// `DashSemantics::LastElement`), or the index after the last element otherwise.
+fn resolve_dash(doc: &Value, path: &Pointer, last: bool) -> Option<PointerBuf> {
+    let (parent, token) = path.split_back()?;
+    if token.encoded() != "-" {
+        return None;
+    }
+    let Some(Value::Array(arr)) = doc.pointer(parent.as_str()) else {
+        return None;
+    };
+    let idx = if last {
+        arr.len().checked_sub(1)?
+    } else {
+        arr.len()
+    };
+    let mut resolved = parent.to_buf();
+    resolved.push_back(idx);
+    Some(resolved)
+}
+
+// Most patches are short, so with `smallvec` the undo stack of those is kept on the stack.
+#[cfg(feature = "smallvec")]
+type UndoStack = smallvec::SmallVec<[PatchOperation; 8]>;
+#[cfg(not(feature = "smallvec"))]
+type UndoStack = Vec<PatchOperation>;
+
 /// Undoes operations performed by `apply_patches`. This is useful to recover the original document
 /// in case of an error.
 fn undo_patches(doc: &mut Value, undo_patches: &[PatchOperation]) -> Result<(), PatchError> {
@@ -504,23 +2052,22 @@ fn undo_patches(doc: &mut Value, undo_patches: &[PatchOperation]) -> Result<(),
         match patch {
             PatchOperation::Add(op) => {
                 add(doc, op.path.as_str(), op.value.clone())
-                    .map_err(|e| translate_error(e, operation, &op.path))?;
+                    .map_err(|e| translate_error(e, operation, patch))?;
             }
             PatchOperation::Remove(op) => {
-                remove(doc, op.path.as_str(), true)
-                    .map_err(|e| translate_error(e, operation, &op.path))?;
+                remove(doc, op.path.as_str()).map_err(|e| translate_error(e, operation, patch))?;
             }
             PatchOperation::Replace(op) => {
                 replace(doc, op.path.as_str(), op.value.clone())
-                    .map_err(|e| translate_error(e, operation, &op.path))?;
+                    .map_err(|e| translate_error(e, operation, patch))?;
             }
             PatchOperation::Move(op) => {
-                mov(doc, op.from.as_str(), op.path.as_str(), true)
-                    .map_err(|e| translate_error(e, operation, &op.path))?;
+                mov(doc, op.from.as_str(), op.path.as_str())
+                    .map_err(|e| translate_error(e, operation, patch))?;
             }
             PatchOperation::Copy(op) => {
                 copy(doc, op.from.as_str(), op.path.as_str())
-                    .map_err(|e| translate_error(e, operation, &op.path))?;
+                    .map_err(|e| translate_error(e, operation, patch))?;
             }
             _ => unreachable!(),
         }
@@ -529,23 +2076,59 @@ fn undo_patches(doc: &mut Value, undo_patches: &[PatchOperation]) -> Result<(),
     Ok(())
 }
 
+/// Clones the value of an `add` or a `replace` operation, replacing its placeholders.
+fn resolve_placeholders(
+    value: &Value,
+    operation: usize,
+    patch: &PatchOperation,
+    options: &PatchOptions,
+) -> Result<Value, PatchError> {
+    let mut value = value.clone();
+    if let Some(ref placeholders) = options.placeholders {
+        let mut location = patch.path().to_buf();
+        placeholders
+            .resolve(&mut value, &mut location)
+            .map_err(|()| {
+                let kind = PatchErrorKind::UnknownPlaceholder;
+                operation_error(kind, operation, patch.kind(), &location, None)
+            })?;
+    }
+    Ok(value)
+}
+
 // Apply patches while tracking all the changes being made so they can be reverted back in case
 // subsequent patches fail. The inverse of all state changes is recorded in the `undo_stack` which
 // can be reapplied using `undo_patches` to get back to the original document.
 fn apply_patches(
     doc: &mut Value,
     patches: &[PatchOperation],
-    undo_stack: Option<&mut Vec<PatchOperation>>,
+    undo_stack: Option<&mut UndoStack>,
+    options: &PatchOptions,
 ) -> Result<(), PatchError> {
+    let last_element = options.dash == DashSemantics::LastElement;
+    // With `DashSemantics::LastElement`, `-` in the existing location refers to the last element
+    let existing = |doc: &Value, path: &Pointer| -> Option<PointerBuf> {
+        if last_element {
+            resolve_dash(doc, path, true)
+        } else {
+            None
+        }
+    };
+    // Undo operations use actual index instead of `-` for appended values
+    let appended = |doc: &Value, path: &PointerBuf| -> PointerBuf {
+        resolve_dash(doc, path, true).unwrap_or_else(|| path.clone())
+    };
+
     for (operation, patch) in patches.iter().enumerate() {
         match patch {
             PatchOperation::Add(ref op) => {
-                let prev = add(doc, op.path.as_str(), op.value.clone())
-                    .map_err(|e| translate_error(e, operation, &op.path))?;
+                let value = resolve_placeholders(&op.value, operation, patch, options)?;
+                let prev = add(doc, op.path.as_str(), value)
+                    .map_err(|e| translate_error(e, operation, patch))?;
                 if let Some(&mut ref mut undo_stack) = undo_stack {
                     undo_stack.push(match prev {
                         None => PatchOperation::Remove(RemoveOperation {
-                            path: op.path.clone(),
+                            path: appended(doc, &op.path),
                         }),
                         Some(v) => PatchOperation::Add(AddOperation {
                             path: op.path.clone(),
@@ -555,28 +2138,35 @@ fn apply_patches(
                 }
             }
             PatchOperation::Remove(ref op) => {
-                let prev = remove(doc, op.path.as_str(), false)
-                    .map_err(|e| translate_error(e, operation, &op.path))?;
+                let path = existing(doc, &op.path);
+                let path = path.as_deref().unwrap_or(&op.path);
+                let prev =
+                    remove(doc, path.as_str()).map_err(|e| translate_error(e, operation, patch))?;
                 if let Some(&mut ref mut undo_stack) = undo_stack {
                     undo_stack.push(PatchOperation::Add(AddOperation {
-                        path: op.path.clone(),
+                        path: path.to_buf(),
                         value: prev,
                     }))
                 }
             }
             PatchOperation::Replace(ref op) => {
-                let prev = replace(doc, op.path.as_str(), op.value.clone())
-                    .map_err(|e| translate_error(e, operation, &op.path))?;
+                let path = existing(doc, &op.path);
+                let path = path.as_deref().unwrap_or(&op.path);
+                let value = resolve_placeholders(&op.value, operation, patch, options)?;
+                let prev = replace(doc, path.as_str(), value)
+                    .map_err(|e| translate_error(e, operation, patch))?;
                 if let Some(&mut ref mut undo_stack) = undo_stack {
                     undo_stack.push(PatchOperation::Replace(ReplaceOperation {
-                        path: op.path.clone(),
+                        path: path.to_buf(),
                         value: prev,
                     }))
                 }
             }
             PatchOperation::Move(ref op) => {
-                let prev = mov(doc, op.from.as_str(), op.path.as_str(), false)
-                    .map_err(|e| translate_error(e, operation, &op.path))?;
+                let from = existing(doc, &op.from);
+                let from = from.as_deref().unwrap_or(&op.from);
+                let prev = mov(doc, from.as_str(), op.path.as_str())
+                    .map_err(|e| translate_error(e, operation, patch))?;
                 if let Some(&mut ref mut undo_stack) = undo_stack {
                     if let Some(prev) = prev {
                         undo_stack.push(PatchOperation::Add(AddOperation {
@@ -585,18 +2175,20 @@ fn apply_patches(
                         }));
                     }
                     undo_stack.push(PatchOperation::Move(MoveOperation {
-                        from: op.path.clone(),
-                        path: op.from.clone(),
+                        from: appended(doc, &op.path),
+                        path: from.to_buf(),
                     }));
                 }
             }
             PatchOperation::Copy(ref op) => {
-                let prev = copy(doc, op.from.as_str(), op.path.as_str())
-                    .map_err(|e| translate_error(e, operation, &op.path))?;
+                let from = existing(doc, &op.from);
+                let from = from.as_deref().unwrap_or(&op.from);
+                let prev = copy(doc, from.as_str(), op.path.as_str())
+                    .map_err(|e| translate_error(e, operation, patch))?;
                 if let Some(&mut ref mut undo_stack) = undo_stack {
                     undo_stack.push(match prev {
                         None => PatchOperation::Remove(RemoveOperation {
-                            path: op.path.clone(),
+                            path: appended(doc, &op.path),
                         }),
                         Some(v) => PatchOperation::Add(AddOperation {
                             path: op.path.clone(),
@@ -606,8 +2198,10 @@ fn apply_patches(
                 }
             }
             PatchOperation::Test(ref op) => {
-                test(doc, op.path.as_str(), &op.value)
-                    .map_err(|e| translate_error(e, operation, &op.path))?;
+                let path = existing(doc, &op.path);
+                let path = path.as_deref().unwrap_or(&op.path);
+                test(doc, path.as_str(), &op.value, options)
+                    .map_err(|e| translate_error(e, operation, patch))?;
             }
         }
     }
@@ -615,6 +2209,251 @@ fn apply_patches(
     Ok(())
 }
 
+/// Representation of JSON Merge Patch (RFC 7396) document.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(
+    feature = "diesel",
+    derive(::diesel::expression::AsExpression, ::diesel::deserialize::FromSqlRow),
+    diesel(sql_type = ::diesel::sql_types::Json, sql_type = ::diesel::sql_types::Jsonb)
+)]
+#[serde(transparent)]
+pub struct MergePatch(#[cfg_attr(feature = "utoipa", schema(value_type = Object))] pub Value);
+
+impl MergePatch {
+    /// Media type of JSON Merge Patch documents, to be used as a `Content-Type` of requests (for
+    /// example, `request_body(content = MergePatch, content_type = MergePatch::CONTENT_TYPE)`
+    /// with `utoipa`).
+    pub const CONTENT_TYPE: &'static str = "application/merge-patch+json";
+
+    /// Returns `true` if this merge patch is an object. Any other value is a valid merge patch,
+    /// too, but it replaces the whole target document, which is rarely intended when patching
+    /// a resource.
+    pub fn is_valid(&self) -> bool {
+        self.0.is_object()
+    }
+
+    /// Patch provided JSON document in place with this merge patch, same as [`merge`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::MergePatch;
+    /// use serde_json::{from_str, json};
+    ///
+    /// let p: MergePatch = from_str(r#"{ "title": "Hello!", "author": null }"#).unwrap();
+    /// assert!(p.is_valid());
+    ///
+    /// let mut doc = json!({ "title": "Goodbye!", "author": "John Doe" });
+    /// p.apply(&mut doc);
+    /// assert_eq!(doc, json!({ "title": "Hello!" }));
+    /// ```
+    pub fn apply(&self, doc: &mut Value) {
+        merge(doc, &self.0)
+    }
+}
+
+impl_display!(MergePatch);
+
+impl From<Value> for MergePatch {
+    fn from(value: Value) -> MergePatch {
+        MergePatch(value)
+    }
+}
+
+/// Either JSON Patch (RFC 6902) or JSON Merge Patch (RFC 7396) document, depending on the media
+/// type it was received with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnyPatch {
+    /// JSON Patch document, `application/json-patch+json`.
+    Patch(Patch),
+    /// JSON Merge Patch document, `application/merge-patch+json`.
+    Merge(MergePatch),
+}
+
+impl AnyPatch {
+    /// Supported media types, to be used as a value of `Accept-Patch` header (RFC 5789).
+    pub const ACCEPT_PATCH: &'static str =
+        "application/json-patch+json, application/merge-patch+json";
+
+    /// Returns `true` if the given `Content-Type` header value is either of the supported media
+    /// types. Parameters like `charset` are ignored.
+    pub fn is_supported(content_type: &str) -> bool {
+        let media_type = media_type(content_type);
+        media_type.eq_ignore_ascii_case(Patch::CONTENT_TYPE)
+            || media_type.eq_ignore_ascii_case(MergePatch::CONTENT_TYPE)
+    }
+
+    /// Parse patch document from the request body, choosing the format by the given
+    /// `Content-Type` header value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::AnyPatch;
+    /// use serde_json::json;
+    ///
+    /// let body = br#"[{ "op": "add", "path": "/author", "value": "John Doe" }]"#;
+    /// let p = AnyPatch::from_slice("application/json-patch+json", body).unwrap();
+    ///
+    /// let mut doc = json!({ "title": "Hello!" });
+    /// p.apply(&mut doc).unwrap();
+    /// assert_eq!(doc, json!({ "title": "Hello!", "author": "John Doe" }));
+    ///
+    /// let body = br#"{ "author": null }"#;
+    /// let p = AnyPatch::from_slice("application/merge-patch+json; charset=utf-8", body).unwrap();
+    /// p.apply(&mut doc).unwrap();
+    /// assert_eq!(doc, json!({ "title": "Hello!" }));
+    /// ```
+    pub fn from_slice(content_type: &str, body: &[u8]) -> Result<AnyPatch, ParsePatchError> {
+        let media_type = media_type(content_type);
+        if media_type.eq_ignore_ascii_case(Patch::CONTENT_TYPE) {
+            Ok(AnyPatch::Patch(serde_json::from_slice(body)?))
+        } else if media_type.eq_ignore_ascii_case(MergePatch::CONTENT_TYPE) {
+            Ok(AnyPatch::Merge(serde_json::from_slice(body)?))
+        } else {
+            Err(ParsePatchError::UnsupportedMediaType(
+                content_type.to_owned(),
+            ))
+        }
+    }
+
+    /// Patch provided JSON document in place. Merge patches never fail.
+    pub fn apply(&self, doc: &mut Value) -> Result<(), PatchError> {
+        match self {
+            AnyPatch::Patch(p) => patch(doc, p),
+            AnyPatch::Merge(p) => {
+                p.apply(doc);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<Patch> for AnyPatch {
+    fn from(patch: Patch) -> AnyPatch {
+        AnyPatch::Patch(patch)
+    }
+}
+
+impl From<MergePatch> for AnyPatch {
+    fn from(patch: MergePatch) -> AnyPatch {
+        AnyPatch::Merge(patch)
+    }
+}
+
+/// This type represents all possible errors that can occur when parsing a patch document with
+/// [`AnyPatch::from_slice`], or converting a string or a JSON value into a [`Patch`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ParsePatchError {
+    /// Media type is neither JSON Patch nor JSON Merge Patch.
+    #[error("unsupported media type '{0}'")]
+    UnsupportedMediaType(String),
+    /// Document is not a valid patch.
+    #[error("invalid patch document: {0}")]
+    InvalidBody(#[from] serde_json::Error),
+}
+
+/// Media type of the `Content-Type` header value, without parameters.
+pub(crate) fn media_type(content_type: &str) -> &str {
+    content_type.split(';').next().unwrap_or_default().trim()
+}
+
+fn validate_operation(op: &PatchOperation) -> Option<PatchErrorKind> {
+    // `-` is only allowed as a last token of a location where a value is inserted
+    fn has_next_index(ptr: &Pointer, allow_last: bool) -> bool {
+        let count = ptr.count();
+        ptr.tokens()
+            .enumerate()
+            .any(|(idx, token)| token.encoded() == "-" && !(allow_last && idx + 1 == count))
+    }
+
+    match op {
+        PatchOperation::Add(op) if has_next_index(&op.path, true) => {
+            Some(PatchErrorKind::InvalidPointer)
+        }
+        PatchOperation::Remove(op) if op.path.is_root() || has_next_index(&op.path, false) => {
+            Some(PatchErrorKind::InvalidPointer)
+        }
+        PatchOperation::Replace(ReplaceOperation { path, .. })
+        | PatchOperation::Test(TestOperation { path, .. })
+            if has_next_index(path, false) =>
+        {
+            Some(PatchErrorKind::InvalidPointer)
+        }
+        PatchOperation::Move(op) if op.from.is_root() || has_next_index(&op.from, false) => {
+            Some(PatchErrorKind::InvalidFromPointer)
+        }
+        PatchOperation::Move(op)
+            if strip_pointer_prefix(&op.path, &op.from).is_some_and(|rest| !rest.is_root()) =>
+        {
+            Some(PatchErrorKind::CannotMoveInsideItself)
+        }
+        PatchOperation::Copy(op) if has_next_index(&op.from, false) => {
+            Some(PatchErrorKind::InvalidFromPointer)
+        }
+        PatchOperation::Move(MoveOperation { path, .. })
+        | PatchOperation::Copy(CopyOperation { path, .. })
+            if has_next_index(path, true) =>
+        {
+            Some(PatchErrorKind::InvalidPointer)
+        }
+        _ => None,
+    }
+}
+
+/// Expand JSON Merge Patch (RFC 7396) into an equivalent JSON Patch (RFC 6902) for the given
+/// document. Applying the resulting patch to `doc` gives the same result as [`merge`].
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{merge_patch_to_patch, Patch};
+/// use serde_json::{from_value, json};
+///
+/// let doc = json!({ "title": "Goodbye!", "author": { "familyName": "Doe" }, "tags": [] });
+/// let merge = json!({ "title": "Hello!", "author": { "familyName": null }, "tags": ["a"] });
+///
+/// let p = merge_patch_to_patch(&doc, &merge);
+/// assert_eq!(p, from_value::<Patch>(json!([
+///   { "op": "replace", "path": "/title", "value": "Hello!" },
+///   { "op": "remove", "path": "/author/familyName" },
+///   { "op": "replace", "path": "/tags", "value": ["a"] },
+/// ])).unwrap());
+/// ```
+pub fn merge_patch_to_patch(doc: &Value, patch: &Value) -> Patch {
+    let mut result = Patch::default();
+    expand_merge_patch(doc, patch, &mut PointerBuf::new(), &mut result);
+    result
+}
+
+fn expand_merge_patch(doc: &Value, patch: &Value, path: &mut PointerBuf, result: &mut Patch) {
+    let (Value::Object(doc), Value::Object(patch)) = (doc, patch) else {
+        let mut value = Value::Null;
+        merge(&mut value, patch);
+        if *doc != value {
+            result.0.push(PatchOperation::replace(path.clone(), value));
+        }
+        return;
+    };
+
+    for (key, value) in patch {
+        path.push_back(key.as_str());
+        match doc.get(key) {
+            Some(_) if value.is_null() => result.0.push(PatchOperation::remove(path.clone())),
+            Some(current) => expand_merge_patch(current, value, path, result),
+            None if value.is_null() => {}
+            None => {
+                let mut added = Value::Null;
+                merge(&mut added, value);
+                result.0.push(PatchOperation::add(path.clone(), added));
+            }
+        }
+        path.pop_back();
+    }
+}
+
 /// Patch provided JSON document (given as `serde_json::Value`) in place with JSON Merge Patch
 /// (RFC 7396).
 ///
@@ -670,9 +2509,280 @@ pub fn merge(doc: &mut Value, patch: &Value) {
     let map = doc.as_object_mut().unwrap();
     for (key, value) in patch.as_object().unwrap() {
         if value.is_null() {
-            map.remove(key.as_str());
+            remove_member(map, key);
         } else {
             merge(map.entry(key.as_str()).or_insert(Value::Null), value);
         }
     }
 }
+
+/// Options controlling [`merge_with`].
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct MergePatchOptions {
+    /// Maximum nesting depth of arrays and objects in the merge patch. Top-level object (or array)
+    /// has depth of 1. No limit if `None`.
+    pub max_depth: Option<usize>,
+    /// Marker value which sets the target member to an explicit `null` instead of being copied
+    /// into the document, as RFC 7396 has no way to express `null` values. Markers are only
+    /// recognized as values of object members, not inside arrays.
+    pub null_sentinel: Option<Value>,
+    /// Where members added by the merge patch are placed among the existing members of an object.
+    /// Only has effect if `serde_json` is built with the `preserve_order` feature.
+    pub key_position: KeyPosition,
+}
+
+/// Placement of new object members, see [`MergePatchOptions::key_position`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum KeyPosition {
+    /// Append new members after existing ones, in the order of the merge patch.
+    #[default]
+    Back,
+    /// Insert new members before existing ones, in the order of the merge patch.
+    Front,
+    /// Insert new members before the first existing member with a greater key, so objects with
+    /// sorted keys stay sorted.
+    Sorted,
+}
+
+/// This type represents all possible errors that can occur when applying JSON Merge Patch with
+/// [`merge_with`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum MergeError {
+    /// Merge patch is nested deeper than allowed by [`MergePatchOptions::max_depth`].
+    #[error("merge patch exceeds maximum depth of {max_depth}")]
+    DepthLimitExceeded {
+        /// Maximum depth allowed by the options.
+        max_depth: usize,
+    },
+}
+
+/// Patch provided JSON document in place with JSON Merge Patch (RFC 7396), same as [`merge`],
+/// but subject to the given options. Options are checked before the document is modified, so
+/// the document is left unchanged on error.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{merge_with, MergeError, MergePatchOptions};
+/// use serde_json::json;
+///
+/// let mut options = MergePatchOptions::default();
+/// options.max_depth = Some(2);
+///
+/// let mut doc = json!({ "a": { "b": 1 } });
+/// merge_with(&mut doc, &json!({ "a": { "b": 2 } }), &options).unwrap();
+/// assert_eq!(doc, json!({ "a": { "b": 2 } }));
+///
+/// let err = merge_with(&mut doc, &json!({ "a": { "b": { "c": 3 } } }), &options).unwrap_err();
+/// assert!(matches!(err, MergeError::DepthLimitExceeded { max_depth: 2 }));
+/// assert_eq!(doc, json!({ "a": { "b": 2 } }));
+/// ```
+pub fn merge_with(
+    doc: &mut Value,
+    patch: &Value,
+    options: &MergePatchOptions,
+) -> Result<(), MergeError> {
+    if let Some(max_depth) = options.max_depth {
+        if exceeds_depth(patch, max_depth) {
+            return Err(MergeError::DepthLimitExceeded { max_depth });
+        }
+    }
+    match options {
+        MergePatchOptions {
+            null_sentinel: None,
+            key_position: KeyPosition::Back,
+            ..
+        } => merge(doc, patch),
+        _ => merge_with_options(doc, patch, options),
+    }
+    Ok(())
+}
+
+fn merge_with_options(doc: &mut Value, patch: &Value, options: &MergePatchOptions) {
+    let Value::Object(patch) = patch else {
+        *doc = patch.clone();
+        return;
+    };
+
+    if !doc.is_object() {
+        *doc = Value::Object(Map::new());
+    }
+    let map = doc.as_object_mut().unwrap();
+    let mut added = Vec::new();
+    for (key, value) in patch {
+        if value.is_null() {
+            remove_member(map, key);
+        } else if options.null_sentinel.as_ref() == Some(value) {
+            match map.get_mut(key) {
+                Some(target) => *target = Value::Null,
+                None => added.push((key.clone(), Value::Null)),
+            }
+        } else if let Some(target) = map.get_mut(key) {
+            merge_with_options(target, value, options);
+        } else {
+            let mut target = Value::Null;
+            merge_with_options(&mut target, value, options);
+            added.push((key.clone(), target));
+        }
+    }
+    insert_members(map, added, options.key_position);
+}
+
+fn insert_members(
+    map: &mut Map<String, Value>,
+    added: Vec<(String, Value)>,
+    position: KeyPosition,
+) {
+    if added.is_empty() {
+        return;
+    }
+    if position == KeyPosition::Back {
+        map.extend(added);
+        return;
+    }
+    let existing = std::mem::take(map);
+    match position {
+        KeyPosition::Back => unreachable!(),
+        KeyPosition::Front => {
+            map.extend(added);
+            map.extend(existing);
+        }
+        KeyPosition::Sorted => {
+            let mut added = added;
+            added.sort_by(|(left, _), (right, _)| left.cmp(right));
+            let mut added = added.into_iter().peekable();
+            for (key, value) in existing {
+                while let Some(member) = added.next_if(|(added, _)| *added < key) {
+                    map.insert(member.0, member.1);
+                }
+                map.insert(key, value);
+            }
+            map.extend(added);
+        }
+    }
+}
+
+// Checked without recursion, so that deep values cannot exhaust the stack here.
+fn exceeds_depth(value: &Value, max_depth: usize) -> bool {
+    let mut stack = vec![(value, 0)];
+    while let Some((value, depth)) = stack.pop() {
+        let children: Box<dyn Iterator<Item = &Value>> = match value {
+            Value::Array(arr) => Box::new(arr.iter()),
+            Value::Object(obj) => Box::new(obj.values()),
+            _ => continue,
+        };
+        if depth + 1 > max_depth {
+            return true;
+        }
+        stack.extend(children.map(|child| (child, depth + 1)));
+    }
+    false
+}
+
+/// Changes made to the document by [`merge_with_report`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MergeReport {
+    /// Locations of members that were added to the document
+    pub added: Vec<PointerBuf>,
+    /// Locations of values that were replaced with different values
+    pub replaced: Vec<PointerBuf>,
+    /// Locations of members that were removed from the document
+    pub removed: Vec<PointerBuf>,
+}
+
+impl MergeReport {
+    /// Returns `true` if merge did not change the document.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.replaced.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Patch provided JSON document in place with JSON Merge Patch (RFC 7396), same as [`merge`], and
+/// report the locations that were changed. Nested objects added or replaced as a whole are
+/// reported by their own location only.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::merge_with_report;
+/// use jsonptr::PointerBuf;
+/// use serde_json::json;
+///
+/// let mut doc = json!({ "title": "Goodbye!", "author": { "givenName": "John" }, "tags": [] });
+/// let patch = json!({ "title": "Hello!", "author": { "givenName": null }, "tags": [], "id": 1 });
+///
+/// let report = merge_with_report(&mut doc, &patch);
+/// assert_eq!(report.added, [PointerBuf::parse("/id").unwrap()]);
+/// assert_eq!(report.replaced, [PointerBuf::parse("/title").unwrap()]);
+/// assert_eq!(report.removed, [PointerBuf::parse("/author/givenName").unwrap()]);
+/// ```
+pub fn merge_with_report(doc: &mut Value, patch: &Value) -> MergeReport {
+    let mut report = MergeReport::default();
+    merge_reporting(doc, patch, &mut PointerBuf::new(), &mut report);
+    report
+}
+
+fn merge_reporting(
+    doc: &mut Value,
+    patch: &Value,
+    path: &mut PointerBuf,
+    report: &mut MergeReport,
+) {
+    let (Value::Object(map), Value::Object(patch)) = (&mut *doc, patch) else {
+        let prev = std::mem::take(doc);
+        merge(doc, patch);
+        if *doc != prev {
+            report.replaced.push(path.clone());
+        }
+        return;
+    };
+
+    for (key, value) in patch {
+        path.push_back(key.as_str());
+        if value.is_null() {
+            if remove_member(map, key).is_some() {
+                report.removed.push(path.clone());
+            }
+        } else if let Some(current) = map.get_mut(key.as_str()) {
+            merge_reporting(current, value, path, report);
+        } else {
+            merge(map.entry(key.as_str()).or_insert(Value::Null), value);
+            report.added.push(path.clone());
+        }
+        path.pop_back();
+    }
+}
+
+/// Patch provided typed value in place with JSON Merge Patch (RFC 7396). The value is serialized
+/// into JSON, merged with the patch and deserialized back. If serialization or deserialization
+/// fails, `target` is left unchanged.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::merge_struct;
+/// use serde::{Deserialize, Serialize};
+/// use serde_json::json;
+///
+/// #[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// struct Person {
+///     name: String,
+///     email: Option<String>,
+/// }
+///
+/// let mut person = Person { name: "John".into(), email: Some("john@example.com".into()) };
+/// merge_struct(&mut person, &json!({ "name": "Jane", "email": null })).unwrap();
+/// assert_eq!(person, Person { name: "Jane".into(), email: None });
+/// ```
+pub fn merge_struct<T>(target: &mut T, patch: &Value) -> Result<(), serde_json::Error>
+where
+    T: Serialize + serde::de::DeserializeOwned,
+{
+    let mut doc = serde_json::to_value(&*target)?;
+    merge(&mut doc, patch);
+    *target = serde_json::from_value(doc)?;
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use jsonptr::PointerBuf;
+use serde_json::{Map, Value};
+
+/// Conflicting change found by [`merge3`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MergeConflict {
+    /// Location of the conflicting change.
+    pub path: PointerBuf,
+    /// Value in the common ancestor, `None` if absent.
+    pub base: Option<Value>,
+    /// Value on "our" side, `None` if absent (removed).
+    pub ours: Option<Value>,
+    /// Value on "their" side, `None` if absent (removed).
+    pub theirs: Option<Value>,
+}
+
+/// Result of the three-way merge done by [`merge3`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThreeWayMerge {
+    /// Merged document. Conflicting locations keep "our" value.
+    pub merged: Value,
+    /// Conflicting changes, in document order.
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl ThreeWayMerge {
+    /// Returns `true` if both sides were merged without conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+fn merge_impl(
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    path: &mut PointerBuf,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Option<Value> {
+    if ours == theirs || theirs == base {
+        return ours.cloned();
+    }
+    if ours == base {
+        return theirs.cloned();
+    }
+
+    let empty = Map::new();
+    match (base, ours, theirs) {
+        (None | Some(Value::Object(_)), Some(Value::Object(ours)), Some(Value::Object(theirs))) => {
+            let base = base.and_then(Value::as_object).unwrap_or(&empty);
+            let mut merged = Map::new();
+            let keys = ours
+                .keys()
+                .chain(theirs.keys().filter(|key| !ours.contains_key(*key)))
+                .chain(
+                    base.keys()
+                        .filter(|key| !ours.contains_key(*key) && !theirs.contains_key(*key)),
+                );
+            for key in keys {
+                path.push_back(key.as_str());
+                let value = merge_impl(
+                    base.get(key),
+                    ours.get(key),
+                    theirs.get(key),
+                    path,
+                    conflicts,
+                );
+                path.pop_back();
+                if let Some(value) = value {
+                    merged.insert(key.clone(), value);
+                }
+            }
+            Some(Value::Object(merged))
+        }
+        _ => {
+            // Both sides changed the value differently (arrays are compared as a whole)
+            conflicts.push(MergeConflict {
+                path: path.clone(),
+                base: base.cloned(),
+                ours: ours.cloned(),
+                theirs: theirs.cloned(),
+            });
+            ours.cloned()
+        }
+    }
+}
+
+/// Merge two JSON documents derived from the common ancestor (three-way merge).
+///
+/// Changes made on only one side are taken as is. Objects changed on both sides are merged
+/// member by member; any other value changed differently on both sides is reported as a
+/// conflict, and "our" value is kept in the merged document. Arrays are not merged element-wise.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::merge3;
+/// use serde_json::json;
+///
+/// let base = json!({ "title": "Hello", "tags": ["a"], "author": "John" });
+/// let ours = json!({ "title": "Hello!", "tags": ["a", "b"], "author": "John" });
+/// let theirs = json!({ "title": "Hello", "tags": ["c"] });
+///
+/// let result = merge3(&base, &ours, &theirs);
+/// assert_eq!(result.merged, json!({ "title": "Hello!", "tags": ["a", "b"] }));
+/// assert_eq!(result.conflicts.len(), 1);
+/// assert_eq!(result.conflicts[0].path.as_str(), "/tags");
+/// assert_eq!(result.conflicts[0].theirs, Some(json!(["c"])));
+/// ```
+pub fn merge3(base: &Value, ours: &Value, theirs: &Value) -> ThreeWayMerge {
+    let mut conflicts = Vec::new();
+    let merged = merge_impl(
+        Some(base),
+        Some(ours),
+        Some(theirs),
+        &mut PointerBuf::new(),
+        &mut conflicts,
+    )
+    .unwrap_or(Value::Null);
+    ThreeWayMerge { merged, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge3, MergeConflict};
+    use jsonptr::PointerBuf;
+    use serde_json::json;
+
+    #[test]
+    pub fn non_conflicting_changes() {
+        let base = json!({ "a": 1, "b": { "c": 2, "d": 3 }, "e": 4 });
+        let ours = json!({ "a": 2, "b": { "c": 2 }, "e": 4, "f": 5 });
+        let theirs = json!({ "a": 1, "b": { "c": 2, "d": 3, "g": 6 }, "h": 7 });
+
+        let result = merge3(&base, &ours, &theirs);
+        assert!(result.is_clean());
+        assert_eq!(
+            result.merged,
+            json!({ "a": 2, "b": { "c": 2, "g": 6 }, "f": 5, "h": 7 })
+        );
+    }
+
+    #[test]
+    pub fn same_change_on_both_sides() {
+        let base = json!({ "a": 1, "b": 2 });
+        let ours = json!({ "a": 3 });
+        let theirs = json!({ "a": 3 });
+
+        let result = merge3(&base, &ours, &theirs);
+        assert!(result.is_clean());
+        assert_eq!(result.merged, json!({ "a": 3 }));
+    }
+
+    #[test]
+    pub fn conflicts() {
+        let base = json!({ "a": 1, "b": { "c": 2 }, "d": [1] });
+        let ours = json!({ "a": 2, "d": [1, 2], "e": { "x": 1 } });
+        let theirs = json!({ "a": 3, "b": { "c": 4 }, "d": [1, 2], "e": { "y": 1 } });
+
+        let result = merge3(&base, &ours, &theirs);
+        assert_eq!(
+            result.merged,
+            json!({ "a": 2, "d": [1, 2], "e": { "x": 1, "y": 1 } })
+        );
+        assert_eq!(
+            result.conflicts,
+            vec![
+                MergeConflict {
+                    path: PointerBuf::parse("/a").unwrap(),
+                    base: Some(json!(1)),
+                    ours: Some(json!(2)),
+                    theirs: Some(json!(3)),
+                },
+                MergeConflict {
+                    path: PointerBuf::parse("/b").unwrap(),
+                    base: Some(json!({ "c": 2 })),
+                    ours: None,
+                    theirs: Some(json!({ "c": 4 })),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    pub fn root_conflict() {
+        let result = merge3(&json!(1), &json!(2), &json!({ "a": 1 }));
+        assert_eq!(result.merged, json!(2));
+        assert_eq!(result.conflicts[0].path, PointerBuf::new());
+    }
+}
@@ -0,0 +1,459 @@
+//! Versioning of JSON documents with ordered, named migrations.
+//!
+//! Each [`Migration`] is either a fixed [`Patch`] or a function building the patch from the
+//! document being migrated. Version of a document is the number of migrations applied to it, so
+//! a document at version `0` has none and a document at [`Migrations::latest`] has all of them.
+//!
+//! Migrating down applies the reverse migrations, either given explicitly (see
+//! [`Migration::with_down`]) or derived by inverting a fixed patch without access to the original
+//! document. Since the inverse cannot contain the values removed or replaced by the patch, such
+//! operations are only invertible if they are preceded by a `test` operation on the same location
+//! recording the expected value. `add`, `copy` and `move` are assumed to create new locations
+//! (unless tested the same way), and their target cannot be `-`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::migration::{Migration, Migrations};
+//! use json_patch::Patch;
+//! use serde_json::{from_value, json};
+//!
+//! let migrations = Migrations::new()
+//!     .with(Migration::new("rename-title", from_value::<Patch>(json!([
+//!         { "op": "move", "from": "/title", "path": "/name" },
+//!     ])).unwrap()))
+//!     .with(Migration::new("drop-legacy", from_value::<Patch>(json!([
+//!         { "op": "test", "path": "/legacy", "value": true },
+//!         { "op": "remove", "path": "/legacy" },
+//!     ])).unwrap()));
+//!
+//! let original = json!({ "title": "Hello!", "legacy": true });
+//! let mut doc = original.clone();
+//! migrations.migrate(&mut doc, 0, migrations.latest()).unwrap();
+//! assert_eq!(doc, json!({ "name": "Hello!" }));
+//!
+//! migrations.migrate(&mut doc, 2, 0).unwrap();
+//! assert_eq!(doc, original);
+//! ```
+use crate::{
+    patch, patch_affects, AddOperation, MoveOperation, Patch, PatchError, PatchOperation,
+    RemoveOperation, ReplaceOperation,
+};
+use jsonptr::PointerBuf;
+use serde_json::Value;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+use thiserror::Error;
+
+type PatchFn = Arc<dyn Fn(&Value) -> Patch + Send + Sync>;
+
+#[derive(Clone)]
+enum Step {
+    Patch(Patch),
+    Fn(PatchFn),
+}
+
+impl Step {
+    fn build(&self, doc: &Value) -> Patch {
+        match self {
+            Step::Patch(patch) => patch.clone(),
+            Step::Fn(f) => f(doc),
+        }
+    }
+}
+
+impl Debug for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Patch(patch) => f.debug_tuple("Patch").field(patch).finish(),
+            Step::Fn(_) => f.write_str("Fn"),
+        }
+    }
+}
+
+/// Named migration of a JSON document to the next version.
+#[derive(Clone, Debug)]
+pub struct Migration {
+    name: String,
+    up: Step,
+    down: Option<Step>,
+}
+
+impl Migration {
+    /// Creates a migration applying the given patch.
+    pub fn new(name: impl Into<String>, up: Patch) -> Self {
+        Migration {
+            name: name.into(),
+            up: Step::Patch(up),
+            down: None,
+        }
+    }
+
+    /// Creates a migration applying the patch built by the function from the document being
+    /// migrated. Such migrations can only be reverted if the reverse migration is given
+    /// explicitly.
+    pub fn from_fn(
+        name: impl Into<String>,
+        up: impl Fn(&Value) -> Patch + Send + Sync + 'static,
+    ) -> Self {
+        Migration {
+            name: name.into(),
+            up: Step::Fn(Arc::new(up)),
+            down: None,
+        }
+    }
+
+    /// Sets the patch reverting the migration, instead of inverting the migration patch.
+    pub fn with_down(mut self, down: Patch) -> Self {
+        self.down = Some(Step::Patch(down));
+        self
+    }
+
+    /// Sets the function building the patch reverting the migration from the migrated document,
+    /// instead of inverting the migration patch.
+    pub fn with_down_fn(mut self, down: impl Fn(&Value) -> Patch + Send + Sync + 'static) -> Self {
+        self.down = Some(Step::Fn(Arc::new(down)));
+        self
+    }
+
+    /// Returns the name of the migration.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn down(&self) -> Option<Step> {
+        match (&self.down, &self.up) {
+            (Some(down), _) => Some(down.clone()),
+            (None, Step::Patch(up)) => invert(up).map(Step::Patch),
+            (None, Step::Fn(_)) => None,
+        }
+    }
+}
+
+/// Patch applied by a migration, as reported by [`Migrations::migrate`] and
+/// [`Migrations::dry_run`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MigrationStep {
+    /// Name of the migration.
+    pub name: String,
+    /// Version of the document after the step.
+    pub version: usize,
+    /// Applied patch: the migration patch when migrating up, its reverse when migrating down.
+    pub patch: Patch,
+}
+
+/// Error that can occur while migrating a document.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum MigrationError {
+    /// Version is greater than the number of migrations.
+    #[error("unknown version {version}, latest is {latest}")]
+    UnknownVersion {
+        /// Requested version.
+        version: usize,
+        /// Latest known version.
+        latest: usize,
+    },
+    /// Migration has no reverse migration and its patch cannot be inverted.
+    #[error("migration '{name}' cannot be reverted")]
+    Irreversible {
+        /// Name of the migration.
+        name: String,
+    },
+    /// Migration patch failed to apply.
+    #[error("migration '{name}' failed: {source}")]
+    Patch {
+        /// Name of the migration.
+        name: String,
+        /// Patch error.
+        source: PatchError,
+    },
+}
+
+/// Ordered list of migrations. See the [module documentation](self).
+#[derive(Clone, Debug, Default)]
+pub struct Migrations {
+    migrations: Vec<Migration>,
+}
+
+impl Migrations {
+    /// Creates an empty list of migrations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the migration to the end of the list.
+    pub fn push(&mut self, migration: Migration) {
+        self.migrations.push(migration);
+    }
+
+    /// Adds the migration to the end of the list, builder style.
+    pub fn with(mut self, migration: Migration) -> Self {
+        self.push(migration);
+        self
+    }
+
+    /// Returns the latest version, that is the number of migrations.
+    pub fn latest(&self) -> usize {
+        self.migrations.len()
+    }
+
+    /// Migrates the document from one version to another, up or down, and returns the applied
+    /// steps. If any of the steps fails, the document is left unchanged.
+    pub fn migrate(
+        &self,
+        doc: &mut Value,
+        from: usize,
+        to: usize,
+    ) -> Result<Vec<MigrationStep>, MigrationError> {
+        let mut migrated = doc.clone();
+        let steps = self.run(&mut migrated, from, to)?;
+        *doc = migrated;
+        Ok(steps)
+    }
+
+    /// Returns the steps [`Migrations::migrate`] would apply, without modifying the document.
+    pub fn dry_run(
+        &self,
+        doc: &Value,
+        from: usize,
+        to: usize,
+    ) -> Result<Vec<MigrationStep>, MigrationError> {
+        self.run(&mut doc.clone(), from, to)
+    }
+
+    fn run(
+        &self,
+        doc: &mut Value,
+        from: usize,
+        to: usize,
+    ) -> Result<Vec<MigrationStep>, MigrationError> {
+        for version in [from, to] {
+            if version > self.latest() {
+                return Err(MigrationError::UnknownVersion {
+                    version,
+                    latest: self.latest(),
+                });
+            }
+        }
+
+        let mut steps = Vec::new();
+        if from <= to {
+            for (idx, migration) in self.migrations[from..to].iter().enumerate() {
+                let p = migration.up.build(doc);
+                steps.push(apply(doc, migration, from + idx + 1, p)?);
+            }
+        } else {
+            // Check all the migrations up front to avoid building patches needlessly
+            let downs = self.migrations[to..from]
+                .iter()
+                .map(|migration| {
+                    migration
+                        .down()
+                        .ok_or_else(|| MigrationError::Irreversible {
+                            name: migration.name.clone(),
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            for (idx, down) in downs.iter().enumerate().rev() {
+                let p = down.build(doc);
+                steps.push(apply(doc, &self.migrations[to + idx], to + idx, p)?);
+            }
+        }
+        Ok(steps)
+    }
+}
+
+fn apply(
+    doc: &mut Value,
+    migration: &Migration,
+    version: usize,
+    p: Patch,
+) -> Result<MigrationStep, MigrationError> {
+    patch(doc, &p).map_err(|source| MigrationError::Patch {
+        name: migration.name.clone(),
+        source,
+    })?;
+    Ok(MigrationStep {
+        name: migration.name.clone(),
+        version,
+        patch: p,
+    })
+}
+
+// Builds the patch reverting the given one, using the values recorded by `test` operations for
+// the values it removes or replaces.
+fn invert(p: &Patch) -> Option<Patch> {
+    let mut inverse = Vec::with_capacity(p.len());
+    for (idx, op) in p.iter().enumerate() {
+        // Value at the location before the operation, if recorded by a preceding `test`
+        let tested = |path: &PointerBuf| {
+            let previous = &p[..idx];
+            previous
+                .iter()
+                .enumerate()
+                .rev()
+                .find_map(|(test_idx, prev)| match prev {
+                    PatchOperation::Test(test) if test.path == *path => {
+                        let between = &previous[test_idx + 1..];
+                        (!patch_affects(between, path)).then(|| test.value.clone())
+                    }
+                    _ => None,
+                })
+        };
+        let restore = |path: &PointerBuf| {
+            if path.as_str().ends_with("/-") {
+                return None;
+            }
+            Some(match tested(path) {
+                // Could be an array element, which is inserted rather than overwritten
+                Some(_) if path.last().is_some_and(|token| token.to_index().is_ok()) => {
+                    return None;
+                }
+                Some(value) => PatchOperation::Add(AddOperation {
+                    path: path.clone(),
+                    value,
+                }),
+                None => PatchOperation::Remove(RemoveOperation { path: path.clone() }),
+            })
+        };
+        match op {
+            PatchOperation::Add(AddOperation { path, .. }) => inverse.push(restore(path)?),
+            PatchOperation::Copy(copy) => inverse.push(restore(&copy.path)?),
+            PatchOperation::Remove(RemoveOperation { path }) => {
+                inverse.push(PatchOperation::Add(AddOperation {
+                    path: path.clone(),
+                    value: tested(path)?,
+                }))
+            }
+            PatchOperation::Replace(ReplaceOperation { path, .. }) => {
+                inverse.push(PatchOperation::Replace(ReplaceOperation {
+                    path: path.clone(),
+                    value: tested(path)?,
+                }))
+            }
+            PatchOperation::Move(MoveOperation { from, path }) => {
+                // Value overwritten by the move is restored after moving the value back
+                let overwritten = restore(path)?;
+                if let PatchOperation::Add(_) = overwritten {
+                    inverse.push(overwritten);
+                }
+                inverse.push(PatchOperation::Move(MoveOperation {
+                    from: path.clone(),
+                    path: from.clone(),
+                }));
+            }
+            PatchOperation::Test(_) => {}
+        }
+    }
+    inverse.reverse();
+    Some(Patch(inverse))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{invert, Migration, MigrationError, Migrations};
+    use crate::Patch;
+    use serde_json::{from_value, json, Value};
+
+    fn patch(value: Value) -> Patch {
+        from_value(value).unwrap()
+    }
+
+    #[test]
+    fn invert_patch() {
+        let p = patch(json!([
+            { "op": "test", "path": "/a", "value": 1 },
+            { "op": "replace", "path": "/a", "value": 2 },
+            { "op": "add", "path": "/b", "value": 3 },
+            { "op": "test", "path": "/c", "value": [1] },
+            { "op": "move", "from": "/b", "path": "/c" },
+        ]));
+        assert_eq!(
+            invert(&p).unwrap(),
+            patch(json!([
+                { "op": "move", "from": "/c", "path": "/b" },
+                { "op": "add", "path": "/c", "value": [1] },
+                { "op": "remove", "path": "/b" },
+                { "op": "replace", "path": "/a", "value": 1 },
+            ]))
+        );
+
+        let doc = json!({ "a": 1, "c": [1] });
+        let mut patched = doc.clone();
+        crate::patch(&mut patched, &p).unwrap();
+        crate::patch(&mut patched, &invert(&p).unwrap()).unwrap();
+        assert_eq!(patched, doc);
+
+        // Tested value is modified before the removal
+        let p = patch(json!([
+            { "op": "test", "path": "/a", "value": 1 },
+            { "op": "replace", "path": "/a", "value": 2 },
+            { "op": "remove", "path": "/a" },
+        ]));
+        assert!(invert(&p).is_none());
+        assert!(invert(&patch(json!([{ "op": "add", "path": "/a/-", "value": 1 }]))).is_none());
+
+        // The parent of the tested location could be an array or an object
+        let p = patch(json!([
+            { "op": "test", "path": "/l/0", "value": "x" },
+            { "op": "add", "path": "/l/0", "value": "y" },
+        ]));
+        assert!(invert(&p).is_none());
+    }
+
+    #[test]
+    fn migrate() {
+        let migrations = Migrations::new()
+            .with(Migration::new(
+                "add-tags",
+                patch(json!([{ "op": "add", "path": "/tags", "value": [] }])),
+            ))
+            .with(Migration::from_fn("count-tags", |doc| {
+                let count = doc["tags"].as_array().map_or(0, Vec::len);
+                patch(json!([{ "op": "add", "path": "/count", "value": count }]))
+            }))
+            .with(Migration::new(
+                "remove-count",
+                patch(json!([
+                    { "op": "test", "path": "/count", "value": 0 },
+                    { "op": "remove", "path": "/count" },
+                ])),
+            ));
+        assert_eq!(migrations.latest(), 3);
+
+        let mut doc = json!({});
+        let steps = migrations.dry_run(&doc, 0, 2).unwrap();
+        assert_eq!(doc, json!({}));
+        let names: Vec<_> = steps.iter().map(|s| (s.name.as_str(), s.version)).collect();
+        assert_eq!(names, [("add-tags", 1), ("count-tags", 2)]);
+
+        migrations.migrate(&mut doc, 0, 2).unwrap();
+        assert_eq!(doc, json!({ "tags": [], "count": 0 }));
+
+        // `count-tags` builds the patch from the document and cannot be inverted
+        let err = migrations.migrate(&mut doc, 2, 0).unwrap_err();
+        assert!(matches!(err, MigrationError::Irreversible { name } if name == "count-tags"));
+        assert_eq!(doc, json!({ "tags": [], "count": 0 }));
+
+        let err = migrations.migrate(&mut doc, 0, 4).unwrap_err();
+        assert!(matches!(
+            err,
+            MigrationError::UnknownVersion {
+                version: 4,
+                latest: 3
+            }
+        ));
+
+        migrations.migrate(&mut doc, 2, 3).unwrap();
+        assert_eq!(doc, json!({ "tags": [] }));
+        migrations.migrate(&mut doc, 3, 2).unwrap();
+        assert_eq!(doc, json!({ "tags": [], "count": 0 }));
+
+        // Fails at the last step, leaving the document unchanged
+        let mut doc = json!({ "tags": ["a"] });
+        let err = migrations.migrate(&mut doc, 1, 3).unwrap_err();
+        assert!(matches!(err, MigrationError::Patch { name, .. } if name == "remove-count"));
+        assert_eq!(doc, json!({ "tags": ["a"] }));
+    }
+}
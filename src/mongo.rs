@@ -0,0 +1,452 @@
+//! Translation of JSON Patches into MongoDB update documents.
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::{mongo, Patch};
+//! use serde_json::{from_value, json};
+//!
+//! let doc = json!({ "title": "Goodbye!", "tags": ["example"], "draft": true });
+//! let p: Patch = from_value(json!([
+//!   { "op": "test", "path": "/draft", "value": true },
+//!   { "op": "replace", "path": "/title", "value": "Hello!" },
+//!   { "op": "add", "path": "/tags/-", "value": "sample" },
+//!   { "op": "remove", "path": "/draft" },
+//! ])).unwrap();
+//!
+//! let update = mongo::to_update(&p, &doc);
+//! assert!(update.unsupported.is_empty());
+//! assert_eq!(update.filter, json!({ "draft": { "$eq": true } }).as_object().unwrap().clone());
+//! assert_eq!(update.update, json!({
+//!   "$set": { "title": "Hello!" },
+//!   "$push": { "tags": { "$each": ["sample"] } },
+//!   "$unset": { "draft": "" },
+//! }).as_object().unwrap().clone());
+//! ```
+use crate::{PatchOperation, ReplaceOperation};
+use jsonptr::Pointer;
+use serde_json::{json, Map, Value};
+use std::fmt::{self, Display, Formatter};
+
+/// MongoDB update equivalent to a patch, see [`to_update`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct MongoUpdate {
+    /// Query filter with an `$eq` condition for each `test` operation. The update should only be
+    /// applied to the documents matching it. Tests of `null`, arrays and objects are reported in
+    /// [`MongoUpdate::unsupported`] instead, see [`UnsupportedReason::TestValue`].
+    pub filter: Map<String, Value>,
+    /// Update document with update operators, like `$set` and `$unset`.
+    pub update: Map<String, Value>,
+    /// Operations which could not be translated. The update is only equivalent to the patch if
+    /// this is empty.
+    pub unsupported: Vec<Unsupported>,
+}
+
+/// Operation which cannot be expressed as a part of MongoDB update, see
+/// [`MongoUpdate::unsupported`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Unsupported {
+    /// Index of the operation.
+    pub operation: usize,
+    /// Why the operation cannot be expressed.
+    pub reason: UnsupportedReason,
+}
+
+impl Display for Unsupported {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "operation '/{}' ", self.operation)?;
+        match self.reason {
+            UnsupportedReason::Root => write!(f, "replaces the whole document"),
+            UnsupportedReason::FieldName => {
+                write!(
+                    f,
+                    "refers to a field which is empty, contains '.' or starts with '$'"
+                )
+            }
+            UnsupportedReason::ArrayRemove => {
+                write!(
+                    f,
+                    "removes an array element which is neither first nor last"
+                )
+            }
+            UnsupportedReason::ArrayMove => write!(f, "moves a value from or into an array"),
+            UnsupportedReason::Conflict { with } => {
+                write!(f, "conflicts with operation '/{with}'")
+            }
+            UnsupportedReason::TestValue => {
+                write!(f, "tests a null, array or object value")
+            }
+            UnsupportedReason::Failed => write!(f, "cannot be applied to the document"),
+        }
+    }
+}
+
+/// Reason why an operation cannot be translated, see [`Unsupported`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum UnsupportedReason {
+    /// Operation replaces the whole document, which needs a replacement instead of an update.
+    Root,
+    /// Path contains a field name which cannot be used in MongoDB paths.
+    FieldName,
+    /// Operation removes an element from the middle of an array.
+    ArrayRemove,
+    /// Operation moves a value from or into an array, which `$rename` does not support.
+    ArrayMove,
+    /// Operation modifies a field which is modified or tested by an earlier operation. MongoDB
+    /// does not allow updating the same field twice, or a field and its parent, in one update.
+    Conflict {
+        /// Index of the earlier operation.
+        with: usize,
+    },
+    /// Operation tests a `null`, array or object value. MongoDB does not compare those the same
+    /// way: `null` also matches missing fields, an array also matches arrays containing it and
+    /// an object only matches objects with the same order of members.
+    TestValue,
+    /// Operation fails when applied to the document.
+    Failed,
+}
+
+/// Translate the patch into an equivalent MongoDB update of the given document.
+///
+/// Operations are translated as follows:
+///
+/// * `add` and `replace` of object members and array elements become `$set`, inserts into arrays
+///   become `$push` (with `$position`, unless appending);
+/// * `remove` of object members becomes `$unset`, removal of the first or the last array element
+///   becomes `$pop`;
+/// * `move` between object members becomes `$rename`;
+/// * `copy` becomes `$set` or `$push` of the copied value;
+/// * `test` of a string, number or boolean becomes an `$eq` condition of the query filter.
+///
+/// MongoDB applies all update operators at once, so operations modifying the same location, or
+/// testing it after a modification, cannot be translated and are reported in
+/// [`MongoUpdate::unsupported`], along with operations which have no equivalent update operator
+/// or condition.
+pub fn to_update(patch: &[PatchOperation], doc: &Value) -> MongoUpdate {
+    let mut result = MongoUpdate::default();
+    // Fields modified so far, with the operations modifying them
+    let mut modified: Vec<(String, usize)> = Vec::new();
+    let mut current = doc.clone();
+    for (operation, op) in patch.iter().enumerate() {
+        let translated = translate(op, &current);
+        if crate::patch(&mut current, std::slice::from_ref(op)).is_err() {
+            let reason = UnsupportedReason::Failed;
+            result.unsupported.push(Unsupported { operation, reason });
+            continue;
+        }
+        let update = translated.and_then(|update| {
+            if let Some(with) = conflict(&update, &modified, &result.update) {
+                return Err(UnsupportedReason::Conflict { with });
+            }
+            Ok(update)
+        });
+        match update {
+            Ok(Update::Test(field, value)) => {
+                result.filter.insert(field, json!({ "$eq": value }));
+            }
+            Ok(update) => {
+                for field in update.fields() {
+                    modified.push((field.to_owned(), operation));
+                }
+                update.merge_into(&mut result.update);
+            }
+            Err(reason) => result.unsupported.push(Unsupported { operation, reason }),
+        }
+    }
+    result
+}
+
+enum Update {
+    Set(String, Value),
+    Unset(String),
+    Push(String, Value, Option<usize>),
+    Pop(String, i8),
+    Rename(String, String),
+    Test(String, Value),
+}
+
+impl Update {
+    fn fields(&self) -> impl Iterator<Item = &str> {
+        let (first, second) = match self {
+            Update::Set(field, _)
+            | Update::Unset(field)
+            | Update::Push(field, _, _)
+            | Update::Pop(field, _)
+            | Update::Test(field, _) => (field, None),
+            Update::Rename(from, to) => (from, Some(to)),
+        };
+        std::iter::once(first.as_str()).chain(second.map(String::as_str))
+    }
+
+    fn merge_into(self, update: &mut Map<String, Value>) {
+        let (operator, field, value) = match self {
+            Update::Set(field, value) => ("$set", field, value),
+            Update::Unset(field) => ("$unset", field, json!("")),
+            Update::Push(field, value, position) => {
+                let each = update
+                    .get_mut("$push")
+                    .and_then(|push| push.get_mut(&field))
+                    .and_then(|push| push.get_mut("$each"))
+                    .and_then(Value::as_array_mut);
+                if let Some(each) = each {
+                    // Appending to the same array
+                    each.push(value);
+                    return;
+                }
+                let mut push = json!({ "$each": [value] });
+                if let Some(position) = position {
+                    push["$position"] = json!(position);
+                }
+                ("$push", field, push)
+            }
+            Update::Pop(field, direction) => ("$pop", field, json!(direction)),
+            Update::Rename(from, to) => ("$rename", from, Value::String(to)),
+            Update::Test(..) => unreachable!(),
+        };
+        let operator = update
+            .entry(operator)
+            .or_insert_with(|| Value::Object(Map::new()));
+        operator.as_object_mut().unwrap().insert(field, value);
+    }
+}
+
+fn overlaps(left: &str, right: &str) -> bool {
+    let (shorter, longer) = if left.len() <= right.len() {
+        (left, right)
+    } else {
+        (right, left)
+    };
+    longer
+        .strip_prefix(shorter)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+fn conflict(
+    update: &Update,
+    modified: &[(String, usize)],
+    result: &Map<String, Value>,
+) -> Option<usize> {
+    if let Update::Push(field, _, None) = update {
+        // Appends to the same array are combined, unless the array is modified otherwise
+        let appending = result
+            .get("$push")
+            .and_then(|push| push.get(field))
+            .is_some_and(|push| push.get("$position").is_none());
+        if appending {
+            return None;
+        }
+    }
+    update.fields().find_map(|field| {
+        modified
+            .iter()
+            .find(|(modified, _)| overlaps(field, modified))
+            .map(|(_, operation)| *operation)
+    })
+}
+
+fn field(path: &Pointer) -> Result<String, UnsupportedReason> {
+    if path.is_root() {
+        return Err(UnsupportedReason::Root);
+    }
+    let mut names = Vec::new();
+    for token in path.tokens() {
+        let name = token.decoded();
+        if name.is_empty() || name.contains('.') || name.starts_with('$') {
+            return Err(UnsupportedReason::FieldName);
+        }
+        names.push(name.into_owned());
+    }
+    Ok(names.join("."))
+}
+
+// Checks if any of the values on the path (excluding the target itself) is an array.
+fn through_array(doc: &Value, path: &Pointer) -> bool {
+    let mut target = doc;
+    for token in path.tokens() {
+        match target {
+            Value::Array(_) => return true,
+            Value::Object(obj) => match obj.get(token.decoded().as_ref()) {
+                Some(value) => target = value,
+                None => return false,
+            },
+            _ => return false,
+        }
+    }
+    false
+}
+
+fn insert(doc: &Value, path: &Pointer, value: Value) -> Result<Update, UnsupportedReason> {
+    let (parent, last) = path.split_back().ok_or(UnsupportedReason::Root)?;
+    match doc.pointer(parent.as_str()) {
+        Some(Value::Array(arr)) => {
+            let field = field(parent)?;
+            match last.encoded() {
+                "-" => Ok(Update::Push(field, value, None)),
+                idx => match idx.parse() {
+                    Ok(idx) if idx == arr.len() => Ok(Update::Push(field, value, None)),
+                    Ok(idx) => Ok(Update::Push(field, value, Some(idx))),
+                    Err(_) => Err(UnsupportedReason::Failed),
+                },
+            }
+        }
+        _ => Ok(Update::Set(field(path)?, value)),
+    }
+}
+
+fn translate(op: &PatchOperation, doc: &Value) -> Result<Update, UnsupportedReason> {
+    match op {
+        PatchOperation::Add(op) => insert(doc, &op.path, op.value.clone()),
+        PatchOperation::Replace(ReplaceOperation { path, value }) => {
+            Ok(Update::Set(field(path)?, value.clone()))
+        }
+        PatchOperation::Remove(op) => {
+            let (parent, last) = op.path.split_back().ok_or(UnsupportedReason::Root)?;
+            match doc.pointer(parent.as_str()) {
+                Some(Value::Array(arr)) => match last.encoded().parse::<usize>() {
+                    Ok(idx) if idx + 1 == arr.len() => Ok(Update::Pop(field(parent)?, 1)),
+                    Ok(0) => Ok(Update::Pop(field(parent)?, -1)),
+                    _ => Err(UnsupportedReason::ArrayRemove),
+                },
+                _ => Ok(Update::Unset(field(&op.path)?)),
+            }
+        }
+        PatchOperation::Move(op) => {
+            if through_array(doc, &op.from) || through_array(doc, &op.path) {
+                return Err(UnsupportedReason::ArrayMove);
+            }
+            Ok(Update::Rename(field(&op.from)?, field(&op.path)?))
+        }
+        PatchOperation::Copy(op) => {
+            let value = doc
+                .pointer(op.from.as_str())
+                .ok_or(UnsupportedReason::Failed)?;
+            insert(doc, &op.path, value.clone())
+        }
+        PatchOperation::Test(op) => {
+            let field = field(&op.path)?;
+            match op.value {
+                Value::Null | Value::Array(_) | Value::Object(_) => {
+                    Err(UnsupportedReason::TestValue)
+                }
+                ref value => Ok(Update::Test(field, value.clone())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_update, Unsupported, UnsupportedReason};
+    use crate::Patch;
+    use serde_json::{from_value, json, Value};
+
+    fn translate(doc: Value, patch: Value) -> (Value, Value, Vec<Unsupported>) {
+        let patch: Patch = from_value(patch).unwrap();
+        let update = to_update(&patch, &doc);
+        (
+            Value::Object(update.filter),
+            Value::Object(update.update),
+            update.unsupported,
+        )
+    }
+
+    #[test]
+    fn arrays() {
+        let (_, update, unsupported) = translate(
+            json!({ "a": [1, 2, 3], "b": [], "c": [1, 2, 3], "d": [{ "e": 1 }] }),
+            json!([
+                { "op": "add", "path": "/a/0", "value": 0 },
+                { "op": "add", "path": "/b/-", "value": 1 },
+                { "op": "add", "path": "/b/1", "value": 2 },
+                { "op": "remove", "path": "/c/0" },
+                { "op": "replace", "path": "/d/0/e", "value": 2 },
+                { "op": "copy", "from": "/d/0", "path": "/f" },
+            ]),
+        );
+        assert_eq!(
+            update,
+            json!({
+                "$push": {
+                    "a": { "$each": [0], "$position": 0 },
+                    "b": { "$each": [1, 2] },
+                },
+                "$pop": { "c": -1 },
+                "$set": { "d.0.e": 2, "f": { "e": 2 } },
+            })
+        );
+        assert!(unsupported.is_empty());
+    }
+
+    #[test]
+    fn unsupported() {
+        let (filter, update, unsupported) = translate(
+            json!({ "a": [1, 2, 3], "b": { "c": 1 }, "d": [{ "e": 1 }] }),
+            json!([
+                { "op": "remove", "path": "/a/1" },
+                { "op": "add", "path": "/x.y", "value": 1 },
+                { "op": "move", "from": "/d/0/e", "path": "/e" },
+                { "op": "replace", "path": "/b/c", "value": 2 },
+                { "op": "test", "path": "/b/c", "value": 2 },
+                { "op": "remove", "path": "/b" },
+                { "op": "replace", "path": "", "value": {} },
+                { "op": "remove", "path": "/missing" },
+            ]),
+        );
+        let reasons: Vec<_> = unsupported
+            .iter()
+            .map(|u| (u.operation, &u.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                (0, &UnsupportedReason::ArrayRemove),
+                (1, &UnsupportedReason::FieldName),
+                (2, &UnsupportedReason::ArrayMove),
+                (4, &UnsupportedReason::Conflict { with: 3 }),
+                (5, &UnsupportedReason::Conflict { with: 3 }),
+                (6, &UnsupportedReason::Root),
+                (7, &UnsupportedReason::Failed),
+            ]
+        );
+        assert_eq!(
+            unsupported[4].to_string(),
+            "operation '/5' conflicts with operation '/3'"
+        );
+        assert_eq!(filter, json!({}));
+        assert_eq!(update, json!({ "$set": { "b.c": 2 } }));
+    }
+
+    #[test]
+    fn tests() {
+        let (filter, update, unsupported) = translate(
+            json!({ "a": "x", "b": 1, "c": null, "d": [1], "e": { "f": true } }),
+            json!([
+                { "op": "test", "path": "/a", "value": "x" },
+                { "op": "test", "path": "/e/f", "value": true },
+                { "op": "test", "path": "/c", "value": null },
+                { "op": "test", "path": "/d", "value": [1] },
+                { "op": "test", "path": "/e", "value": { "f": true } },
+                { "op": "replace", "path": "/b", "value": 2 },
+            ]),
+        );
+        assert_eq!(
+            filter,
+            json!({ "a": { "$eq": "x" }, "e.f": { "$eq": true } })
+        );
+        assert_eq!(update, json!({ "$set": { "b": 2 } }));
+        let reasons: Vec<_> = unsupported
+            .iter()
+            .map(|u| (u.operation, &u.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                (2, &UnsupportedReason::TestValue),
+                (3, &UnsupportedReason::TestValue),
+                (4, &UnsupportedReason::TestValue),
+            ]
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{patch, AddOperation, CopyOperation, MoveOperation, Patch, PatchError, PatchOperation};
+use jsonptr::PointerBuf;
+use serde_json::Value;
+
+impl Patch {
+    /// Rewrites `add` and `remove` operations of this patch into `move` and `copy` operations
+    /// where the values match, so the patch gets smaller while having the same effect on the
+    /// given document.
+    ///
+    /// An `add` and a `remove` of the same value are combined into a `move` (in place of the
+    /// `add`), and an `add` of a value which exists elsewhere in the document at that point is
+    /// turned into a `copy` if that makes the operation smaller. Each rewrite is only kept if the
+    /// rewritten patch gives the same result on the document, so the returned patch is not
+    /// guaranteed to be equivalent to this one on other documents. Fails if this patch cannot be
+    /// applied to the document.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::Patch;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let doc = json!({ "draft": { "title": "Hello!", "tags": ["a", "b"] } });
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "remove", "path": "/draft" },
+    ///   { "op": "add", "path": "/published", "value": { "title": "Hello!", "tags": ["a", "b"] } },
+    ///   { "op": "add", "path": "/backup", "value": { "title": "Hello!", "tags": ["a", "b"] } },
+    /// ])).unwrap();
+    ///
+    /// assert_eq!(p.detect_moves(&doc).unwrap(), from_value::<Patch>(json!([
+    ///   { "op": "move", "from": "/draft", "path": "/published" },
+    ///   { "op": "copy", "from": "/published", "path": "/backup" },
+    /// ])).unwrap());
+    /// ```
+    pub fn detect_moves(&self, doc: &Value) -> Result<Patch, PatchError> {
+        let mut expected = doc.clone();
+        patch(&mut expected, self)?;
+        let gives_expected = |candidate: &[PatchOperation]| {
+            let mut patched = doc.clone();
+            patch(&mut patched, candidate).is_ok() && patched == expected
+        };
+
+        // Values removed by the `remove` operations, by index
+        let mut removed = Vec::with_capacity(self.len());
+        let mut current = doc.clone();
+        for op in self.iter() {
+            removed.push(match op {
+                PatchOperation::Remove(remove) => current.pointer(remove.path.as_str()).cloned(),
+                _ => None,
+            });
+            patch(&mut current, std::slice::from_ref(op))?;
+        }
+
+        let mut ops: Vec<Option<PatchOperation>> = self.iter().cloned().map(Some).collect();
+        let flatten = |ops: &[Option<PatchOperation>]| -> Vec<PatchOperation> {
+            ops.iter().flatten().cloned().collect()
+        };
+        for idx in 0..ops.len() {
+            let Some(PatchOperation::Add(AddOperation { path, value })) = ops[idx].clone() else {
+                continue;
+            };
+            let sources = (0..ops.len()).filter(|remove_idx| {
+                ops[*remove_idx].is_some() && removed[*remove_idx].as_ref() == Some(&value)
+            });
+            for remove_idx in sources {
+                let Some(PatchOperation::Remove(remove)) = ops[remove_idx].clone() else {
+                    continue;
+                };
+                let mut candidate = ops.clone();
+                candidate[idx] = Some(PatchOperation::Move(MoveOperation {
+                    from: remove.path,
+                    path: path.clone(),
+                }));
+                candidate[remove_idx] = None;
+                if gives_expected(&flatten(&candidate)) {
+                    ops = candidate;
+                    break;
+                }
+            }
+        }
+
+        for idx in 0..ops.len() {
+            let Some(add) = ops[idx].clone() else {
+                continue;
+            };
+            let PatchOperation::Add(AddOperation { path, value }) = &add else {
+                continue;
+            };
+            let mut current = doc.clone();
+            patch(&mut current, &flatten(&ops[..idx]))?;
+            let mut sources = Vec::new();
+            find_value(&current, value, &mut PointerBuf::new(), &mut sources);
+            for from in sources {
+                let copy = PatchOperation::Copy(CopyOperation {
+                    from,
+                    path: path.clone(),
+                });
+                if copy.estimated_size() >= add.estimated_size() {
+                    continue;
+                }
+                let mut candidate = ops.clone();
+                candidate[idx] = Some(copy);
+                if gives_expected(&flatten(&candidate)) {
+                    ops = candidate;
+                    break;
+                }
+            }
+        }
+        Ok(Patch(flatten(&ops)))
+    }
+}
+
+// Collects the locations of the values equal to the given one, in document order
+fn find_value(doc: &Value, value: &Value, path: &mut PointerBuf, found: &mut Vec<PointerBuf>) {
+    if doc == value {
+        found.push(path.clone());
+        return;
+    }
+    match doc {
+        Value::Object(obj) => {
+            for (key, member) in obj {
+                path.push_back(key.as_str());
+                find_value(member, value, path, found);
+                path.pop_back();
+            }
+        }
+        Value::Array(arr) => {
+            for (idx, element) in arr.iter().enumerate() {
+                path.push_back(idx);
+                find_value(element, value, path, found);
+                path.pop_back();
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Patch;
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn array_moves() {
+        let doc = json!({ "items": ["a", "b", "c"], "done": [] });
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "/done/0", "value": "b" },
+            { "op": "remove", "path": "/items/1" },
+            { "op": "add", "path": "/items/0", "value": "z" },
+        ]))
+        .unwrap();
+        assert_eq!(
+            p.detect_moves(&doc).unwrap(),
+            from_value::<Patch>(json!([
+                { "op": "move", "from": "/items/1", "path": "/done/0" },
+                { "op": "add", "path": "/items/0", "value": "z" },
+            ]))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn keeps_result() {
+        // Moving the value would change what the `test` sees
+        let doc = json!({ "a": { "x": 1 } });
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "/b", "value": { "x": 1 } },
+            { "op": "test", "path": "/a", "value": { "x": 1 } },
+            { "op": "remove", "path": "/a" },
+        ]))
+        .unwrap();
+        assert_eq!(
+            p.detect_moves(&doc).unwrap(),
+            from_value::<Patch>(json!([
+                { "op": "copy", "from": "/a", "path": "/b" },
+                { "op": "test", "path": "/a", "value": { "x": 1 } },
+                { "op": "remove", "path": "/a" },
+            ]))
+            .unwrap()
+        );
+
+        let p: Patch = from_value(json!([{ "op": "remove", "path": "/b" }])).unwrap();
+        assert!(p.detect_moves(&doc).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+//! Encoding and decoding of JSON Patches and documents as [MessagePack](https://msgpack.org).
+//!
+//! Operations are encoded as maps with `op`, `path`, `from` and `value` members, same as in JSON,
+//! so patches can be exchanged with other MessagePack implementations.
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::{msgpack, Patch};
+//! use serde_json::{from_value, json};
+//!
+//! let p: Patch = from_value(json!([
+//!   { "op": "add", "path": "/0/happy", "value": true }
+//! ])).unwrap();
+//! let doc = msgpack::document_to_vec(&json!([{ "name": "Andrew" }])).unwrap();
+//!
+//! let bytes = msgpack::to_vec(&p).unwrap();
+//! assert_eq!(msgpack::from_slice(&bytes).unwrap(), p);
+//!
+//! let patched = msgpack::patch_slice(&doc, &bytes).unwrap();
+//! assert_eq!(
+//!     msgpack::document_from_slice(&patched).unwrap(),
+//!     json!([{ "name": "Andrew", "happy": true }])
+//! );
+//! ```
+use crate::{Patch, PatchError};
+use serde_json::Value;
+use std::io;
+use thiserror::Error;
+
+/// Error that can occur while encoding a patch or a document as MessagePack.
+pub type EncodeError = rmp_serde::encode::Error;
+
+/// Error that can occur while decoding a patch or a document from MessagePack.
+pub type DecodeError = rmp_serde::decode::Error;
+
+/// This type represents all possible errors that can occur when patching a MessagePack document
+/// with [`patch_slice`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Document or patch is not valid MessagePack.
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    /// Patched document could not be encoded.
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
+    /// Patch could not be applied.
+    #[error(transparent)]
+    Patch(#[from] PatchError),
+}
+
+/// Encode the patch as MessagePack into the given writer.
+pub fn to_writer<W: io::Write>(patch: &Patch, mut writer: W) -> Result<(), EncodeError> {
+    rmp_serde::encode::write_named(&mut writer, patch)
+}
+
+/// Encode the patch as a MessagePack byte vector.
+pub fn to_vec(patch: &Patch) -> Result<Vec<u8>, EncodeError> {
+    rmp_serde::to_vec_named(patch)
+}
+
+/// Decode a patch from MessagePack read from the given reader.
+pub fn from_reader<R: io::Read>(reader: R) -> Result<Patch, DecodeError> {
+    rmp_serde::from_read(reader)
+}
+
+/// Decode a patch from a MessagePack byte slice.
+pub fn from_slice(bytes: &[u8]) -> Result<Patch, DecodeError> {
+    rmp_serde::from_slice(bytes)
+}
+
+/// Encode the document as a MessagePack byte vector.
+pub fn document_to_vec(doc: &Value) -> Result<Vec<u8>, EncodeError> {
+    rmp_serde::to_vec_named(doc)
+}
+
+/// Decode a document from a MessagePack byte slice.
+pub fn document_from_slice(bytes: &[u8]) -> Result<Value, DecodeError> {
+    rmp_serde::from_slice(bytes)
+}
+
+/// Decode a document and a patch from MessagePack, apply the patch and encode the patched
+/// document back.
+pub fn patch_slice(doc: &[u8], patch: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut doc = document_from_slice(doc)?;
+    let patch = from_slice(patch)?;
+    crate::patch(&mut doc, &patch)?;
+    Ok(document_to_vec(&doc)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Patch;
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn roundtrip() {
+        let patch: Patch = from_value(json!([
+            { "op": "add", "path": "/a~1b/0", "value": { "x": [1, 2.5, null, "s"] } },
+            { "op": "remove", "path": "/c" },
+            { "op": "replace", "path": "", "value": false },
+            { "op": "move", "from": "/d", "path": "/e" },
+            { "op": "copy", "from": "/e", "path": "/f" },
+            { "op": "test", "path": "/f", "value": -1 },
+        ]))
+        .unwrap();
+
+        let bytes = super::to_vec(&patch).unwrap();
+        assert!(bytes.len() < patch.to_string().len());
+        assert_eq!(super::from_slice(&bytes).unwrap(), patch);
+
+        let mut buf = Vec::new();
+        super::to_writer(&patch, &mut buf).unwrap();
+        assert_eq!(super::from_reader(&buf[..]).unwrap(), patch);
+    }
+
+    #[test]
+    fn patch_slice() {
+        let doc = super::document_to_vec(&json!({ "a": 1 })).unwrap();
+        let patch: Patch = from_value(json!([{ "op": "test", "path": "/a", "value": 2 }])).unwrap();
+        let patch = super::to_vec(&patch).unwrap();
+        assert!(matches!(
+            super::patch_slice(&doc, &patch),
+            Err(super::Error::Patch(_))
+        ));
+        assert!(matches!(
+            super::patch_slice(&patch[1..], &doc),
+            Err(super::Error::Decode(_))
+        ));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{diff::diff_impl, strip_pointer_prefix, DiffOptions, Patch};
+use jsonptr::PointerBuf;
+use serde_json::Value;
+
+/// Start recording changes made to the document. See [`Observer`].
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{observe, Patch};
+/// use serde_json::{from_value, json};
+///
+/// let mut doc = json!({
+///   "title": "Goodbye!",
+///   "author": { "givenName": "John", "familyName": "Doe" },
+///   "tags": ["example", "sample"],
+/// });
+///
+/// let mut observer = observe(&mut doc);
+/// let author = observer.pointer_mut("/author").unwrap().as_object_mut().unwrap();
+/// author.remove("familyName");
+/// observer.pointer_mut("/tags").unwrap().as_array_mut().unwrap().pop();
+/// *observer.pointer_mut("/title").unwrap() = json!("Hello!");
+///
+/// let p = observer.finish();
+/// assert_eq!(p, from_value::<Patch>(json!([
+///   { "op": "remove", "path": "/author/familyName" },
+///   { "op": "remove", "path": "/tags/1" },
+///   { "op": "replace", "path": "/title", "value": "Hello!" },
+/// ])).unwrap());
+/// ```
+pub fn observe(doc: &mut Value) -> Observer<'_> {
+    Observer {
+        doc,
+        snapshots: Vec::new(),
+    }
+}
+
+/// Mutable view of a JSON document which records changes made through it, created by
+/// [`observe`].
+///
+/// Only the sub-documents handed out by [`Observer::pointer_mut`] are copied (once, on first
+/// access) and compared when the observer is finished, so small edits of a large document do not
+/// need a copy and a diff of the whole document.
+#[derive(Debug)]
+pub struct Observer<'a> {
+    doc: &'a mut Value,
+    // Original values of the sub-documents that could have been changed. Locations never
+    // overlap: taking a snapshot of a parent absorbs the snapshots of its children.
+    snapshots: Vec<(PointerBuf, Value)>,
+}
+
+impl Observer<'_> {
+    /// Current state of the document.
+    pub fn doc(&self) -> &Value {
+        self.doc
+    }
+
+    /// Looks up a value by a JSON Pointer and returns a mutable reference to it, same as
+    /// [`Value::pointer_mut`]. Any changes made through the reference are recorded.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        let path = PointerBuf::parse(pointer).ok()?;
+        let current = self.doc.pointer(pointer)?;
+        let observed = self
+            .snapshots
+            .iter()
+            .any(|(location, _)| strip_pointer_prefix(&path, location).is_some());
+        if !observed {
+            let mut original = current.clone();
+            let mut idx = 0;
+            while idx < self.snapshots.len() {
+                let Some(rest) = strip_pointer_prefix(&self.snapshots[idx].0, &path) else {
+                    idx += 1;
+                    continue;
+                };
+                // Parents of observed locations are never modified, so the location still exists
+                let (_, value) = self.snapshots.swap_remove(idx);
+                *original.pointer_mut(rest.as_str()).unwrap() = value;
+            }
+            self.snapshots.push((path, original));
+        }
+        self.doc.pointer_mut(pointer)
+    }
+
+    /// Stop recording and return the patch transforming the original document into the current
+    /// one.
+    pub fn finish(self) -> Patch {
+        self.finish_with(&DiffOptions::default())
+    }
+
+    /// Stop recording and return the patch transforming the original document into the current
+    /// one, comparing changed sub-documents using the given options.
+    pub fn finish_with(mut self, options: &DiffOptions) -> Patch {
+        self.snapshots
+            .sort_by(|(left, _), (right, _)| left.cmp(right));
+        let mut patch = Patch::default();
+        for (mut path, original) in self.snapshots {
+            let current = self.doc.pointer(path.as_str()).unwrap();
+            diff_impl(&original, current, &mut path, &mut patch, options);
+        }
+        patch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    #[test]
+    fn nested_observations() {
+        let original = json!({ "a": { "b": { "c": 1 }, "d": [1, 2] }, "e": 3 });
+        let mut doc = original.clone();
+
+        let mut observer = super::observe(&mut doc);
+        assert!(observer.pointer_mut("/x").is_none());
+        assert!(observer.pointer_mut("a").is_none());
+        *observer.pointer_mut("/a/b/c").unwrap() = json!(2);
+        observer
+            .pointer_mut("/a/d")
+            .unwrap()
+            .as_array_mut()
+            .unwrap()
+            .push(json!(3));
+        // Parent absorbs both children
+        *observer.pointer_mut("/a/b").unwrap() = json!({ "c": 2, "f": 4 });
+        observer.pointer_mut("/a").unwrap()["g"] = json!(5);
+        *observer.pointer_mut("/a/b/f").unwrap() = json!(6);
+        assert_eq!(observer.snapshots.len(), 1);
+        assert_eq!(observer.doc()["a"]["b"], json!({ "c": 2, "f": 6 }));
+
+        let p = observer.finish();
+        assert_eq!(
+            p,
+            serde_json::from_value(json!([
+                { "op": "replace", "path": "/a/b/c", "value": 2 },
+                { "op": "add", "path": "/a/b/f", "value": 6 },
+                { "op": "add", "path": "/a/d/2", "value": 3 },
+                { "op": "add", "path": "/a/g", "value": 5 },
+            ]))
+            .unwrap()
+        );
+
+        let mut patched = original.clone();
+        crate::patch(&mut patched, &p).unwrap();
+        assert_eq!(patched, doc);
+    }
+
+    #[test]
+    fn unchanged_observations() {
+        let mut doc = json!({ "a": [1, 2], "b": 1 });
+        let mut observer = super::observe(&mut doc);
+        observer.pointer_mut("/a/0").unwrap();
+        observer.pointer_mut("/b").unwrap();
+        assert!(observer.finish().is_empty());
+    }
+}
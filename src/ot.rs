@@ -0,0 +1,426 @@
+//! Operational transformation of concurrent patches, for collaborative editing.
+//!
+//! The server keeps the history of patches applied to the document. A client sends a patch
+//! made against an older version; [`transform`] rebases it onto the latest version and produces
+//! the patch the client needs to apply to its own copy to catch up with the server. Both sides
+//! end up with the same document.
+//!
+//! Documents are not available to the transformation, so tokens which look like array indices
+//! (numbers and `-`) are treated as array indices. When both sides change the same location,
+//! the server wins. Two concurrent appends with the `-` index are both kept, but not necessarily
+//! in the same order on both sides. `move` operations are transformed as a removal followed by
+//! an insertion, with values inside the moved location following it.
+use crate::{
+    strip_pointer_prefix, AddOperation, CopyOperation, MoveOperation, Patch, PatchOperation,
+    RemoveOperation, ReplaceOperation, TestOperation,
+};
+use jsonptr::{index::Index, Pointer, PointerBuf};
+
+/// Result of [`transform`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Transformed {
+    /// Client patch rebased onto the latest version of the document, to apply on the server.
+    pub patch: Patch,
+    /// Server history rebased onto the client patch, to apply on the client.
+    pub reply: Patch,
+}
+
+/// Transform the `client` patch, made against the version before `history`, so it can be applied
+/// after all patches of `history`.
+///
+/// Operations of the client patch which conflict with the history (for example, modify a value
+/// removed on the server) are dropped. Applying [`Transformed::patch`] to the latest version
+/// gives the same document as applying [`Transformed::reply`] to the client version.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{ot, patch, Patch};
+/// use serde_json::{from_value, json};
+///
+/// let base = json!({ "tags": ["a", "b"] });
+/// let history: Vec<Patch> = from_value(json!([
+///   [{ "op": "add", "path": "/tags/0", "value": "x" }],
+/// ])).unwrap();
+/// let client: Patch = from_value(json!([
+///   { "op": "replace", "path": "/tags/1", "value": "c" },
+/// ])).unwrap();
+///
+/// let result = ot::transform(&history, &client);
+/// assert_eq!(result.patch, from_value::<Patch>(json!([
+///   { "op": "replace", "path": "/tags/2", "value": "c" },
+/// ])).unwrap());
+///
+/// let mut server = base.clone();
+/// patch(&mut server, &history[0]).unwrap();
+/// patch(&mut server, &result.patch).unwrap();
+///
+/// let mut local = base.clone();
+/// patch(&mut local, &client).unwrap();
+/// patch(&mut local, &result.reply).unwrap();
+///
+/// assert_eq!(server, json!({ "tags": ["x", "a", "c"] }));
+/// assert_eq!(local, server);
+/// ```
+pub fn transform(history: &[Patch], client: &Patch) -> Transformed {
+    let mut ops: Vec<PatchOperation> = client.to_vec();
+    let mut reply = Vec::new();
+    for server in history.iter().flat_map(|p| p.iter()) {
+        let mut server = Some(server.clone());
+        let mut rebased = Vec::with_capacity(ops.len());
+        for op in ops {
+            let Some(current) = server.take() else {
+                rebased.push(op);
+                continue;
+            };
+            rebased.extend(transform_operation(&op, &current, false));
+            server = transform_operation(&current, &op, true);
+        }
+        ops = rebased;
+        reply.extend(server);
+    }
+    Transformed {
+        patch: Patch(ops),
+        reply: Patch(reply),
+    }
+}
+
+/// Transform the operation `op` to apply after the concurrent operation `applied`. Returns `None`
+/// if the operation conflicts with `applied` and should be dropped. If `wins` is set, `op` takes
+/// precedence when both operations change the same location.
+fn transform_operation(
+    op: &PatchOperation,
+    applied: &PatchOperation,
+    wins: bool,
+) -> Option<PatchOperation> {
+    let Some(change) = Change::of(applied) else {
+        return Some(op.clone());
+    };
+    let map = |ptr: &Pointer, role: Role| change.map(ptr, role, wins);
+    Some(match op {
+        PatchOperation::Add(AddOperation { path, value }) => PatchOperation::Add(AddOperation {
+            path: map(path, Role::insert(path))?,
+            value: value.clone(),
+        }),
+        PatchOperation::Remove(RemoveOperation { path }) => {
+            PatchOperation::Remove(RemoveOperation {
+                path: map(path, Role::Remove)?,
+            })
+        }
+        PatchOperation::Replace(ReplaceOperation { path, value }) => {
+            let mapped = map(path, Role::Set)?;
+            // Value removed by the losing operation is put back
+            if matches!(applied, PatchOperation::Remove(removed) if removed.path == *path) {
+                PatchOperation::Add(AddOperation {
+                    path: mapped,
+                    value: value.clone(),
+                })
+            } else {
+                PatchOperation::Replace(ReplaceOperation {
+                    path: mapped,
+                    value: value.clone(),
+                })
+            }
+        }
+        PatchOperation::Move(MoveOperation { from, path }) => PatchOperation::Move(MoveOperation {
+            from: map(from, Role::Remove)?,
+            path: map(path, Role::insert(path))?,
+        }),
+        PatchOperation::Copy(CopyOperation { from, path }) => PatchOperation::Copy(CopyOperation {
+            from: map(from, Role::Read)?,
+            path: map(path, Role::insert(path))?,
+        }),
+        PatchOperation::Test(TestOperation { path, value }) => {
+            PatchOperation::Test(TestOperation {
+                path: map(path, Role::Test)?,
+                value: value.clone(),
+            })
+        }
+    })
+}
+
+/// How an operation uses the location.
+#[derive(Clone, Copy, PartialEq)]
+enum Role {
+    /// Inserts a new array element.
+    Insert,
+    /// Sets the value (including `add` of an object member).
+    Set,
+    /// Removes the value.
+    Remove,
+    /// Checks the value with `test`.
+    Test,
+    /// Reads the value as the source of `copy`.
+    Read,
+}
+
+impl Role {
+    fn insert(path: &Pointer) -> Role {
+        if index(path).is_some() {
+            Role::Insert
+        } else {
+            Role::Set
+        }
+    }
+}
+
+/// Structural change done by an operation.
+enum Change<'a> {
+    /// Element inserted into an array.
+    Insert(&'a Pointer),
+    /// Value set at the location.
+    Set(&'a Pointer),
+    /// Value removed from the location.
+    Remove(&'a Pointer),
+    /// Value moved from one location to another.
+    Move(&'a Pointer, &'a Pointer),
+}
+
+impl Change<'_> {
+    fn of(op: &PatchOperation) -> Option<Change<'_>> {
+        Some(match op {
+            PatchOperation::Add(AddOperation { path, .. })
+            | PatchOperation::Copy(CopyOperation { path, .. }) => Change::insert(path),
+            PatchOperation::Replace(ReplaceOperation { path, .. }) => Change::Set(path),
+            PatchOperation::Remove(RemoveOperation { path }) => Change::Remove(path),
+            PatchOperation::Move(MoveOperation { from, path }) => Change::Move(from, path),
+            PatchOperation::Test(_) => return None,
+        })
+    }
+
+    fn insert(path: &Pointer) -> Change<'_> {
+        if index(path).is_some() {
+            Change::Insert(path)
+        } else {
+            Change::Set(path)
+        }
+    }
+
+    /// Maps the location used by a concurrent operation to the location after this change.
+    /// Returns `None` if the concurrent operation should be dropped.
+    fn map(&self, ptr: &Pointer, role: Role, wins: bool) -> Option<PointerBuf> {
+        match *self {
+            Change::Insert(location) => Some(insert(location, ptr, role, wins)),
+            Change::Set(location) => set(location, ptr, role, wins),
+            Change::Remove(location) => remove(location, ptr, role, wins),
+            Change::Move(from, to) => {
+                if ptr != from || role != Role::Insert {
+                    if let Some(rest) = strip_pointer_prefix(ptr, from) {
+                        return Some(to.concat(&rest));
+                    }
+                }
+                let ptr = remove(from, ptr, role, wins)?;
+                Change::insert(to).map(&ptr, role, wins)
+            }
+        }
+    }
+}
+
+fn insert(location: &Pointer, ptr: &Pointer, role: Role, wins: bool) -> PointerBuf {
+    let Some((parent, Index::Num(inserted))) = index(location) else {
+        return ptr.to_buf();
+    };
+    match element(parent, ptr) {
+        // Concurrent insertion at the same index goes after the winning one
+        Some((idx, rest)) if idx == inserted && rest.is_root() && role == Role::Insert && wins => {
+            ptr.to_buf()
+        }
+        Some((idx, rest)) if idx >= inserted => with_element(parent, idx + 1, rest),
+        _ => ptr.to_buf(),
+    }
+}
+
+fn set(location: &Pointer, ptr: &Pointer, role: Role, wins: bool) -> Option<PointerBuf> {
+    if ptr == location {
+        return match role {
+            // Inserts before the element, the element itself is not used
+            Role::Insert | Role::Read => Some(ptr.to_buf()),
+            Role::Set | Role::Remove => wins.then(|| ptr.to_buf()),
+            Role::Test => None,
+        };
+    }
+    if strip_pointer_prefix(ptr, location).is_some() {
+        return None;
+    }
+    Some(ptr.to_buf())
+}
+
+fn remove(location: &Pointer, ptr: &Pointer, role: Role, wins: bool) -> Option<PointerBuf> {
+    if ptr == location {
+        return match role {
+            Role::Insert => Some(ptr.to_buf()),
+            Role::Set => wins.then(|| ptr.to_buf()),
+            Role::Remove | Role::Test | Role::Read => None,
+        };
+    }
+    if strip_pointer_prefix(ptr, location).is_some() {
+        return None;
+    }
+    let Some((parent, Index::Num(removed))) = index(location) else {
+        return Some(ptr.to_buf());
+    };
+    match element(parent, ptr) {
+        Some((idx, rest)) if idx > removed => Some(with_element(parent, idx - 1, rest)),
+        _ => Some(ptr.to_buf()),
+    }
+}
+
+// Splits the pointer into the parent and the array index, if the last token looks like one.
+fn index(ptr: &Pointer) -> Option<(&Pointer, Index)> {
+    let (parent, last) = ptr.split_back()?;
+    Some((parent, last.to_index().ok()?))
+}
+
+// Finds the index of the `parent` array element containing the location, and the location
+// within the element.
+fn element<'a>(parent: &Pointer, ptr: &'a Pointer) -> Option<(usize, &'a Pointer)> {
+    let rest = ptr.strip_prefix(parent)?;
+    let (token, rest) = rest.split_front()?;
+    match token.to_index() {
+        Ok(Index::Num(idx)) => Some((idx, rest)),
+        _ => None,
+    }
+}
+
+fn with_element(parent: &Pointer, idx: usize, rest: &Pointer) -> PointerBuf {
+    parent.with_trailing_token(idx).concat(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::transform;
+    use crate::{patch, Patch};
+    use serde_json::{from_value, json, Value};
+
+    fn patches(value: Value) -> Patch {
+        from_value(value).unwrap()
+    }
+
+    // Applies both sides of the transformation and checks they converge.
+    fn converge(base: Value, server: Value, client: Value) -> (Patch, Value) {
+        let server = patches(server);
+        let client = patches(client);
+        let result = transform(std::slice::from_ref(&server), &client);
+
+        let mut on_server = base.clone();
+        patch(&mut on_server, &server).unwrap();
+        patch(&mut on_server, &result.patch).unwrap();
+
+        let mut on_client = base;
+        patch(&mut on_client, &client).unwrap();
+        patch(&mut on_client, &result.reply).unwrap();
+
+        assert_eq!(on_server, on_client);
+        (result.patch, on_server)
+    }
+
+    #[test]
+    fn array_indices() {
+        let base = json!({ "list": [0, 1, 2, 3] });
+        let (p, doc) = converge(
+            base.clone(),
+            json!([{ "op": "remove", "path": "/list/0" }]),
+            json!([
+                { "op": "replace", "path": "/list/2", "value": 20 },
+                { "op": "add", "path": "/list/4", "value": 4 },
+            ]),
+        );
+        assert_eq!(
+            p,
+            patches(json!([
+                { "op": "replace", "path": "/list/1", "value": 20 },
+                { "op": "add", "path": "/list/3", "value": 4 },
+            ]))
+        );
+        assert_eq!(doc, json!({ "list": [1, 20, 3, 4] }));
+
+        let (_, doc) = converge(
+            base,
+            json!([{ "op": "add", "path": "/list/1", "value": "s" }]),
+            json!([{ "op": "add", "path": "/list/1", "value": "c" }]),
+        );
+        assert_eq!(doc, json!({ "list": [0, "s", "c", 1, 2, 3] }));
+    }
+
+    #[test]
+    fn conflicts() {
+        let base = json!({ "a": { "b": 1 }, "c": 1, "list": [0, 1] });
+        let (p, doc) = converge(
+            base.clone(),
+            json!([
+                { "op": "remove", "path": "/a" },
+                { "op": "replace", "path": "/c", "value": 2 },
+                { "op": "remove", "path": "/list/1" },
+            ]),
+            json!([
+                { "op": "replace", "path": "/a/b", "value": 2 },
+                { "op": "replace", "path": "/c", "value": 3 },
+                { "op": "remove", "path": "/list/1" },
+                { "op": "add", "path": "/d", "value": 4 },
+            ]),
+        );
+        assert_eq!(
+            p,
+            patches(json!([{ "op": "add", "path": "/d", "value": 4 }]))
+        );
+        assert_eq!(doc, json!({ "c": 2, "d": 4, "list": [0] }));
+
+        let (_, doc) = converge(
+            base,
+            json!([{ "op": "replace", "path": "/c", "value": 2 }]),
+            json!([{ "op": "remove", "path": "/c" }]),
+        );
+        assert_eq!(doc, json!({ "a": { "b": 1 }, "c": 2, "list": [0, 1] }));
+    }
+
+    #[test]
+    fn moved_values() {
+        let (p, doc) = converge(
+            json!({ "a": { "b": 1 }, "list": [0, 1, 2] }),
+            json!([
+                { "op": "move", "from": "/a", "path": "/x" },
+                { "op": "move", "from": "/list/0", "path": "/list/2" },
+            ]),
+            json!([
+                { "op": "replace", "path": "/a/b", "value": 2 },
+                { "op": "replace", "path": "/list/1", "value": 10 },
+            ]),
+        );
+        assert_eq!(
+            p,
+            patches(json!([
+                { "op": "replace", "path": "/x/b", "value": 2 },
+                { "op": "replace", "path": "/list/0", "value": 10 },
+            ]))
+        );
+        assert_eq!(doc, json!({ "x": { "b": 2 }, "list": [10, 2, 0] }));
+    }
+
+    #[test]
+    fn history() {
+        let base = json!({ "list": [] });
+        let history: Vec<Patch> = from_value(json!([
+            [{ "op": "add", "path": "/list/0", "value": "a" }],
+            [{ "op": "add", "path": "/list/0", "value": "b" }],
+        ]))
+        .unwrap();
+        let client = patches(json!([
+            { "op": "add", "path": "/list/0", "value": "c" },
+            { "op": "add", "path": "/title", "value": "t" },
+        ]));
+        let result = transform(&history, &client);
+
+        let mut on_server = base.clone();
+        for p in &history {
+            patch(&mut on_server, p).unwrap();
+        }
+        patch(&mut on_server, &result.patch).unwrap();
+
+        let mut on_client = base;
+        patch(&mut on_client, &client).unwrap();
+        patch(&mut on_client, &result.reply).unwrap();
+
+        assert_eq!(on_server, json!({ "list": ["b", "a", "c"], "title": "t" }));
+        assert_eq!(on_client, on_server);
+    }
+}
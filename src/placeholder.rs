@@ -0,0 +1,187 @@
+use jsonptr::PointerBuf;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+
+/// Values resolved while applying a patch, see [`PatchOptions::placeholders`].
+///
+/// A placeholder is an object with a single `$placeholder` member holding the name of the value,
+/// like `{ "$placeholder": "now" }`. Placeholders in the values of `add` and `replace` operations,
+/// at any depth, are replaced with the values produced by the resolvers registered under their
+/// names, so clients can ask for values only known to the server applying the patch (current
+/// time, generated identifiers, the authenticated user). Placeholders with no registered
+/// resolver fail the patch with [`PatchErrorKind::UnknownPlaceholder`].
+///
+/// Resolvers are called once per placeholder, so, for example, two placeholders for a generated
+/// identifier get different values.
+///
+/// [`PatchOptions::placeholders`]: crate::PatchOptions::placeholders
+/// [`PatchErrorKind::UnknownPlaceholder`]: crate::PatchErrorKind::UnknownPlaceholder
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{patch_with, Patch, PatchOptions, Placeholders};
+/// use serde_json::{from_value, json};
+///
+/// let mut options = PatchOptions::default();
+/// options.placeholders = Some(
+///     Placeholders::new()
+///         .resolver("now", || json!("2024-05-01T12:00:00Z"))
+///         .value("user", json!("andrew")),
+/// );
+///
+/// let mut doc = json!({ "title": "Draft" });
+/// let p: Patch = from_value(json!([
+///   { "op": "replace", "path": "/title", "value": "Final" },
+///   { "op": "add", "path": "/updated", "value": {
+///     "at": { "$placeholder": "now" },
+///     "by": { "$placeholder": "user" },
+///   } },
+/// ])).unwrap();
+///
+/// patch_with(&mut doc, &p, &options).unwrap();
+/// assert_eq!(doc, json!({
+///   "title": "Final",
+///   "updated": { "at": "2024-05-01T12:00:00Z", "by": "andrew" },
+/// }));
+/// ```
+#[derive(Clone, Default)]
+#[allow(clippy::type_complexity)]
+pub struct Placeholders {
+    resolvers: BTreeMap<String, Arc<dyn Fn() -> Value + Send + Sync>>,
+}
+
+impl Placeholders {
+    /// Name of the member marking an object as a placeholder.
+    pub const MEMBER: &'static str = "$placeholder";
+
+    /// Creates an empty set of placeholders.
+    pub fn new() -> Self {
+        Placeholders::default()
+    }
+
+    /// Registers a function producing the value of the placeholder with the given name.
+    pub fn resolver(
+        mut self,
+        name: impl Into<String>,
+        f: impl Fn() -> Value + Send + Sync + 'static,
+    ) -> Self {
+        self.resolvers.insert(name.into(), Arc::new(f));
+        self
+    }
+
+    /// Registers a fixed value of the placeholder with the given name.
+    pub fn value(self, name: impl Into<String>, value: Value) -> Self {
+        self.resolver(name, move || value.clone())
+    }
+
+    /// Replaces the placeholders in the value located at `path`. On failure, returns the location
+    /// of the unknown placeholder.
+    pub(crate) fn resolve(&self, value: &mut Value, path: &mut PointerBuf) -> Result<(), ()> {
+        match value {
+            Value::Object(obj) if obj.len() == 1 => {
+                if let Some(Value::String(name)) = obj.get(Self::MEMBER) {
+                    let resolver = self.resolvers.get(name).ok_or(())?;
+                    *value = resolver();
+                    return Ok(());
+                }
+                self.resolve_members(obj.iter_mut(), path)
+            }
+            Value::Object(obj) => self.resolve_members(obj.iter_mut(), path),
+            Value::Array(arr) => self.resolve_members(
+                arr.iter_mut()
+                    .enumerate()
+                    .map(|(idx, value)| (idx.to_string(), value)),
+                path,
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    fn resolve_members<'a, K: AsRef<str>>(
+        &self,
+        members: impl Iterator<Item = (K, &'a mut Value)>,
+        path: &mut PointerBuf,
+    ) -> Result<(), ()> {
+        for (key, value) in members {
+            path.push_back(key.as_ref());
+            self.resolve(value, path)?;
+            path.pop_back();
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Placeholders {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Placeholders")
+            .field("names", &self.resolvers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Placeholders;
+    use crate::{patch_with, Patch, PatchErrorKind, PatchOptions};
+    use serde_json::{from_value, json};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn resolve() {
+        let next_id = AtomicU64::new(1);
+        let options = PatchOptions {
+            placeholders: Some(
+                Placeholders::new()
+                    .resolver("id", move || json!(next_id.fetch_add(1, Ordering::Relaxed))),
+            ),
+            ..PatchOptions::default()
+        };
+
+        let mut doc = json!({ "items": [] });
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "/items/-", "value": { "id": { "$placeholder": "id" } } },
+            { "op": "add", "path": "/items/-", "value": [{ "$placeholder": "id" }] },
+            { "op": "test", "path": "/items/0/id", "value": 1 },
+            { "op": "add", "path": "/literal", "value": { "$placeholder": "id", "x": 1 } },
+            { "op": "replace", "path": "/items/1", "value": { "$placeholder": "id" } },
+        ]))
+        .unwrap();
+        patch_with(&mut doc, &p, &options).unwrap();
+        assert_eq!(
+            doc,
+            json!({ "items": [{ "id": 1 }, 3], "literal": { "$placeholder": "id", "x": 1 } })
+        );
+
+        // Without placeholders configured, values are left as is
+        let mut doc = json!({});
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "/a", "value": { "$placeholder": "id" } },
+        ]))
+        .unwrap();
+        patch_with(&mut doc, &p, &PatchOptions::default()).unwrap();
+        assert_eq!(doc, json!({ "a": { "$placeholder": "id" } }));
+    }
+
+    #[test]
+    fn unknown_placeholder() {
+        let options = PatchOptions {
+            placeholders: Some(Placeholders::new().value("user", json!("andrew"))),
+            ..PatchOptions::default()
+        };
+
+        let mut doc = json!({ "a": 1 });
+        let p: Patch = from_value(json!([
+            { "op": "replace", "path": "/a", "value": { "$placeholder": "user" } },
+            { "op": "add", "path": "/b", "value": { "list": [0, { "$placeholder": "now" }] } },
+        ]))
+        .unwrap();
+        let err = patch_with(&mut doc, &p, &options).unwrap_err();
+        assert_eq!(err.operation, 1);
+        assert!(matches!(err.kind, PatchErrorKind::UnknownPlaceholder));
+        assert_eq!(err.path, "/b/list/1");
+        assert_eq!(doc, json!({ "a": 1 }));
+    }
+}
@@ -0,0 +1,216 @@
+//! JSON Pointer ([RFC 6901](https://tools.ietf.org/html/rfc6901)) utilities: a stable facade
+//! over the pointer type used by the patches, and helpers for working with pointer strings.
+//!
+//! Pointers in patch operations are the types of the [`jsonptr`] crate, which had
+//! semver-incompatible releases in the past. Crates which only need to build and inspect pointers
+//! can use [`PointerBuf`] instead, which is kept stable across such upgrades, and convert it to
+//! and from the underlying type where needed. Operation constructors, like
+//! [`PatchOperation::add`](crate::PatchOperation::add), accept it directly.
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::pointer::PointerBuf;
+//! use json_patch::PatchOperation;
+//!
+//! let mut path: PointerBuf = "/users".parse().unwrap();
+//! path.push_back("0");
+//! path.push_back("a/b");
+//! assert_eq!(path.as_str(), "/users/0/a~1b");
+//!
+//! let op = PatchOperation::remove(path.clone());
+//! assert_eq!(PointerBuf::from(op.path()), path);
+//! ```
+//!
+//! Pointer strings are sequences of tokens, each prefixed by `/`. Within a token, `~` is escaped
+//! as `~0` and `/` as `~1`; unescaping replaces `~1` before `~0`, so `~01` stands for `~1`.
+//!
+//! ```rust
+//! use json_patch::pointer::{escape, parse_index, split_last, unescape};
+//!
+//! assert_eq!(escape("a/b~c"), "a~1b~0c");
+//! assert_eq!(unescape("~01"), "~1");
+//! assert_eq!(split_last("/users/0/a~1b"), Some(("/users/0", "a~1b")));
+//! assert_eq!(parse_index("2", 3), Some(2));
+//! assert_eq!(parse_index("02", 3), None);
+//! ```
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Owned JSON Pointer ([RFC 6901](https://tools.ietf.org/html/rfc6901)).
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PointerBuf(jsonptr::PointerBuf);
+
+/// Error returned when a string is not a valid JSON Pointer.
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct ParsePointerError(String);
+
+impl PointerBuf {
+    /// Creates a pointer to the root of the document.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the pointer from its string representation, like `/users/0/name`.
+    pub fn parse(s: &str) -> Result<Self, ParsePointerError> {
+        jsonptr::PointerBuf::parse(s)
+            .map(PointerBuf)
+            .map_err(|err| ParsePointerError(err.to_string()))
+    }
+
+    /// Creates a pointer from the unescaped tokens.
+    pub fn from_tokens<'a>(tokens: impl IntoIterator<Item = &'a str>) -> Self {
+        PointerBuf(jsonptr::PointerBuf::from_tokens(tokens))
+    }
+
+    /// Returns the string representation of the pointer.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    /// Returns `true` if the pointer refers to the root of the document.
+    pub fn is_root(&self) -> bool {
+        self.0.is_root()
+    }
+
+    /// Returns an iterator over the unescaped tokens of the pointer.
+    pub fn tokens(&self) -> impl Iterator<Item = String> + '_ {
+        self.0.tokens().map(|token| token.decoded().into_owned())
+    }
+
+    /// Appends the unescaped token to the end of the pointer.
+    pub fn push_back(&mut self, token: &str) {
+        self.0.push_back(token);
+    }
+
+    /// Removes the last token of the pointer and returns it unescaped.
+    pub fn pop_back(&mut self) -> Option<String> {
+        self.0.pop_back().map(|token| token.decoded().into_owned())
+    }
+}
+
+impl FromStr for PointerBuf {
+    type Err = ParsePointerError;
+
+    fn from_str(s: &str) -> Result<Self, ParsePointerError> {
+        PointerBuf::parse(s)
+    }
+}
+
+impl Display for PointerBuf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<jsonptr::PointerBuf> for PointerBuf {
+    fn from(pointer: jsonptr::PointerBuf) -> Self {
+        PointerBuf(pointer)
+    }
+}
+
+impl From<&jsonptr::Pointer> for PointerBuf {
+    fn from(pointer: &jsonptr::Pointer) -> Self {
+        PointerBuf(pointer.to_buf())
+    }
+}
+
+impl From<PointerBuf> for jsonptr::PointerBuf {
+    fn from(pointer: PointerBuf) -> Self {
+        pointer.0
+    }
+}
+
+impl AsRef<jsonptr::Pointer> for PointerBuf {
+    fn as_ref(&self) -> &jsonptr::Pointer {
+        &self.0
+    }
+}
+
+/// Escapes a token for use in a pointer string: `~` as `~0` and `/` as `~1`.
+pub fn escape(token: &str) -> Cow<'_, str> {
+    if token.contains(['~', '/']) {
+        Cow::Owned(token.replace('~', "~0").replace('/', "~1"))
+    } else {
+        Cow::Borrowed(token)
+    }
+}
+
+/// Unescapes a token of a pointer string: `~1` as `/` and `~0` as `~`, in that order.
+pub fn unescape(token: &str) -> Cow<'_, str> {
+    if token.contains('~') {
+        Cow::Owned(token.replace("~1", "/").replace("~0", "~"))
+    } else {
+        Cow::Borrowed(token)
+    }
+}
+
+/// Splits the pointer string into the pointer to the parent and the last token, still escaped.
+/// Returns `None` for the root pointer (empty string) and for strings not starting with `/`.
+pub fn split_last(pointer: &str) -> Option<(&str, &str)> {
+    if !pointer.starts_with('/') {
+        return None;
+    }
+    let idx = pointer.rfind('/')?;
+    Some((&pointer[..idx], &pointer[idx + 1..]))
+}
+
+/// Parses the token as an index of an array with `len` elements. Indices are decimal numbers
+/// without leading zeroes (other than `0` itself) or a sign, and must be less than `len`. The `-`
+/// token, referring to the element past the end, is not an index.
+pub fn parse_index(token: &str, len: usize) -> Option<usize> {
+    // RFC 6901 prohibits leading zeroes in index
+    if (token.starts_with('0') && token.len() != 1) || token.starts_with('+') {
+        return None;
+    }
+    token.parse::<usize>().ok().filter(|index| *index < len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape, parse_index, split_last, unescape, PointerBuf};
+    use serde_json::{from_value, json, to_value};
+
+    #[test]
+    fn tokens() {
+        let mut pointer = PointerBuf::from_tokens(["a", "b/c", "d~e"]);
+        assert_eq!(pointer.to_string(), "/a/b~1c/d~0e");
+        assert_eq!(pointer.tokens().collect::<Vec<_>>(), ["a", "b/c", "d~e"]);
+        assert_eq!(pointer.pop_back().as_deref(), Some("d~e"));
+        assert!(!pointer.is_root());
+        assert!(PointerBuf::new().is_root());
+    }
+
+    #[test]
+    fn conversions() {
+        let pointer: PointerBuf = "/a/0".parse().unwrap();
+        let inner = jsonptr::PointerBuf::from(pointer.clone());
+        assert_eq!(PointerBuf::from(inner.as_ptr()), pointer);
+        assert_eq!(to_value(&pointer).unwrap(), json!("/a/0"));
+        assert_eq!(from_value::<PointerBuf>(json!("/a/0")).unwrap(), pointer);
+
+        let err = "a".parse::<PointerBuf>().unwrap_err();
+        assert!(err.to_string().contains("does not start with"));
+        assert!(from_value::<PointerBuf>(json!("a")).is_err());
+    }
+
+    #[test]
+    fn helpers() {
+        for token in ["", "a", "~", "/", "~1", "~0/", "a/b~c"] {
+            assert_eq!(unescape(&escape(token)), token);
+        }
+        assert_eq!(split_last(""), None);
+        assert_eq!(split_last("a/b"), None);
+        assert_eq!(split_last("/"), Some(("", "")));
+        assert_eq!(split_last("/a/"), Some(("/a", "")));
+        assert_eq!(parse_index("0", 1), Some(0));
+        assert_eq!(parse_index("1", 1), None);
+        assert_eq!(parse_index("+1", 2), None);
+        assert_eq!(parse_index("-", 2), None);
+    }
+}
@@ -0,0 +1,67 @@
+//! Python bindings, exposing [`patch`](crate::patch), [`diff`](crate::diff) and
+//! [`merge`](crate::merge) to Python via `pyo3`.
+//!
+//! Documents and patches are passed as plain Python values (dicts, lists and primitives) and the
+//! results are returned the same way. Values are converted through the standard `json` module, so
+//! they are subject to the same rules as `json.dumps` and `json.loads`. The module is meant to be
+//! built as an extension module, for example, with `maturin`:
+//!
+//! ```python
+//! import json_patch
+//!
+//! doc = json_patch.apply({"name": "Andrew"}, [{"op": "add", "path": "/happy", "value": True}])
+//! ops = json_patch.diff({"name": "Andrew"}, {"name": "Maxim"})
+//! merged = json_patch.merge({"name": "Andrew", "age": 42}, {"age": None})
+//! ```
+use pyo3::{exceptions::PyValueError, prelude::*};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+fn from_py<T: DeserializeOwned>(value: &Bound<'_, PyAny>) -> PyResult<T> {
+    let json = value.py().import("json")?;
+    let text: String = json.call_method1("dumps", (value,))?.extract()?;
+    serde_json::from_str(&text).map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+fn to_py<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let text =
+        serde_json::to_string(value).map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let json = py.import("json")?;
+    Ok(json.call_method1("loads", (text,))?.unbind())
+}
+
+/// Apply JSON Patch (RFC 6902) to the document, returning the patched document. Raises
+/// `ValueError` if the patch cannot be parsed or applied; the original document is never
+/// modified.
+#[pyfunction]
+fn apply(doc: &Bound<'_, PyAny>, patch: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+    let mut value: Value = from_py(doc)?;
+    let patch: crate::Patch = from_py(patch)?;
+    crate::patch(&mut value, &patch).map_err(|err| PyValueError::new_err(err.to_string()))?;
+    to_py(doc.py(), &value)
+}
+
+/// Diff two documents, returning JSON Patch (RFC 6902) as a list of operations.
+#[cfg(feature = "diff")]
+#[pyfunction]
+fn diff(left: &Bound<'_, PyAny>, right: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+    let patch = crate::diff(&from_py(left)?, &from_py(right)?);
+    to_py(left.py(), &patch)
+}
+
+/// Apply JSON Merge Patch (RFC 7396) to the document, returning the merged document.
+#[pyfunction]
+fn merge(doc: &Bound<'_, PyAny>, patch: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+    let mut value: Value = from_py(doc)?;
+    crate::merge(&mut value, &from_py(patch)?);
+    to_py(doc.py(), &value)
+}
+
+#[pymodule]
+fn json_patch(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(apply, m)?)?;
+    #[cfg(feature = "diff")]
+    m.add_function(wrap_pyfunction!(diff, m)?)?;
+    m.add_function(wrap_pyfunction!(merge, m)?)?;
+    Ok(())
+}
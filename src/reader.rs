@@ -0,0 +1,251 @@
+use crate::de::{parse_operation, OperationError};
+use crate::{apply_patches, undo_patches, PatchError, PatchOperation, PatchOptions, UndoStack};
+use serde::de::Error as _;
+use serde::Deserialize;
+use serde_json::Value;
+use std::io::{self, Read};
+use thiserror::Error;
+
+/// Error that can occur while applying a patch read by [`PatchReader`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ReadError {
+    /// Patch could not be read: the input is not a JSON array or one of its elements is not a
+    /// valid operation.
+    #[error(transparent)]
+    Parse(#[from] serde_json::Error),
+    /// Operation could not be applied.
+    #[error(transparent)]
+    Patch(#[from] PatchError),
+}
+
+/// Reader deserializing the operations of a JSON Patch from an [`io::Read`] one at a time, so
+/// patches can be applied without keeping all of their operations in memory.
+///
+/// The reader is an iterator over the operations, which stops after the first error. Reading is
+/// done one byte at a time, so the underlying reader should be buffered.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::PatchReader;
+/// use serde_json::json;
+///
+/// let input = r#"[
+///   { "op": "add", "path": "/a", "value": 1 },
+///   { "op": "remove", "path": "/b" }
+/// ]"#;
+///
+/// let mut doc = json!({ "b": 2 });
+/// let applied = PatchReader::new(input.as_bytes()).apply(&mut doc).unwrap();
+/// assert_eq!(applied, 2);
+/// assert_eq!(doc, json!({ "a": 1 }));
+/// ```
+#[derive(Debug)]
+pub struct PatchReader<R> {
+    reader: R,
+    transactional: bool,
+    index: usize,
+    state: State,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    Start,
+    Elements,
+    Done,
+}
+
+impl<R: Read> PatchReader<R> {
+    /// Creates a reader reading a patch from the given reader.
+    pub fn new(reader: R) -> Self {
+        PatchReader {
+            reader,
+            transactional: true,
+            index: 0,
+            state: State::Start,
+        }
+    }
+
+    /// Sets whether [`PatchReader::apply`] reverts all the applied operations on failure, which
+    /// is the default. Reverting requires keeping the values replaced or removed by the
+    /// operations; without it, the document is left with the operations before the failed one
+    /// applied.
+    pub fn transactional(mut self, transactional: bool) -> Self {
+        self.transactional = transactional;
+        self
+    }
+
+    /// Reads the operations and applies each one to the document as it is read, returning the
+    /// number of applied operations. Fails if the input is not a valid patch or an operation
+    /// cannot be applied.
+    pub fn apply(mut self, doc: &mut Value) -> Result<usize, ReadError> {
+        let mut undo_stack = UndoStack::new();
+        let result = self.apply_all(doc, &mut undo_stack);
+        if result.is_err() && self.transactional {
+            if let Err(e) = undo_patches(doc, &undo_stack) {
+                unreachable!("unable to undo applied patches: {e}")
+            }
+        }
+        result
+    }
+
+    fn apply_all(
+        &mut self,
+        doc: &mut Value,
+        undo_stack: &mut UndoStack,
+    ) -> Result<usize, ReadError> {
+        let transactional = self.transactional;
+        let mut applied = 0;
+        for op in self.by_ref() {
+            let op = op?;
+            let undo_stack = if transactional {
+                Some(&mut *undo_stack)
+            } else {
+                None
+            };
+            apply_patches(
+                doc,
+                std::slice::from_ref(&op),
+                undo_stack,
+                &PatchOptions::default(),
+            )
+            .map_err(|err| PatchError {
+                operation: applied,
+                ..err
+            })?;
+            applied += 1;
+        }
+        Ok(applied)
+    }
+
+    fn read_operation(&mut self) -> Result<Option<PatchOperation>, serde_json::Error> {
+        if self.state == State::Start {
+            match self.next_byte()? {
+                Some(b'[') => self.state = State::Elements,
+                _ => return Err(serde_json::Error::custom("expected a JSON array")),
+            }
+        }
+
+        let unexpected_end = || serde_json::Error::custom("unexpected end of input");
+        let byte = match self.next_byte()? {
+            None => return Err(unexpected_end()),
+            Some(b']') => return self.end(),
+            Some(b',') if self.index > 0 => self.next_byte()?.ok_or_else(unexpected_end)?,
+            Some(byte) if self.index == 0 => byte,
+            Some(_) => return Err(serde_json::Error::custom("expected `,` or `]`")),
+        };
+
+        // Operations are objects, so the deserializer stops at the closing brace without
+        // consuming anything after it
+        let first = [byte];
+        let mut deserializer = serde_json::Deserializer::from_reader(first.chain(&mut self.reader));
+        let value = Value::deserialize(&mut deserializer)?;
+        let op = parse_operation(value).map_err(|error| {
+            serde_json::Error::custom(OperationError {
+                operation: self.index,
+                error,
+            })
+        })?;
+        self.index += 1;
+        Ok(Some(op))
+    }
+
+    fn end(&mut self) -> Result<Option<PatchOperation>, serde_json::Error> {
+        match self.next_byte()? {
+            None => {
+                self.state = State::Done;
+                Ok(None)
+            }
+            Some(_) => Err(serde_json::Error::custom("trailing characters")),
+        }
+    }
+
+    /// Reads the next byte which is not a whitespace.
+    fn next_byte(&mut self) -> Result<Option<u8>, serde_json::Error> {
+        let mut byte = 0;
+        loop {
+            match self.reader.read(std::slice::from_mut(&mut byte)) {
+                Ok(0) => return Ok(None),
+                Ok(_) if byte.is_ascii_whitespace() => {}
+                Ok(_) => return Ok(Some(byte)),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(serde_json::Error::io(err)),
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for PatchReader<R> {
+    type Item = Result<PatchOperation, serde_json::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.state == State::Done {
+            return None;
+        }
+        let result = self.read_operation().transpose();
+        if let Some(Err(_)) = result {
+            self.state = State::Done;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PatchReader, ReadError};
+    use serde_json::json;
+
+    #[test]
+    fn read_operations() {
+        let ops = PatchReader::new(&b" [ ] "[..]).collect::<Result<Vec<_>, _>>();
+        assert!(ops.unwrap().is_empty());
+
+        let input = br#"[{"op":"remove","path":"/a"} ,{"op":"copy","from":"/a","path":""}]"#;
+        let ops = PatchReader::new(&input[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(ops.len(), 2);
+
+        let errors = [
+            (&br#"{}"#[..], "expected a JSON array"),
+            (
+                br#"[{"op":"remove","path":"/a"}"#,
+                "unexpected end of input",
+            ),
+            (br#"[{"op":"remove","path":"/a"}{}]"#, "expected `,` or `]`"),
+            (br#"[] []"#, "trailing characters"),
+            (
+                br#"[{"op":"remove"}]"#,
+                "operation '/0' is missing field 'path'",
+            ),
+        ];
+        for (input, expected) in errors {
+            let mut reader = PatchReader::new(input);
+            let err = reader.find_map(Result::err).unwrap();
+            assert_eq!(err.to_string(), expected);
+            assert!(reader.next().is_none());
+        }
+    }
+
+    #[test]
+    fn apply() {
+        let input = br#"[
+            { "op": "add", "path": "/a", "value": 1 },
+            { "op": "remove", "path": "/b" },
+            { "op": "remove", "path": "/b" }
+        ]"#;
+
+        let mut doc = json!({ "b": 2 });
+        let err = PatchReader::new(&input[..]).apply(&mut doc).unwrap_err();
+        assert!(matches!(err, ReadError::Patch(ref err) if err.operation == 2));
+        assert_eq!(doc, json!({ "b": 2 }));
+
+        let err = PatchReader::new(&input[..])
+            .transactional(false)
+            .apply(&mut doc)
+            .unwrap_err();
+        assert!(matches!(err, ReadError::Patch(_)));
+        assert_eq!(doc, json!({ "a": 1 }));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{strip_pointer_prefix, Patch, PatchOperation};
+use jsonptr::Pointer;
+use serde_json::Value;
+use std::fmt::{self, Display, Formatter};
+
+impl Patch {
+    /// Placeholder replacing redacted values.
+    pub const REDACTED: &'static str = "[REDACTED]";
+
+    /// Returns a copy of this patch with values at or under any of the `secrets` pointers
+    /// replaced with [`Patch::REDACTED`], so the patch can be logged safely. The structure of the
+    /// patch is kept: operations are neither removed nor reordered, and only the secret parts of
+    /// values are replaced.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::Patch;
+    /// use jsonptr::Pointer;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "replace", "path": "/credentials/password", "value": "hunter2" },
+    ///   { "op": "add", "path": "/credentials", "value": { "password": "x" } },
+    /// ])).unwrap();
+    ///
+    /// let redacted = p.redact(&[Pointer::from_static("/credentials/password")]);
+    /// assert_eq!(redacted, from_value(json!([
+    ///   { "op": "replace", "path": "/credentials/password", "value": "[REDACTED]" },
+    ///   { "op": "add", "path": "/credentials", "value": { "password": "[REDACTED]" } },
+    /// ])).unwrap());
+    /// ```
+    pub fn redact(&self, secrets: &[&Pointer]) -> Patch {
+        self.iter()
+            .map(|op| redact_operation(op, secrets))
+            .collect()
+    }
+
+    /// Returns a wrapper displaying this patch with the secret values redacted, same as
+    /// [`Patch::redact`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::Patch;
+    /// use jsonptr::Pointer;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "add", "path": "/token", "value": "secret" },
+    /// ])).unwrap();
+    ///
+    /// let secrets = [Pointer::from_static("/token")];
+    /// assert_eq!(
+    ///   p.redacted(&secrets).to_string(),
+    ///   r#"[{"op":"add","path":"/token","value":"[REDACTED]"}]"#
+    /// );
+    /// ```
+    pub fn redacted<'a>(&'a self, secrets: &'a [&'a Pointer]) -> Redacted<'a> {
+        Redacted {
+            patch: self,
+            secrets,
+        }
+    }
+}
+
+/// Displays a patch with the secret values redacted. Created by [`Patch::redacted`].
+#[derive(Clone, Copy, Debug)]
+pub struct Redacted<'a> {
+    patch: &'a Patch,
+    secrets: &'a [&'a Pointer],
+}
+
+impl Display for Redacted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let redacted = self.patch.redact(self.secrets);
+        Display::fmt(&redacted, f)
+    }
+}
+
+fn redact_operation(op: &PatchOperation, secrets: &[&Pointer]) -> PatchOperation {
+    let mut op = op.clone();
+    let (path, value) = match &mut op {
+        PatchOperation::Add(op) => (&op.path, &mut op.value),
+        PatchOperation::Replace(op) => (&op.path, &mut op.value),
+        PatchOperation::Test(op) => (&op.path, &mut op.value),
+        PatchOperation::Remove(_) | PatchOperation::Move(_) | PatchOperation::Copy(_) => {
+            return op;
+        }
+    };
+    for secret in secrets {
+        if strip_pointer_prefix(path, secret).is_some() {
+            *value = Value::from(Patch::REDACTED);
+            break;
+        }
+        if let Some(relative) = strip_pointer_prefix(secret, path) {
+            if let Some(nested) = value.pointer_mut(relative.as_str()) {
+                *nested = Value::from(Patch::REDACTED);
+            }
+        }
+    }
+    op
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Patch;
+    use jsonptr::Pointer;
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn redact() {
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "", "value": {
+                "users": [{ "password": "a" }, { "password": "b" }],
+            } },
+            { "op": "test", "path": "/users/1/password", "value": "b" },
+            { "op": "copy", "from": "/users/1/password", "path": "/users/0/password" },
+            { "op": "replace", "path": "/users/1", "value": { "name": "andrew" } },
+        ]))
+        .unwrap();
+        let secrets = [
+            Pointer::from_static("/users/1/password"),
+            Pointer::from_static("/users/0/password"),
+        ];
+        let expected: Patch = from_value(json!([
+            { "op": "add", "path": "", "value": {
+                "users": [{ "password": "[REDACTED]" }, { "password": "[REDACTED]" }],
+            } },
+            { "op": "test", "path": "/users/1/password", "value": "[REDACTED]" },
+            { "op": "copy", "from": "/users/1/password", "path": "/users/0/password" },
+            { "op": "replace", "path": "/users/1", "value": { "name": "andrew" } },
+        ]))
+        .unwrap();
+        assert_eq!(p.redact(&secrets), expected);
+        assert_eq!(p.redacted(&secrets).to_string(), expected.to_string());
+    }
+}
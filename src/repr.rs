@@ -0,0 +1,164 @@
+use crate::{
+    AddOperation, CopyOperation, MoveOperation, PatchOperation, RemoveOperation, ReplaceOperation,
+    TestOperation,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// Defines a wrapper of `PatchOperation` serialized with the given serde enum representation
+macro_rules! tagged_operation {
+    ($(#[$attr:meta])* $name:ident, $($repr:tt)*) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct $name(pub PatchOperation);
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                #[derive(Serialize)]
+                #[serde($($repr)*)]
+                enum Repr<'a> {
+                    Add(&'a AddOperation),
+                    Remove(&'a RemoveOperation),
+                    Replace(&'a ReplaceOperation),
+                    Move(&'a MoveOperation),
+                    Copy(&'a CopyOperation),
+                    Test(&'a TestOperation),
+                }
+
+                match &self.0 {
+                    PatchOperation::Add(op) => Repr::Add(op),
+                    PatchOperation::Remove(op) => Repr::Remove(op),
+                    PatchOperation::Replace(op) => Repr::Replace(op),
+                    PatchOperation::Move(op) => Repr::Move(op),
+                    PatchOperation::Copy(op) => Repr::Copy(op),
+                    PatchOperation::Test(op) => Repr::Test(op),
+                }
+                .serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                #[derive(Deserialize)]
+                #[serde($($repr)*)]
+                enum Repr {
+                    Add(AddOperation),
+                    Remove(RemoveOperation),
+                    Replace(ReplaceOperation),
+                    Move(MoveOperation),
+                    Copy(CopyOperation),
+                    Test(TestOperation),
+                }
+
+                Ok($name(match Repr::deserialize(deserializer)? {
+                    Repr::Add(op) => PatchOperation::Add(op),
+                    Repr::Remove(op) => PatchOperation::Remove(op),
+                    Repr::Replace(op) => PatchOperation::Replace(op),
+                    Repr::Move(op) => PatchOperation::Move(op),
+                    Repr::Copy(op) => PatchOperation::Copy(op),
+                    Repr::Test(op) => PatchOperation::Test(op),
+                }))
+            }
+        }
+
+        impl From<PatchOperation> for $name {
+            fn from(op: PatchOperation) -> Self {
+                $name(op)
+            }
+        }
+
+        impl From<$name> for PatchOperation {
+            fn from(op: $name) -> Self {
+                op.0
+            }
+        }
+    };
+}
+
+tagged_operation!(
+    /// Patch operation serialized as adjacently tagged enum: the operation name in `op` and the
+    /// rest of the members nested in `args`, like `{ "op": "add", "args": { "path": "/a",
+    /// "value": 1 } }`.
+    ///
+    /// For schemas which cannot express internally tagged enums, where the members of the
+    /// operation depend on the value of `op`. [`CompatPatch`](crate::CompatPatch) accepts this
+    /// representation, too.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::{AdjacentlyTagged, Patch};
+    /// use serde_json::{from_value, json, to_value};
+    ///
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "add", "path": "/a", "value": 1 },
+    /// ])).unwrap();
+    ///
+    /// let ops: Vec<AdjacentlyTagged> = p.iter().cloned().map(AdjacentlyTagged).collect();
+    /// let value = to_value(&ops).unwrap();
+    /// assert_eq!(value, json!([
+    ///   { "op": "add", "args": { "path": "/a", "value": 1 } },
+    /// ]));
+    ///
+    /// let ops: Vec<AdjacentlyTagged> = from_value(value).unwrap();
+    /// assert_eq!(ops.into_iter().map(|op| op.0).collect::<Patch>(), p);
+    /// ```
+    AdjacentlyTagged,
+    tag = "op", content = "args", rename_all = "lowercase"
+);
+
+tagged_operation!(
+    /// Patch operation serialized as externally tagged enum: an object with the operation name as
+    /// its only member, like `{ "add": { "path": "/a", "value": 1 } }`.
+    ///
+    /// For schemas representing the operation as a union of the operation types, like Avro or
+    /// JSON mappings of Protocol Buffers `oneof` fields.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::pointer::PointerBuf;
+    /// use json_patch::{ExternallyTagged, PatchOperation};
+    /// use serde_json::{from_value, json, to_value};
+    ///
+    /// let op = ExternallyTagged(PatchOperation::remove(PointerBuf::parse("/a").unwrap()));
+    /// assert_eq!(to_value(&op).unwrap(), json!({ "remove": { "path": "/a" } }));
+    /// assert_eq!(from_value::<ExternallyTagged>(json!({ "remove": { "path": "/a" } })).unwrap(), op);
+    /// ```
+    ExternallyTagged,
+    rename_all = "lowercase"
+);
+
+#[cfg(test)]
+mod tests {
+    use super::{AdjacentlyTagged, ExternallyTagged};
+    use crate::Patch;
+    use serde_json::{from_value, json, to_value};
+
+    #[test]
+    fn roundtrip() {
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "/a", "value": [1] },
+            { "op": "remove", "path": "/b" },
+            { "op": "replace", "path": "/c", "value": null },
+            { "op": "move", "from": "/d", "path": "/e" },
+            { "op": "copy", "from": "/e", "path": "/f" },
+            { "op": "test", "path": "/g", "value": "x" },
+        ]))
+        .unwrap();
+
+        for op in p.iter() {
+            let adjacent = AdjacentlyTagged(op.clone());
+            let value = to_value(&adjacent).unwrap();
+            assert_eq!(value["args"].get("op"), None);
+            assert_eq!(from_value::<AdjacentlyTagged>(value).unwrap(), adjacent);
+
+            let external = ExternallyTagged(op.clone());
+            let value = to_value(&external).unwrap();
+            assert_eq!(value.as_object().unwrap().len(), 1);
+            assert_eq!(from_value::<ExternallyTagged>(value).unwrap(), external);
+        }
+
+        let err = from_value::<ExternallyTagged>(json!({ "op": "remove", "path": "/a" }));
+        assert!(err.is_err());
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{
+    apply_patches, patch, AddOperation, CopyOperation, MoveOperation, Patch, PatchError,
+    PatchOperation, PatchOptions, RemoveOperation, ReplaceOperation, UndoStack,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+/// Patch which embeds the values it displaces, so it can be reverted without access to the
+/// original document. Created by [`Patch::to_reversible`].
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ReversiblePatch(pub Vec<ReversibleOperation>);
+
+/// Operation of a [`ReversiblePatch`].
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ReversibleOperation {
+    /// Operation, with `-` array indices resolved to the actual ones.
+    #[serde(flatten)]
+    pub operation: PatchOperation,
+    /// Value removed or overwritten by the operation, if any.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub previous: Option<Value>,
+}
+
+// Distinguishes `"previous": null` (displaced `null`) from the missing member.
+fn deserialize_some<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+impl Patch {
+    /// Creates a reversible version of this patch by recording the values it displaces in the
+    /// given document. The document itself is not modified.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::{patch, Patch};
+    /// use serde_json::{from_value, json};
+    ///
+    /// let doc = json!({ "title": "Goodbye!", "tags": ["a"] });
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "replace", "path": "/title", "value": "Hello!" },
+    ///   { "op": "add", "path": "/tags/-", "value": "b" },
+    /// ])).unwrap();
+    ///
+    /// let reversible = p.to_reversible(&doc).unwrap();
+    /// assert_eq!(serde_json::to_value(&reversible).unwrap(), json!([
+    ///   { "op": "replace", "path": "/title", "value": "Hello!", "previous": "Goodbye!" },
+    ///   { "op": "add", "path": "/tags/1", "value": "b" },
+    /// ]));
+    ///
+    /// let mut patched = doc.clone();
+    /// patch(&mut patched, &p).unwrap();
+    /// reversible.revert(&mut patched).unwrap();
+    /// assert_eq!(patched, doc);
+    /// ```
+    pub fn to_reversible(&self, doc: &Value) -> Result<ReversiblePatch, PatchError> {
+        let mut doc = doc.clone();
+        let options = PatchOptions::default();
+        let mut ops = Vec::with_capacity(self.len());
+        for (operation, op) in self.iter().enumerate() {
+            let mut undo_stack = UndoStack::new();
+            apply_patches(
+                &mut doc,
+                std::slice::from_ref(op),
+                Some(&mut undo_stack),
+                &options,
+            )
+            .map_err(|e| PatchError { operation, ..e })?;
+            ops.push(reversible(op, &undo_stack));
+        }
+        Ok(ReversiblePatch(ops))
+    }
+}
+
+// Builds the reversible operation from the undo operations recorded while applying it.
+fn reversible(op: &PatchOperation, undo: &[PatchOperation]) -> ReversibleOperation {
+    let (operation, previous) = match (op, undo) {
+        (PatchOperation::Add(op), [PatchOperation::Remove(undo)]) => {
+            let add = AddOperation {
+                path: undo.path.clone(),
+                value: op.value.clone(),
+            };
+            (PatchOperation::Add(add), None)
+        }
+        (PatchOperation::Copy(op), [PatchOperation::Remove(undo)]) => {
+            let copy = CopyOperation {
+                from: op.from.clone(),
+                path: undo.path.clone(),
+            };
+            (PatchOperation::Copy(copy), None)
+        }
+        (PatchOperation::Add(_) | PatchOperation::Copy(_), [PatchOperation::Add(undo)]) => {
+            (op.clone(), Some(undo.value.clone()))
+        }
+        (PatchOperation::Remove(_), [PatchOperation::Add(undo)]) => {
+            let remove = RemoveOperation {
+                path: undo.path.clone(),
+            };
+            (PatchOperation::Remove(remove), Some(undo.value.clone()))
+        }
+        (PatchOperation::Replace(op), [PatchOperation::Replace(undo)]) => {
+            let replace = ReplaceOperation {
+                path: undo.path.clone(),
+                value: op.value.clone(),
+            };
+            (PatchOperation::Replace(replace), Some(undo.value.clone()))
+        }
+        (PatchOperation::Move(_), [overwritten @ .., PatchOperation::Move(undo)]) => {
+            let mov = MoveOperation {
+                from: undo.path.clone(),
+                path: undo.from.clone(),
+            };
+            let previous = match overwritten {
+                [PatchOperation::Add(add)] => Some(add.value.clone()),
+                _ => None,
+            };
+            (PatchOperation::Move(mov), previous)
+        }
+        _ => (op.clone(), None),
+    };
+    ReversibleOperation {
+        operation,
+        previous,
+    }
+}
+
+impl ReversiblePatch {
+    /// Returns the patch applying the changes.
+    pub fn forward(&self) -> Patch {
+        self.0.iter().map(|op| op.operation.clone()).collect()
+    }
+
+    /// Returns the patch reverting the changes, to be applied to the patched document.
+    pub fn inverse(&self) -> Patch {
+        let mut inverse = Vec::with_capacity(self.0.len());
+        for ReversibleOperation {
+            operation,
+            previous,
+        } in self.0.iter().rev()
+        {
+            let restore = |path: &jsonptr::PointerBuf| match previous {
+                Some(value) => PatchOperation::Add(AddOperation {
+                    path: path.clone(),
+                    value: value.clone(),
+                }),
+                None => PatchOperation::Remove(RemoveOperation { path: path.clone() }),
+            };
+            match operation {
+                PatchOperation::Add(AddOperation { path, .. })
+                | PatchOperation::Copy(CopyOperation { path, .. })
+                | PatchOperation::Remove(RemoveOperation { path }) => inverse.push(restore(path)),
+                PatchOperation::Replace(ReplaceOperation { path, .. }) => {
+                    inverse.push(PatchOperation::Replace(ReplaceOperation {
+                        path: path.clone(),
+                        value: previous.clone().unwrap_or_default(),
+                    }))
+                }
+                PatchOperation::Move(MoveOperation { from, path }) => {
+                    inverse.push(PatchOperation::Move(MoveOperation {
+                        from: path.clone(),
+                        path: from.clone(),
+                    }));
+                    if previous.is_some() {
+                        inverse.push(restore(path));
+                    }
+                }
+                PatchOperation::Test(_) => {}
+            }
+        }
+        Patch(inverse)
+    }
+
+    /// Reverts the changes in the patched document. Like [`patch`], the document is left unchanged
+    /// if reverting fails (for example, if the document was modified after patching).
+    pub fn revert(&self, doc: &mut Value) -> Result<(), PatchError> {
+        patch(doc, &self.inverse())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReversiblePatch;
+    use crate::{patch, Patch};
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn roundtrip_without_document() {
+        let doc = json!({ "a": { "b": null }, "c": [1, 2, 3], "d": "x" });
+        let p: Patch = from_value(json!([
+            { "op": "remove", "path": "/a/b" },
+            { "op": "move", "from": "/c/0", "path": "/d" },
+            { "op": "copy", "from": "/d", "path": "/c/-" },
+            { "op": "add", "path": "/a/e", "value": 1 },
+            { "op": "add", "path": "/a/e", "value": 2 },
+            { "op": "test", "path": "/d", "value": 1 },
+        ]))
+        .unwrap();
+        let reversible = p.to_reversible(&doc).unwrap();
+        assert_eq!(reversible.forward().len(), p.len());
+
+        // Stored and loaded without the original document
+        let json = serde_json::to_string(&reversible).unwrap();
+        let loaded: ReversiblePatch = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, reversible);
+        assert_eq!(loaded.0[0].previous, Some(json!(null)));
+        assert_eq!(loaded.0[2].operation.path().as_str(), "/c/2");
+
+        let mut patched = doc.clone();
+        patch(&mut patched, &p).unwrap();
+        assert_eq!(patched, json!({ "a": { "e": 2 }, "c": [2, 3, 1], "d": 1 }));
+        loaded.revert(&mut patched).unwrap();
+        assert_eq!(patched, doc);
+    }
+
+    #[test]
+    fn failed_operation() {
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "/a", "value": 1 },
+            { "op": "remove", "path": "/b" },
+        ]))
+        .unwrap();
+        let err = p.to_reversible(&json!({})).unwrap_err();
+        assert_eq!(err.operation, 1);
+    }
+}
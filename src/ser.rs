@@ -0,0 +1,541 @@
+use crate::{diff, Patch};
+use jsonptr::PointerBuf;
+use serde::ser::{self, Serialize};
+use serde_json::{Map, Value};
+use std::fmt::{self, Display, Formatter};
+use thiserror::Error;
+
+/// Error that can occur in [`diff_serialize`] when one of the values cannot be represented as
+/// JSON.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum DiffSerializeError {
+    /// The left value could not be serialized.
+    #[error("cannot serialize left value at '{path}': {message}")]
+    Left {
+        /// Location of the value which could not be serialized. For a map with a key which is
+        /// not a string or a number, this is the location of the map.
+        path: PointerBuf,
+        /// Error reported by the serializer.
+        message: String,
+    },
+    /// The right value could not be serialized.
+    #[error("cannot serialize right value at '{path}': {message}")]
+    Right {
+        /// Location of the value which could not be serialized. For a map with a key which is
+        /// not a string or a number, this is the location of the map.
+        path: PointerBuf,
+        /// Error reported by the serializer.
+        message: String,
+    },
+}
+
+/// Diff two values serialized as JSON documents, same as [`diff`]. Fails, reporting the location
+/// of the offending value, if a value cannot be represented as JSON, like a map with keys which
+/// are neither strings nor numbers.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{diff_serialize, DiffSerializeError, Patch};
+/// use serde_json::{from_value, json};
+/// use std::collections::BTreeMap;
+///
+/// #[derive(serde::Serialize)]
+/// struct User { name: String, tags: Vec<&'static str> }
+///
+/// let left = User { name: "Andrew".into(), tags: vec!["a"] };
+/// let right = User { name: "Maxim".into(), tags: vec!["a"] };
+/// assert_eq!(diff_serialize(&left, &right).unwrap(), from_value::<Patch>(json!([
+///   { "op": "replace", "path": "/name", "value": "Maxim" },
+/// ])).unwrap());
+///
+/// let positions = BTreeMap::from([((0, 0), "origin")]);
+/// let err = diff_serialize(&json!({}), &BTreeMap::from([("a", [positions])])).unwrap_err();
+/// assert!(matches!(err, DiffSerializeError::Right { ref path, .. } if path == "/a/0"));
+/// ```
+pub fn diff_serialize<L: Serialize, R: Serialize>(
+    left: &L,
+    right: &R,
+) -> Result<Patch, DiffSerializeError> {
+    let left = left
+        .serialize(ValueSerializer)
+        .map_err(|err| DiffSerializeError::Left {
+            path: err.path(),
+            message: err.message,
+        })?;
+    let right = right
+        .serialize(ValueSerializer)
+        .map_err(|err| DiffSerializeError::Right {
+            path: err.path(),
+            message: err.message,
+        })?;
+    Ok(diff(&left, &right))
+}
+
+/// Serialization error with the location of the failed value.
+#[derive(Debug)]
+struct Error {
+    /// Tokens of the location, innermost first.
+    tokens: Vec<String>,
+    message: String,
+}
+
+impl Error {
+    fn at(mut self, token: impl Into<String>) -> Self {
+        self.tokens.push(token.into());
+        self
+    }
+
+    fn path(&self) -> PointerBuf {
+        PointerBuf::from_tokens(self.tokens.iter().rev())
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error {
+            tokens: Vec::new(),
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// Serializes values into [`Value`], same as [`serde_json::to_value`], but keeps track of the
+/// location of the value which failed to serialize.
+struct ValueSerializer;
+
+fn variant(variant: &'static str, value: Value) -> Value {
+    let mut obj = Map::new();
+    obj.insert(variant.into(), value);
+    Value::Object(obj)
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeArray;
+    type SerializeMap = SerializeObject;
+    type SerializeStruct = SerializeObject;
+    type SerializeStructVariant = SerializeObject;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value, Error> {
+        if let Ok(v) = i64::try_from(v) {
+            Ok(Value::from(v))
+        } else if let Ok(v) = u64::try_from(v) {
+            Ok(Value::from(v))
+        } else {
+            Err(ser::Error::custom("number out of range"))
+        }
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value, Error> {
+        u64::try_from(v)
+            .map(Value::from)
+            .map_err(|_| ser::Error::custom("number out of range"))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Error> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Error> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+        Ok(Value::Array(
+            v.iter().map(|byte| Value::from(*byte)).collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        Ok(Value::String(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        let value = value.serialize(self).map_err(|err| err.at(name))?;
+        Ok(variant(name, value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArray, Error> {
+        Ok(SerializeArray {
+            variant: None,
+            elements: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray, Error> {
+        Ok(SerializeArray {
+            variant: Some(variant),
+            elements: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeObject, Error> {
+        Ok(SerializeObject {
+            variant: None,
+            members: Map::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeObject, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeObject, Error> {
+        Ok(SerializeObject {
+            variant: Some(variant),
+            members: Map::new(),
+            key: None,
+        })
+    }
+}
+
+struct SerializeArray {
+    variant: Option<&'static str>,
+    elements: Vec<Value>,
+}
+
+impl SerializeArray {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let value = value.serialize(ValueSerializer).map_err(|err| {
+            let err = err.at(self.elements.len().to_string());
+            match self.variant {
+                Some(variant) => err.at(variant),
+                None => err,
+            }
+        })?;
+        self.elements.push(value);
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        let array = Value::Array(self.elements);
+        match self.variant {
+            Some(name) => variant(name, array),
+            None => array,
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeArray {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+struct SerializeObject {
+    variant: Option<&'static str>,
+    members: Map<String, Value>,
+    key: Option<String>,
+}
+
+impl SerializeObject {
+    fn insert<T: ?Sized + Serialize>(&mut self, key: String, value: &T) -> Result<(), Error> {
+        let value = value.serialize(ValueSerializer).map_err(|err| {
+            let err = err.at(key.as_str());
+            match self.variant {
+                Some(variant) => err.at(variant),
+                None => err,
+            }
+        })?;
+        self.members.insert(key, value);
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        let obj = Value::Object(self.members);
+        match self.variant {
+            Some(name) => variant(name, obj),
+            None => obj,
+        }
+    }
+}
+
+impl ser::SerializeMap for SerializeObject {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        // Like `serde_json`, accept numbers and booleans as keys, converted to strings
+        self.key = Some(match key.serialize(ValueSerializer)? {
+            Value::String(key) => key,
+            key @ (Value::Number(_) | Value::Bool(_)) => key.to_string(),
+            _ => return Err(ser::Error::custom("key must be a string")),
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStruct for SerializeObject {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(key.to_owned(), value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeObject {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(key.to_owned(), value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_serialize, DiffSerializeError, ValueSerializer};
+    use serde::Serialize;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Line(i32, i32),
+        Rect { width: u128, height: i128 },
+    }
+
+    #[test]
+    fn same_as_serde_json() {
+        let value = (
+            [Shape::Point, Shape::Circle(1.5), Shape::Line(1, -2)],
+            Shape::Rect {
+                width: 1,
+                height: -1,
+            },
+            BTreeMap::from([(1, 'c'), (2, 'd')]),
+            BTreeMap::from([(true, None::<()>)]),
+            Some(()),
+        );
+        assert_eq!(
+            value.serialize(ValueSerializer).unwrap(),
+            serde_json::to_value(&value).unwrap()
+        );
+    }
+
+    #[test]
+    fn error_path() {
+        #[derive(Serialize)]
+        struct Doc {
+            shapes: Vec<Shape>,
+        }
+
+        let left = Doc {
+            shapes: vec![
+                Shape::Point,
+                Shape::Rect {
+                    width: u128::MAX,
+                    height: 0,
+                },
+            ],
+        };
+        let err = diff_serialize(&left, &json!(null)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot serialize left value at '/shapes/1/Rect/width': number out of range"
+        );
+
+        let right = BTreeMap::from([("a~b", [BTreeMap::from([((), 1)])])]);
+        let err = diff_serialize(&json!(null), &right).unwrap_err();
+        assert!(matches!(err, DiffSerializeError::Right { ref path, .. } if path == "/a~0b/0"));
+        assert_eq!(
+            err.to_string(),
+            "cannot serialize right value at '/a~0b/0': key must be a string"
+        );
+    }
+}
@@ -0,0 +1,270 @@
+//! JSON documents sharing unchanged subtrees between versions.
+//!
+//! [`SharedValue`] is a JSON value with reference-counted arrays, objects and strings, so cloning
+//! it is cheap. Patching a clone with [`patch`] copies only the arrays and objects on the paths
+//! modified by the patch, everything else stays shared with the previous version. This keeps
+//! memory usage proportional to the size of the changes when many versions of a large document
+//! are kept around.
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::shared::{self, SharedValue};
+//! use json_patch::Patch;
+//! use serde_json::{from_value, json};
+//!
+//! let v1 = SharedValue::from(json!({ "title": "Draft", "body": { "text": "..." } }));
+//! let p: Patch = from_value(json!([
+//!   { "op": "replace", "path": "/title", "value": "Hello!" },
+//! ])).unwrap();
+//!
+//! let mut v2 = v1.clone();
+//! shared::patch(&mut v2, &p).unwrap();
+//! assert_eq!(serde_json::Value::from(&v2)["title"], "Hello!");
+//! assert_eq!(serde_json::Value::from(&v1)["title"], "Draft");
+//!
+//! // Unchanged members are shared by both versions
+//! let body = |doc: &SharedValue| doc.pointer("/body").unwrap().clone();
+//! assert!(body(&v1).ptr_eq(&body(&v2)));
+//! ```
+use crate::{document::Document, parse_index, PatchError, PatchErrorKind, PatchOperation};
+use jsonptr::{Pointer, Token};
+use serde_json::{Map, Number, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// JSON value with reference-counted arrays, objects and strings.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SharedValue {
+    /// JSON `null`.
+    Null,
+    /// JSON boolean.
+    Bool(bool),
+    /// JSON number.
+    Number(Number),
+    /// JSON string.
+    String(Arc<str>),
+    /// JSON array.
+    Array(Arc<Vec<SharedValue>>),
+    /// JSON object.
+    Object(Arc<BTreeMap<String, SharedValue>>),
+}
+
+impl SharedValue {
+    /// Looks up a value by a JSON Pointer, same as [`Value::pointer`].
+    pub fn pointer(&self, pointer: &str) -> Option<&SharedValue> {
+        Pointer::parse(pointer)
+            .ok()?
+            .tokens()
+            .try_fold(self, |value, token| value.child(&token))
+    }
+
+    /// Checks if both values are the same shared array, object or string, rather than merely
+    /// equal ones.
+    pub fn ptr_eq(&self, other: &SharedValue) -> bool {
+        match (self, other) {
+            (SharedValue::String(left), SharedValue::String(right)) => Arc::ptr_eq(left, right),
+            (SharedValue::Array(left), SharedValue::Array(right)) => Arc::ptr_eq(left, right),
+            (SharedValue::Object(left), SharedValue::Object(right)) => Arc::ptr_eq(left, right),
+            _ => false,
+        }
+    }
+}
+
+impl From<&Value> for SharedValue {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Null => SharedValue::Null,
+            Value::Bool(b) => SharedValue::Bool(*b),
+            Value::Number(n) => SharedValue::Number(n.clone()),
+            Value::String(s) => SharedValue::String(s.as_str().into()),
+            Value::Array(arr) => SharedValue::Array(Arc::new(arr.iter().map(Into::into).collect())),
+            Value::Object(obj) => SharedValue::Object(Arc::new(
+                obj.iter()
+                    .map(|(key, value)| (key.clone(), value.into()))
+                    .collect(),
+            )),
+        }
+    }
+}
+
+impl From<Value> for SharedValue {
+    fn from(value: Value) -> Self {
+        SharedValue::from(&value)
+    }
+}
+
+impl From<&SharedValue> for Value {
+    fn from(value: &SharedValue) -> Self {
+        match value {
+            SharedValue::Null => Value::Null,
+            SharedValue::Bool(b) => Value::Bool(*b),
+            SharedValue::Number(n) => Value::Number(n.clone()),
+            SharedValue::String(s) => Value::String(s.to_string()),
+            SharedValue::Array(arr) => Value::Array(arr.iter().map(Into::into).collect()),
+            SharedValue::Object(obj) => Value::Object(
+                obj.iter()
+                    .map(|(key, value)| (key.clone(), value.into()))
+                    .collect::<Map<_, _>>(),
+            ),
+        }
+    }
+}
+
+/// Patch provided document in place, same as [`crate::patch`]. Only the arrays and objects
+/// modified by the patch are copied, so the document keeps sharing the rest with its clones. If
+/// any of the operations fails, the document is left unchanged.
+pub fn patch(doc: &mut SharedValue, patch: &[PatchOperation]) -> Result<(), PatchError> {
+    crate::document::patch(doc, patch)
+}
+
+/// Diff two documents and generate a JSON Patch (RFC 6902), same as [`crate::diff`].
+#[cfg(feature = "diff")]
+pub fn diff(left: &SharedValue, right: &SharedValue) -> crate::Patch {
+    match crate::document::diff(left, right) {
+        Ok(patch) => patch,
+        Err(never) => match never {},
+    }
+}
+
+impl Document for SharedValue {
+    fn from_json(value: &Value) -> Result<Self, PatchErrorKind> {
+        Ok(value.into())
+    }
+
+    fn child(&self, token: &Token) -> Option<&Self> {
+        match self {
+            SharedValue::Object(obj) => obj.get(token.decoded().as_ref()),
+            SharedValue::Array(arr) => arr.get(parse_index(token.encoded(), arr.len()).ok()?),
+            _ => None,
+        }
+    }
+
+    fn child_mut(&mut self, token: &Token) -> Option<&mut Self> {
+        match self {
+            SharedValue::Object(obj) => {
+                // Avoid copying the object if the member does not exist
+                if !obj.contains_key(token.decoded().as_ref()) {
+                    return None;
+                }
+                Arc::make_mut(obj).get_mut(token.decoded().as_ref())
+            }
+            SharedValue::Array(arr) => {
+                let idx = parse_index(token.encoded(), arr.len()).ok()?;
+                Arc::make_mut(arr).get_mut(idx)
+            }
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, token: &Token, value: Self) -> Result<(), PatchErrorKind> {
+        match self {
+            SharedValue::Object(obj) => {
+                Arc::make_mut(obj).insert(token.decoded().into_owned(), value);
+            }
+            SharedValue::Array(arr) if token.encoded() == "-" => Arc::make_mut(arr).push(value),
+            SharedValue::Array(arr) => {
+                let idx = parse_index(token.encoded(), arr.len() + 1)?;
+                Arc::make_mut(arr).insert(idx, value);
+            }
+            _ => return Err(PatchErrorKind::InvalidPointer),
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, token: &Token) -> Option<Self> {
+        match self {
+            SharedValue::Object(obj) => {
+                if !obj.contains_key(token.decoded().as_ref()) {
+                    return None;
+                }
+                Arc::make_mut(obj).remove(token.decoded().as_ref())
+            }
+            SharedValue::Array(arr) => {
+                let idx = parse_index(token.encoded(), arr.len()).ok()?;
+                Some(Arc::make_mut(arr).remove(idx))
+            }
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "diff")]
+    type JsonError = std::convert::Infallible;
+
+    #[cfg(feature = "diff")]
+    fn to_json(&self) -> Result<Value, Self::JsonError> {
+        Ok(self.into())
+    }
+
+    #[cfg(feature = "diff")]
+    fn node(&self) -> crate::document::Node<'_, Self> {
+        use crate::document::Node;
+        match self {
+            SharedValue::Object(obj) => Node::Object(
+                obj.iter()
+                    .map(|(key, value)| (key.clone(), value))
+                    .collect(),
+            ),
+            SharedValue::Array(arr) => Node::Array(arr),
+            _ => Node::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SharedValue;
+    use crate::Patch;
+    use serde_json::{from_value, json, Value};
+
+    #[test]
+    fn patch_shares_unchanged_subtrees() {
+        let original = json!({ "a": { "b": [1, 2], "c": "x" }, "d": { "e": 1 } });
+        let v1 = SharedValue::from(&original);
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "/a/b/-", "value": 3 },
+            { "op": "copy", "from": "/d", "path": "/f" },
+        ]))
+        .unwrap();
+
+        let mut v2 = v1.clone();
+        super::patch(&mut v2, &p).unwrap();
+        assert_eq!(Value::from(&v1), original);
+        assert_eq!(
+            Value::from(&v2),
+            json!({ "a": { "b": [1, 2, 3], "c": "x" }, "d": { "e": 1 }, "f": { "e": 1 } })
+        );
+
+        let get = |doc: &SharedValue, path| doc.pointer(path).unwrap().clone();
+        assert!(get(&v1, "/d").ptr_eq(&get(&v2, "/d")));
+        assert!(get(&v2, "/d").ptr_eq(&get(&v2, "/f")));
+        assert!(get(&v1, "/a/c").ptr_eq(&get(&v2, "/a/c")));
+        assert!(!get(&v1, "/a").ptr_eq(&get(&v2, "/a")));
+
+        // Failed patch leaves the document unchanged
+        let p: Patch = from_value(json!([
+            { "op": "remove", "path": "/d" },
+            { "op": "remove", "path": "/x" },
+        ]))
+        .unwrap();
+        let err = super::patch(&mut v2, &p).unwrap_err();
+        assert_eq!(err.operation, 1);
+        assert!(get(&v1, "/d").ptr_eq(&get(&v2, "/d")));
+    }
+
+    #[test]
+    #[cfg(feature = "diff")]
+    fn diff() {
+        let left = SharedValue::from(json!({ "a": [1, 2], "b": { "c": 1 } }));
+        let right = SharedValue::from(json!({ "a": [1], "b": { "c": 1 }, "d": null }));
+        let p = super::diff(&left, &right);
+        assert_eq!(
+            p,
+            from_value::<Patch>(json!([
+                { "op": "remove", "path": "/a/1" },
+                { "op": "add", "path": "/d", "value": null },
+            ]))
+            .unwrap()
+        );
+    }
+}
@@ -0,0 +1,282 @@
+//! Rendering of JSON Patches as SQL expressions, so patches can be applied by the database.
+//!
+//! The patch is rendered as a single expression transforming the given column (or any other
+//! SQL expression of JSON type), which can be used in an `UPDATE` statement:
+//!
+//! ```rust
+//! use json_patch::{sql, Patch};
+//! use serde_json::{from_value, json};
+//!
+//! let p: Patch = from_value(json!([
+//!   { "op": "replace", "path": "/title", "value": "Hello!" },
+//!   { "op": "remove", "path": "/author/familyName" },
+//! ])).unwrap();
+//!
+//! let expr = sql::to_sql(&p, "doc", sql::Dialect::PostgreSql).unwrap();
+//! assert_eq!(
+//!     format!("UPDATE articles SET doc = {expr}"),
+//!     r#"UPDATE articles SET doc = (jsonb_set(doc, '{"title"}', '"Hello!"'::jsonb, false) #- '{"author","familyName"}')"#,
+//! );
+//! ```
+//!
+//! SQL JSON functions differ from JSON Patch in a few ways, which should be kept in mind:
+//!
+//! * the column is not inspected, so `add` operations with the last token of the path being an
+//!   array index (or `-`) are rendered as array inserts, and other `add` operations as setting
+//!   object members; with MySQL, all numeric tokens are treated as array indices;
+//! * operations referring to missing locations are ignored instead of failing;
+//! * a failed `test` operation makes the whole expression `NULL`;
+//! * `move`, `copy` and `test` operations repeat the expression built so far, so the expression
+//!   grows quickly for patches with many of them.
+use crate::{translate_error, validate_operation, PatchError, PatchOperation};
+use jsonptr::{Pointer, Token};
+use serde_json::Value;
+
+/// SQL dialect, see [`to_sql`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Dialect {
+    /// PostgreSQL `jsonb` functions and operators: `jsonb_set`, `jsonb_insert`, `#-` and `#>`.
+    #[default]
+    PostgreSql,
+    /// MySQL JSON functions: `JSON_SET`, `JSON_REPLACE`, `JSON_REMOVE`, `JSON_ARRAY_INSERT`,
+    /// `JSON_ARRAY_APPEND` and `JSON_EXTRACT`.
+    MySql,
+}
+
+/// Render the patch as an SQL expression transforming the `column`, which is included in the
+/// expression as is. Returns an error if any of the operations is invalid regardless of the
+/// document, for example, removes the whole document.
+pub fn to_sql(
+    patch: &[PatchOperation],
+    column: &str,
+    dialect: Dialect,
+) -> Result<String, PatchError> {
+    let mut expr = column.to_owned();
+    for (operation, op) in patch.iter().enumerate() {
+        if let Some(kind) = validate_operation(op) {
+            return Err(translate_error(kind, operation, op));
+        }
+        expr = match dialect {
+            Dialect::PostgreSql => postgres(expr, op),
+            Dialect::MySql => mysql(expr, op),
+        };
+    }
+    Ok(expr)
+}
+
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+fn is_index(token: &Token) -> bool {
+    token.encoded() == "-" || token.to_index().is_ok()
+}
+
+fn postgres_path(path: &Pointer) -> String {
+    let tokens: Vec<_> = path
+        .tokens()
+        .map(|token| match token.encoded() {
+            // Negative indices count from the end
+            "-" => "\"-1\"".to_owned(),
+            _ => {
+                let token = token.decoded();
+                format!("\"{}\"", token.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+        })
+        .collect();
+    quote(&format!("{{{}}}", tokens.join(",")))
+}
+
+fn postgres_value(value: &Value) -> String {
+    format!("{}::jsonb", quote(&value.to_string()))
+}
+
+fn postgres_add(expr: &str, path: &Pointer, value: &str) -> String {
+    match path.back() {
+        None => value.to_owned(),
+        Some(last) if last.encoded() == "-" => {
+            format!(
+                "jsonb_insert({expr}, {}, {value}, true)",
+                postgres_path(path)
+            )
+        }
+        Some(last) if is_index(&last) => {
+            format!("jsonb_insert({expr}, {}, {value})", postgres_path(path))
+        }
+        Some(_) => format!("jsonb_set({expr}, {}, {value})", postgres_path(path)),
+    }
+}
+
+fn postgres(expr: String, op: &PatchOperation) -> String {
+    match op {
+        PatchOperation::Add(op) => postgres_add(&expr, &op.path, &postgres_value(&op.value)),
+        PatchOperation::Remove(op) => format!("({expr} #- {})", postgres_path(&op.path)),
+        PatchOperation::Replace(op) if op.path.is_root() => postgres_value(&op.value),
+        PatchOperation::Replace(op) => format!(
+            "jsonb_set({expr}, {}, {}, false)",
+            postgres_path(&op.path),
+            postgres_value(&op.value)
+        ),
+        PatchOperation::Move(op) if op.from == op.path => expr,
+        PatchOperation::Move(op) => {
+            let from = postgres_path(&op.from);
+            let removed = format!("({expr} #- {from})");
+            postgres_add(&removed, &op.path, &format!("({expr} #> {from})"))
+        }
+        PatchOperation::Copy(op) => {
+            let value = format!("({expr} #> {})", postgres_path(&op.from));
+            postgres_add(&expr, &op.path, &value)
+        }
+        PatchOperation::Test(op) => format!(
+            "CASE WHEN ({expr} #> {}) = {} THEN {expr} END",
+            postgres_path(&op.path),
+            postgres_value(&op.value)
+        ),
+    }
+}
+
+fn mysql_quote(s: &str) -> String {
+    // Backslash is an escape character in MySQL string literals
+    quote(&s.replace('\\', "\\\\"))
+}
+
+fn mysql_path(path: &Pointer) -> String {
+    let mut result = "$".to_owned();
+    for token in path.tokens() {
+        match token.to_index() {
+            Ok(jsonptr::index::Index::Num(idx)) => result.push_str(&format!("[{idx}]")),
+            Ok(jsonptr::index::Index::Next) => result.push_str("[last]"),
+            Err(_) => {
+                let token = token.decoded();
+                let token = token.replace('\\', "\\\\").replace('"', "\\\"");
+                result.push_str(&format!(".\"{token}\""));
+            }
+        }
+    }
+    mysql_quote(&result)
+}
+
+fn mysql_value(value: &Value) -> String {
+    format!("CAST({} AS JSON)", mysql_quote(&value.to_string()))
+}
+
+fn mysql_add(expr: &str, path: &Pointer, value: &str) -> String {
+    match path.split_back() {
+        None => value.to_owned(),
+        Some((parent, last)) if last.encoded() == "-" => {
+            format!("JSON_ARRAY_APPEND({expr}, {}, {value})", mysql_path(parent))
+        }
+        Some((_, last)) if is_index(&last) => {
+            format!("JSON_ARRAY_INSERT({expr}, {}, {value})", mysql_path(path))
+        }
+        Some(_) => format!("JSON_SET({expr}, {}, {value})", mysql_path(path)),
+    }
+}
+
+fn mysql(expr: String, op: &PatchOperation) -> String {
+    match op {
+        PatchOperation::Add(op) => mysql_add(&expr, &op.path, &mysql_value(&op.value)),
+        PatchOperation::Remove(op) => format!("JSON_REMOVE({expr}, {})", mysql_path(&op.path)),
+        PatchOperation::Replace(op) if op.path.is_root() => mysql_value(&op.value),
+        PatchOperation::Replace(op) => format!(
+            "JSON_REPLACE({expr}, {}, {})",
+            mysql_path(&op.path),
+            mysql_value(&op.value)
+        ),
+        PatchOperation::Move(op) if op.from == op.path => expr,
+        PatchOperation::Move(op) => {
+            let from = mysql_path(&op.from);
+            let removed = format!("JSON_REMOVE({expr}, {from})");
+            mysql_add(&removed, &op.path, &format!("JSON_EXTRACT({expr}, {from})"))
+        }
+        PatchOperation::Copy(op) => {
+            let value = format!("JSON_EXTRACT({expr}, {})", mysql_path(&op.from));
+            mysql_add(&expr, &op.path, &value)
+        }
+        PatchOperation::Test(op) => format!(
+            "CASE WHEN JSON_EXTRACT({expr}, {}) = {} THEN {expr} END",
+            mysql_path(&op.path),
+            mysql_value(&op.value)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_sql, Dialect};
+    use crate::Patch;
+    use serde_json::{from_value, json};
+
+    fn render(patch: serde_json::Value, dialect: Dialect) -> String {
+        let patch: Patch = from_value(patch).unwrap();
+        to_sql(&patch, "doc", dialect).unwrap()
+    }
+
+    #[test]
+    fn postgres() {
+        let render = |patch| render(patch, Dialect::PostgreSql);
+        assert_eq!(
+            render(json!([{ "op": "add", "path": "/a/-", "value": "it's" }])),
+            r#"jsonb_insert(doc, '{"a","-1"}', '"it''s"'::jsonb, true)"#
+        );
+        assert_eq!(
+            render(json!([{ "op": "add", "path": "/a/0", "value": 1 }])),
+            r#"jsonb_insert(doc, '{"a","0"}', '1'::jsonb)"#
+        );
+        assert_eq!(
+            render(json!([{ "op": "add", "path": "/a\"b\\c/d~1e", "value": null }])),
+            r#"jsonb_set(doc, '{"a\"b\\c","d/e"}', 'null'::jsonb)"#
+        );
+        assert_eq!(
+            render(json!([{ "op": "move", "from": "/a", "path": "/b" }])),
+            r#"jsonb_set((doc #- '{"a"}'), '{"b"}', (doc #> '{"a"}'))"#
+        );
+        assert_eq!(
+            render(json!([
+                { "op": "test", "path": "/a", "value": [1] },
+                { "op": "copy", "from": "/a/0", "path": "" },
+            ])),
+            r#"(CASE WHEN (doc #> '{"a"}') = '[1]'::jsonb THEN doc END #> '{"a","0"}')"#
+        );
+        assert_eq!(
+            render(json!([{ "op": "replace", "path": "", "value": {} }])),
+            "'{}'::jsonb"
+        );
+    }
+
+    #[test]
+    fn mysql() {
+        let render = |patch| render(patch, Dialect::MySql);
+        assert_eq!(
+            render(json!([{ "op": "add", "path": "/a/-", "value": "it's \\" }])),
+            r#"JSON_ARRAY_APPEND(doc, '$."a"', CAST('"it''s \\\\"' AS JSON))"#
+        );
+        assert_eq!(
+            render(json!([{ "op": "add", "path": "/a/0/b\"", "value": 1 }])),
+            r#"JSON_SET(doc, '$."a"[0]."b\\""', CAST('1' AS JSON))"#
+        );
+        assert_eq!(
+            render(json!([
+                { "op": "remove", "path": "/a/1" },
+                { "op": "replace", "path": "/b", "value": true },
+            ])),
+            r#"JSON_REPLACE(JSON_REMOVE(doc, '$."a"[1]'), '$."b"', CAST('true' AS JSON))"#
+        );
+        assert_eq!(
+            render(json!([{ "op": "copy", "from": "/a", "path": "/b/2" }])),
+            r#"JSON_ARRAY_INSERT(doc, '$."b"[2]', JSON_EXTRACT(doc, '$."a"'))"#
+        );
+        assert_eq!(
+            render(json!([{ "op": "test", "path": "/a", "value": 1 }])),
+            r#"CASE WHEN JSON_EXTRACT(doc, '$."a"') = CAST('1' AS JSON) THEN doc END"#
+        );
+    }
+
+    #[test]
+    fn invalid_operation() {
+        let patch: Patch = from_value(json!([{ "op": "remove", "path": "" }])).unwrap();
+        let err = to_sql(&patch, "doc", Dialect::MySql).unwrap_err();
+        assert!(matches!(err.kind, crate::PatchErrorKind::InvalidPointer));
+    }
+}
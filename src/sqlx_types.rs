@@ -0,0 +1,84 @@
+//! Storing patches in JSON columns with [sqlx](https://docs.rs/sqlx).
+//!
+//! [`Patch`] and [`MergePatch`] are encoded and decoded same as `sqlx::types::Json`, so they can
+//! be bound to queries and read from JSON (or `jsonb`) columns of any database supported by sqlx,
+//! without a `Json` wrapper.
+use crate::{MergePatch, Patch};
+use sqlx::{encode::IsNull, error::BoxDynError, types::Json, Database, Decode, Encode, Type};
+
+macro_rules! impl_sqlx {
+    ($name:ident) => {
+        impl<DB: Database> Type<DB> for $name
+        where
+            Json<$name>: Type<DB>,
+        {
+            fn type_info() -> DB::TypeInfo {
+                <Json<$name> as Type<DB>>::type_info()
+            }
+
+            fn compatible(ty: &DB::TypeInfo) -> bool {
+                <Json<$name> as Type<DB>>::compatible(ty)
+            }
+        }
+
+        impl<'q, DB: Database> Encode<'q, DB> for $name
+        where
+            for<'a> Json<&'a $name>: Encode<'q, DB>,
+        {
+            fn encode_by_ref(
+                &self,
+                buf: &mut <DB as Database>::ArgumentBuffer<'q>,
+            ) -> Result<IsNull, BoxDynError> {
+                Json(self).encode_by_ref(buf)
+            }
+        }
+
+        impl<'r, DB: Database> Decode<'r, DB> for $name
+        where
+            Json<$name>: Decode<'r, DB>,
+        {
+            fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+                Ok(Json::<$name>::decode(value)?.0)
+            }
+        }
+    };
+}
+
+impl_sqlx!(Patch);
+impl_sqlx!(MergePatch);
+
+#[cfg(test)]
+mod tests {
+    use crate::{MergePatch, Patch};
+    use serde_json::{from_value, json};
+    use sqlx::{
+        postgres::{PgArgumentBuffer, PgTypeInfo},
+        Decode, Encode, Postgres, Type,
+    };
+
+    fn assert_column<T>()
+    where
+        T: Type<Postgres> + for<'q> Encode<'q, Postgres> + for<'r> Decode<'r, Postgres>,
+    {
+        assert!(T::compatible(&PgTypeInfo::with_name("jsonb")));
+        assert!(T::compatible(&PgTypeInfo::with_name("json")));
+        assert!(!T::compatible(&PgTypeInfo::with_name("text")));
+    }
+
+    #[test]
+    fn postgres_columns() {
+        assert_column::<Patch>();
+        assert_column::<MergePatch>();
+    }
+
+    #[test]
+    fn encode() {
+        let patch: Patch = from_value(json!([{ "op": "remove", "path": "/a" }])).unwrap();
+        let mut buf = PgArgumentBuffer::default();
+        let is_null = Encode::<Postgres>::encode_by_ref(&patch, &mut buf).unwrap();
+        assert!(matches!(is_null, sqlx::encode::IsNull::No));
+        // `jsonb` version prefix
+        assert_eq!(buf[0], 1);
+        assert_eq!(&buf[1..], br#"[{"op":"remove","path":"/a"}]"#);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{apply_patches, undo_patches, Patch, PatchError, PatchOptions, UndoStack};
+use serde_json::Value;
+
+/// Operation applied by [`ApplyIter`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Applied {
+    /// Operations reverting the applied operation, in the order they should be applied.
+    pub undo: Patch,
+}
+
+/// Iterator applying a patch one operation at a time, see [`Patch::apply_iter`].
+#[derive(Debug)]
+pub struct ApplyIter<'a> {
+    doc: &'a mut Value,
+    patch: &'a Patch,
+    next: usize,
+    failed: bool,
+    undo_stack: UndoStack,
+}
+
+impl Patch {
+    /// Returns an iterator applying the operations of this patch to the document one at a time,
+    /// yielding the index of each operation with its result. Iteration stops after the first
+    /// failed operation, which leaves the document unchanged; the operations applied before it
+    /// are kept until [`ApplyIter::rollback`] is called.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::Patch;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let mut doc = json!({ "a": 1 });
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "replace", "path": "/a", "value": 2 },
+    ///   { "op": "add", "path": "/b", "value": 3 },
+    ///   { "op": "test", "path": "/a", "value": 1 },
+    /// ])).unwrap();
+    ///
+    /// let mut steps = p.apply_iter(&mut doc);
+    /// let (idx, applied) = steps.next().unwrap();
+    /// assert_eq!(idx, 0);
+    /// assert_eq!(applied.unwrap().undo, from_value::<Patch>(json!([
+    ///   { "op": "replace", "path": "/a", "value": 1 },
+    /// ])).unwrap());
+    /// assert!(steps.next().unwrap().1.is_ok());
+    /// assert!(steps.next().unwrap().1.is_err());
+    /// assert!(steps.next().is_none());
+    ///
+    /// steps.rollback().unwrap();
+    /// assert_eq!(doc, json!({ "a": 1 }));
+    /// ```
+    pub fn apply_iter<'a>(&'a self, doc: &'a mut Value) -> ApplyIter<'a> {
+        ApplyIter {
+            doc,
+            patch: self,
+            next: 0,
+            failed: false,
+            undo_stack: UndoStack::new(),
+        }
+    }
+}
+
+impl ApplyIter<'_> {
+    /// Returns the number of operations applied so far.
+    pub fn applied(&self) -> usize {
+        if self.failed {
+            self.next - 1
+        } else {
+            self.next
+        }
+    }
+
+    /// Returns the document with the operations applied so far.
+    pub fn doc(&self) -> &Value {
+        self.doc
+    }
+
+    /// Reverts all the operations applied so far, leaving the document as it was before the
+    /// iteration started.
+    pub fn rollback(self) -> Result<(), PatchError> {
+        undo_patches(self.doc, &self.undo_stack)
+    }
+}
+
+impl Iterator for ApplyIter<'_> {
+    type Item = (usize, Result<Applied, PatchError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let idx = self.next;
+        let op = self.patch.get(idx)?;
+        self.next += 1;
+
+        let undo_len = self.undo_stack.len();
+        let result = apply_patches(
+            self.doc,
+            std::slice::from_ref(op),
+            Some(&mut self.undo_stack),
+            &PatchOptions::default(),
+        );
+        Some((
+            idx,
+            match result {
+                Ok(()) => Ok(Applied {
+                    undo: self.undo_stack[undo_len..].iter().rev().cloned().collect(),
+                }),
+                Err(err) => {
+                    self.failed = true;
+                    Err(PatchError {
+                        operation: idx,
+                        ..err
+                    })
+                }
+            },
+        ))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = if self.failed {
+            0
+        } else {
+            self.patch.len() - self.next
+        };
+        (0, Some(remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Patch, PatchErrorKind};
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn step_and_rollback() {
+        let mut doc = json!({ "a": [1, 2] });
+        let p: Patch = from_value(json!([
+            { "op": "move", "from": "/a/0", "path": "/b" },
+            { "op": "add", "path": "/a/-", "value": 3 },
+            { "op": "remove", "path": "/c" },
+            { "op": "add", "path": "/d", "value": 4 },
+        ]))
+        .unwrap();
+
+        let mut steps = p.apply_iter(&mut doc);
+        assert_eq!(steps.next().unwrap().0, 0);
+        let (idx, applied) = steps.next().unwrap();
+        assert_eq!(idx, 1);
+        assert_eq!(
+            applied.unwrap().undo,
+            from_value::<Patch>(json!([{ "op": "remove", "path": "/a/1" }])).unwrap()
+        );
+        assert_eq!(steps.doc(), &json!({ "a": [2, 3], "b": 1 }));
+
+        let (idx, err) = steps.next().unwrap();
+        assert_eq!(idx, 2);
+        let err = err.unwrap_err();
+        assert_eq!(err.operation, 2);
+        assert!(matches!(err.kind, PatchErrorKind::InvalidPointer));
+        assert!(steps.next().is_none());
+        assert_eq!(steps.applied(), 2);
+
+        steps.rollback().unwrap();
+        assert_eq!(doc, json!({ "a": [1, 2] }));
+    }
+}
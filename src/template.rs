@@ -0,0 +1,374 @@
+//! Parameterized patches with `{{name}}` placeholders.
+//!
+//! Placeholders can appear in string values (at any depth) and in `path` and `from` pointers.
+//! A string value consisting of a single placeholder is replaced by the variable value as is, so
+//! variables can hold any JSON value. Placeholders embedded in a longer string, or in a pointer,
+//! are replaced by the string form of the variable, which must be a string, a number or a
+//! boolean. Variables substituted into pointers are escaped, so a variable containing `/` does not
+//! introduce new path segments.
+//!
+//! Placeholder names can contain ASCII letters, digits, `_`, `-` and `.`, and can be surrounded by
+//! spaces (`{{ name }}`).
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::template::PatchTemplate;
+//! use serde_json::{from_value, json};
+//!
+//! let template: PatchTemplate = from_value(json!([
+//!   { "op": "add", "path": "/tenants/{{tenant_id}}", "value": {
+//!     "owner": "{{owner}}",
+//!     "title": "Tenant {{tenant_id}}",
+//!   } },
+//! ])).unwrap();
+//! assert_eq!(template.placeholders().collect::<Vec<_>>(), ["owner", "tenant_id"]);
+//!
+//! let mut doc = json!({ "tenants": {} });
+//! let vars = json!({ "tenant_id": 42, "owner": { "name": "Andrew" } });
+//! template.apply(&mut doc, vars.as_object().unwrap()).unwrap();
+//! assert_eq!(doc, json!({
+//!   "tenants": { "42": { "owner": { "name": "Andrew" }, "title": "Tenant 42" } }
+//! }));
+//! ```
+use crate::{
+    patch, AddOperation, Patch, PatchError, PatchOperation, ReplaceOperation, TestOperation,
+};
+use jsonptr::{Pointer, PointerBuf};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{cell::RefCell, collections::BTreeSet};
+use thiserror::Error;
+
+/// Patch with placeholders, validated up front.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(try_from = "Patch", into = "Patch")]
+pub struct PatchTemplate {
+    patch: Patch,
+    placeholders: BTreeSet<String>,
+}
+
+/// Error that can occur while parsing or rendering a [`PatchTemplate`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TemplateError {
+    /// Placeholder is not terminated or has an invalid name.
+    #[error("operation '/{operation}' has invalid placeholder `{text}`")]
+    InvalidPlaceholder {
+        /// Index of the operation.
+        operation: usize,
+        /// Text of the placeholder.
+        text: String,
+    },
+    /// Some placeholders have no value.
+    #[error("missing template variables: {}", .0.join(", "))]
+    MissingVariables(Vec<String>),
+    /// Variable substituted into a string or a pointer is not a string, a number or a boolean.
+    #[error("template variable `{0}` cannot be converted to a string")]
+    NotScalar(String),
+    /// Rendered patch failed to apply.
+    #[error(transparent)]
+    Patch(#[from] PatchError),
+}
+
+impl PatchTemplate {
+    /// Validates the placeholders of the patch and creates a template from it.
+    pub fn new(patch: Patch) -> Result<Self, TemplateError> {
+        let mut placeholders = BTreeSet::new();
+        for (operation, op) in patch.iter().enumerate() {
+            let mut collect = |text: &str| {
+                for part in parse(text) {
+                    match part {
+                        Part::Variable(name) => {
+                            placeholders.insert(name.to_owned());
+                        }
+                        Part::Invalid(text) => {
+                            return Err(TemplateError::InvalidPlaceholder {
+                                operation,
+                                text: text.to_owned(),
+                            })
+                        }
+                        Part::Text(_) => {}
+                    }
+                }
+                Ok(())
+            };
+            collect(op.path().as_str())?;
+            if let Some(from) = op.from() {
+                collect(from.as_str())?;
+            }
+            if let Some(value) = op.value() {
+                visit_strings(value, &mut collect)?;
+            }
+        }
+        Ok(PatchTemplate {
+            patch,
+            placeholders,
+        })
+    }
+
+    /// Returns the names of all placeholders used by the template, in sorted order.
+    pub fn placeholders(&self) -> impl Iterator<Item = &str> {
+        self.placeholders.iter().map(String::as_str)
+    }
+
+    /// Returns the template patch, with placeholders left as is.
+    pub fn as_patch(&self) -> &Patch {
+        &self.patch
+    }
+
+    /// Substitutes the variables into the template. Extra variables are ignored.
+    pub fn render(&self, vars: &Map<String, Value>) -> Result<Patch, TemplateError> {
+        let missing: Vec<String> = self
+            .placeholders()
+            .filter(|name| !vars.contains_key(*name))
+            .map(str::to_owned)
+            .collect();
+        if !missing.is_empty() {
+            return Err(TemplateError::MissingVariables(missing));
+        }
+        self.patch
+            .iter()
+            .map(|op| render_operation(op, vars))
+            .collect()
+    }
+
+    /// Renders the template and applies it to the document.
+    pub fn apply(&self, doc: &mut Value, vars: &Map<String, Value>) -> Result<(), TemplateError> {
+        let rendered = self.render(vars)?;
+        patch(doc, &rendered)?;
+        Ok(())
+    }
+}
+
+impl TryFrom<Patch> for PatchTemplate {
+    type Error = TemplateError;
+
+    fn try_from(patch: Patch) -> Result<Self, Self::Error> {
+        PatchTemplate::new(patch)
+    }
+}
+
+impl From<PatchTemplate> for Patch {
+    fn from(template: PatchTemplate) -> Self {
+        template.patch
+    }
+}
+
+enum Part<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+    Invalid(&'a str),
+}
+
+// Splits the text into literal parts and placeholders. Parsing stops at the first invalid
+// placeholder.
+fn parse(mut text: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    while let Some(start) = text.find("{{") {
+        if start > 0 {
+            parts.push(Part::Text(&text[..start]));
+        }
+        let rest = &text[start..];
+        let Some(end) = rest.find("}}") else {
+            parts.push(Part::Invalid(rest));
+            return parts;
+        };
+        let name = rest[2..end].trim();
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if !valid {
+            parts.push(Part::Invalid(&rest[..end + 2]));
+            return parts;
+        }
+        parts.push(Part::Variable(name));
+        text = &rest[end + 2..];
+    }
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    parts
+}
+
+fn visit_strings<E>(value: &Value, f: &mut impl FnMut(&str) -> Result<(), E>) -> Result<(), E> {
+    match value {
+        Value::String(s) => f(s),
+        Value::Array(arr) => arr.iter().try_for_each(|item| visit_strings(item, f)),
+        Value::Object(obj) => obj.values().try_for_each(|item| visit_strings(item, f)),
+        _ => Ok(()),
+    }
+}
+
+fn render_operation(
+    op: &PatchOperation,
+    vars: &Map<String, Value>,
+) -> Result<PatchOperation, TemplateError> {
+    let error = RefCell::new(None);
+    let rendered = op.map_pointers(|ptr| {
+        render_pointer(ptr, vars)
+            .map_err(|err| *error.borrow_mut() = Some(err))
+            .ok()
+    });
+    let Some(mut rendered) = rendered else {
+        return Err(error.into_inner().expect("error is set"));
+    };
+    match &mut rendered {
+        PatchOperation::Add(AddOperation { value, .. })
+        | PatchOperation::Replace(ReplaceOperation { value, .. })
+        | PatchOperation::Test(TestOperation { value, .. }) => {
+            *value = render_value(value, vars)?;
+        }
+        _ => {}
+    }
+    Ok(rendered)
+}
+
+fn render_pointer(ptr: &Pointer, vars: &Map<String, Value>) -> Result<PointerBuf, TemplateError> {
+    if !ptr.as_str().contains("{{") {
+        return Ok(ptr.to_buf());
+    }
+    let mut rendered = String::new();
+    for part in parse(ptr.as_str()) {
+        match part {
+            Part::Text(text) => rendered.push_str(text),
+            Part::Variable(name) => {
+                let value = to_string(name, vars)?;
+                rendered.push_str(&value.replace('~', "~0").replace('/', "~1"));
+            }
+            Part::Invalid(_) => unreachable!("placeholders are validated"),
+        }
+    }
+    Ok(PointerBuf::parse(&rendered).expect("escaped variables keep the pointer valid"))
+}
+
+fn render_value(value: &Value, vars: &Map<String, Value>) -> Result<Value, TemplateError> {
+    Ok(match value {
+        Value::String(s) if s.contains("{{") => {
+            let parts = parse(s);
+            if let [Part::Variable(name)] = parts[..] {
+                return Ok(vars[name].clone());
+            }
+            let mut rendered = String::new();
+            for part in parts {
+                match part {
+                    Part::Text(text) => rendered.push_str(text),
+                    Part::Variable(name) => rendered.push_str(&to_string(name, vars)?),
+                    Part::Invalid(_) => unreachable!("placeholders are validated"),
+                }
+            }
+            Value::String(rendered)
+        }
+        Value::Array(arr) => Value::Array(
+            arr.iter()
+                .map(|item| render_value(item, vars))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .map(|(key, item)| Ok((key.clone(), render_value(item, vars)?)))
+                .collect::<Result<_, TemplateError>>()?,
+        ),
+        value => value.clone(),
+    })
+}
+
+fn to_string(name: &str, vars: &Map<String, Value>) -> Result<String, TemplateError> {
+    match &vars[name] {
+        Value::String(s) => Ok(s.clone()),
+        value @ (Value::Number(_) | Value::Bool(_)) => Ok(value.to_string()),
+        _ => Err(TemplateError::NotScalar(name.to_owned())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PatchTemplate, TemplateError};
+    use crate::Patch;
+    use serde_json::{from_value, json, Value};
+
+    fn template(value: Value) -> Result<PatchTemplate, TemplateError> {
+        PatchTemplate::new(from_value(value).unwrap())
+    }
+
+    #[test]
+    fn invalid_placeholders() {
+        for (text, invalid) in [
+            ("/a/{{name", "{{name"),
+            ("/a/{{}}", "{{}}"),
+            ("/a/{{na me}}", "{{na me}}"),
+        ] {
+            let err = template(json!([
+                { "op": "add", "path": "/x", "value": 1 },
+                { "op": "add", "path": text, "value": 1 },
+            ]))
+            .unwrap_err();
+            assert!(
+                matches!(&err, TemplateError::InvalidPlaceholder { operation: 1, text } if text == invalid),
+                "{err}"
+            );
+        }
+
+        let err = template(json!([{ "op": "add", "path": "/x", "value": ["{{ok}} {{bad"] }]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "operation '/0' has invalid placeholder `{{bad`"
+        );
+    }
+
+    #[test]
+    fn render() {
+        let t = template(json!([
+            { "op": "copy", "from": "/templates/{{ kind }}", "path": "/users/{{id}}" },
+            { "op": "test", "path": "/count", "value": "{{count}}" },
+            { "op": "replace", "path": "/label", "value": ["{{kind}}-{{count}}", "{{flag}}"] },
+        ]))
+        .unwrap();
+        assert_eq!(
+            t.placeholders().collect::<Vec<_>>(),
+            ["count", "flag", "id", "kind"]
+        );
+
+        let vars = json!({ "id": "a/b", "kind": "admin", "count": 3, "flag": true, "extra": 1 });
+        let p = t.render(vars.as_object().unwrap()).unwrap();
+        assert_eq!(
+            p,
+            from_value::<Patch>(json!([
+                { "op": "copy", "from": "/templates/admin", "path": "/users/a~1b" },
+                { "op": "test", "path": "/count", "value": 3 },
+                { "op": "replace", "path": "/label", "value": ["admin-3", true] },
+            ]))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn render_errors() {
+        let t = template(json!([
+            { "op": "add", "path": "/{{a}}", "value": "{{b}}" },
+            { "op": "add", "path": "/x", "value": "{{c}}!" },
+        ]))
+        .unwrap();
+
+        let err = t
+            .render(json!({ "b": 1 }).as_object().unwrap())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "missing template variables: a, c");
+
+        let vars = json!({ "a": {}, "b": 1, "c": 2 });
+        let err = t.render(vars.as_object().unwrap()).unwrap_err();
+        assert!(matches!(err, TemplateError::NotScalar(name) if name == "a"));
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        let value = json!([{ "op": "add", "path": "/{{a}}", "value": "{{b}}" }]);
+        let t: PatchTemplate = from_value(value.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&t).unwrap(), value);
+
+        let err = from_value::<PatchTemplate>(json!([{ "op": "remove", "path": "/{{a" }]));
+        assert!(err.is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Assertions for testing code producing or applying patches.
+//!
+//! On failure, the assertions report the first location where the documents differ along with
+//! both values, rather than the whole documents.
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::{assert_diff_roundtrip, assert_patches_to, Patch};
+//! use serde_json::{from_value, json};
+//!
+//! let doc = json!({ "title": "Hello", "tags": ["a"] });
+//! let p: Patch = from_value(json!([
+//!   { "op": "add", "path": "/tags/-", "value": "b" },
+//! ])).unwrap();
+//! assert_patches_to!(doc, p, json!({ "title": "Hello", "tags": ["a", "b"] }));
+//!
+//! assert_diff_roundtrip!(doc, json!({ "title": "Hello!" }));
+//! ```
+use crate::{diff, PatchOperation};
+use jsonptr::PointerBuf;
+use serde_json::Value;
+use std::fmt::{self, Display, Formatter};
+
+/// First location where two documents differ, see [`find_mismatch`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    /// Location of the differing values.
+    pub path: PointerBuf,
+    /// Value in the left document, `None` if there is no value at this location.
+    pub left: Option<Value>,
+    /// Value in the right document, `None` if there is no value at this location.
+    pub right: Option<Value>,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let describe = |value: &Option<Value>| match value {
+            Some(value) => serde_json::to_string_pretty(value).unwrap_or_default(),
+            None => "(missing)".to_owned(),
+        };
+        writeln!(f, "documents differ at '{}'", self.path)?;
+        writeln!(f, "  left: {}", describe(&self.left))?;
+        write!(f, " right: {}", describe(&self.right))
+    }
+}
+
+/// Returns the first location, in document order, where the documents differ, or `None` if they
+/// are equal.
+pub fn find_mismatch(left: &Value, right: &Value) -> Option<Mismatch> {
+    find_mismatch_at(left, right, &mut PointerBuf::new())
+}
+
+fn find_mismatch_at(left: &Value, right: &Value, path: &mut PointerBuf) -> Option<Mismatch> {
+    let missing = |path: &PointerBuf, left: Option<&Value>, right: Option<&Value>| Mismatch {
+        path: path.clone(),
+        left: left.cloned(),
+        right: right.cloned(),
+    };
+    match (left, right) {
+        (Value::Object(left_obj), Value::Object(right_obj)) => {
+            let keys = left_obj
+                .keys()
+                .chain(right_obj.keys().filter(|key| !left_obj.contains_key(*key)));
+            for key in keys {
+                path.push_back(key.as_str());
+                let mismatch = match (left_obj.get(key), right_obj.get(key)) {
+                    (Some(left), Some(right)) => find_mismatch_at(left, right, path),
+                    (left, right) => Some(missing(path, left, right)),
+                };
+                path.pop_back();
+                if mismatch.is_some() {
+                    return mismatch;
+                }
+            }
+            None
+        }
+        (Value::Array(left_arr), Value::Array(right_arr)) => {
+            for idx in 0..left_arr.len().max(right_arr.len()) {
+                path.push_back(idx);
+                let mismatch = match (left_arr.get(idx), right_arr.get(idx)) {
+                    (Some(left), Some(right)) => find_mismatch_at(left, right, path),
+                    (left, right) => Some(missing(path, left, right)),
+                };
+                path.pop_back();
+                if mismatch.is_some() {
+                    return mismatch;
+                }
+            }
+            None
+        }
+        _ if left == right => None,
+        _ => Some(missing(path, Some(left), Some(right))),
+    }
+}
+
+/// Applies the patch to a copy of the document and panics if it fails or the result differs from
+/// the expected document. Used by [`assert_patches_to!`](crate::assert_patches_to).
+#[track_caller]
+pub fn assert_patches_to(doc: &Value, patch: &[PatchOperation], expected: &Value) {
+    let mut patched = doc.clone();
+    if let Err(err) = crate::patch(&mut patched, patch) {
+        panic!("assertion failed: patch could not be applied: {}", err);
+    }
+    if let Some(mismatch) = find_mismatch(&patched, expected) {
+        panic!(
+            "assertion failed: patched document is not the expected one, {}",
+            mismatch
+        );
+    }
+}
+
+/// Diffs the documents and panics if applying the diff to the left one does not give the right
+/// one. Used by [`assert_diff_roundtrip!`](crate::assert_diff_roundtrip).
+#[track_caller]
+pub fn assert_diff_roundtrip(left: &Value, right: &Value) {
+    let patch = diff(left, right);
+    let mut patched = left.clone();
+    let result = crate::patch(&mut patched, &patch)
+        .map_err(|err| err.to_string())
+        .and_then(|()| find_mismatch(&patched, right).map_or(Ok(()), |m| Err(m.to_string())));
+    if let Err(reason) = result {
+        panic!(
+            "assertion failed: diff does not roundtrip, {}\n patch: {}",
+            reason,
+            serde_json::to_string_pretty(&patch).unwrap_or_default()
+        );
+    }
+}
+
+/// Asserts that applying the patch to the document gives the expected document.
+///
+/// The document is not modified. See [`testing::assert_patches_to`](crate::testing::assert_patches_to).
+#[macro_export]
+macro_rules! assert_patches_to {
+    ($doc:expr, $patch:expr, $expected:expr $(,)?) => {
+        $crate::testing::assert_patches_to(&$doc, &$patch, &$expected)
+    };
+}
+
+/// Asserts that the diff of two documents, applied to the first one, gives the second one.
+///
+/// See [`testing::assert_diff_roundtrip`](crate::testing::assert_diff_roundtrip).
+#[macro_export]
+macro_rules! assert_diff_roundtrip {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::testing::assert_diff_roundtrip(&$left, &$right)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_mismatch;
+    use crate::Patch;
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn mismatch() {
+        let left = json!({ "a": [1, { "b": 2 }], "c": 3 });
+        assert_eq!(find_mismatch(&left, &left), None);
+
+        let mismatch = find_mismatch(&left, &json!({ "a": [1, { "b": 3 }], "c": 4 })).unwrap();
+        assert_eq!(mismatch.path.as_str(), "/a/1/b");
+        assert_eq!(
+            mismatch.to_string(),
+            "documents differ at '/a/1/b'\n  left: 2\n right: 3"
+        );
+
+        let mismatch = find_mismatch(&left, &json!({ "a": [1], "c": 3 })).unwrap();
+        assert_eq!(mismatch.path.as_str(), "/a/1");
+        assert_eq!(mismatch.right, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "documents differ at '/a'")]
+    fn patches_to_mismatch() {
+        let p: Patch = from_value(json!([{ "op": "add", "path": "/a", "value": 1 }])).unwrap();
+        assert_patches_to!(json!({}), p, json!({ "a": 2 }));
+    }
+}
@@ -0,0 +1,301 @@
+//! JSON Patch and JSON Merge Patch for TOML documents.
+//!
+//! Documents are patched as [`toml::Value`] directly. Tables are navigated as JSON objects and
+//! arrays as JSON arrays. Values carried by the patch are converted to TOML; since TOML has no
+//! `null`, operations with values containing `null` fail with
+//! [`PatchErrorKind::UnsupportedValue`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::{patch_toml, Patch};
+//! use serde_json::{from_value, json};
+//!
+//! let mut doc: toml::Value = toml::from_str(r#"
+//! [package]
+//! name = "example"
+//! version = "0.1.0"
+//!
+//! [dependencies]
+//! serde = "1.0"
+//! "#).unwrap();
+//!
+//! let p: Patch = from_value(json!([
+//!   { "op": "replace", "path": "/package/version", "value": "0.2.0" },
+//!   { "op": "add", "path": "/dependencies/serde_json", "value": { "version": "1.0" } },
+//! ])).unwrap();
+//! patch_toml(&mut doc, &p).unwrap();
+//!
+//! assert_eq!(doc["package"]["version"].as_str(), Some("0.2.0"));
+//! assert_eq!(doc["dependencies"]["serde_json"]["version"].as_str(), Some("1.0"));
+//! ```
+#[cfg(feature = "diff")]
+use crate::document::Node;
+use crate::{document::Document, parse_index, PatchError, PatchErrorKind, PatchOperation};
+use jsonptr::Token;
+use toml::{Table, Value};
+
+/// Patch provided TOML document in place, same as [`crate::patch`]. If any of the operations
+/// fails, the document is left unchanged.
+pub fn patch_toml(doc: &mut Value, patch: &[PatchOperation]) -> Result<(), PatchError> {
+    crate::document::patch(doc, patch)
+}
+
+/// Patch provided TOML document in place with JSON Merge Patch (RFC 7396), same as
+/// [`crate::merge`]. The merge patch is given as JSON, so it can remove members with `null`.
+///
+/// Returns [`PatchErrorKind::UnsupportedValue`] and leaves the document unchanged if a merged
+/// value cannot be represented in TOML (for example, an array containing `null`).
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::merge_toml;
+/// use serde_json::json;
+///
+/// let mut doc: toml::Value = toml::from_str("a = 1\n[b]\nc = 2\nd = 3\n").unwrap();
+/// merge_toml(&mut doc, &json!({ "b": { "c": null, "e": [4] } })).unwrap();
+/// assert_eq!(doc, toml::from_str("a = 1\n[b]\nd = 3\ne = [4]\n").unwrap());
+/// ```
+pub fn merge_toml(doc: &mut Value, patch: &serde_json::Value) -> Result<(), PatchErrorKind> {
+    let mut merged = doc.clone();
+    merge_impl(&mut merged, patch)?;
+    *doc = merged;
+    Ok(())
+}
+
+fn merge_impl(doc: &mut Value, patch: &serde_json::Value) -> Result<(), PatchErrorKind> {
+    let serde_json::Value::Object(patch) = patch else {
+        *doc = Value::from_json(patch)?;
+        return Ok(());
+    };
+
+    if !doc.is_table() {
+        *doc = Value::Table(Table::new());
+    }
+    let Value::Table(table) = doc else {
+        unreachable!()
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            table.remove(key);
+        } else {
+            let member = table
+                .entry(key.as_str())
+                .or_insert_with(|| Value::Table(Table::new()));
+            merge_impl(member, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Diff two TOML documents and generate a JSON Patch (RFC 6902), same as [`crate::diff`].
+///
+/// Returns [`PatchErrorKind::UnsupportedValue`] if a value added by the patch cannot be
+/// represented as JSON so that [`patch_toml`] restores it, that is, a datetime or a non-finite
+/// float.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{diff_toml, Patch};
+/// use serde_json::{from_value, json};
+///
+/// let left: toml::Value = toml::from_str("a = 1\nb = [1, 2]\n").unwrap();
+/// let right: toml::Value = toml::from_str("a = 2\nb = [1]\n").unwrap();
+/// assert_eq!(diff_toml(&left, &right).unwrap(), from_value::<Patch>(json!([
+///   { "op": "replace", "path": "/a", "value": 2 },
+///   { "op": "remove", "path": "/b/1" },
+/// ])).unwrap());
+/// ```
+#[cfg(feature = "diff")]
+pub fn diff_toml(left: &Value, right: &Value) -> Result<crate::Patch, PatchErrorKind> {
+    crate::document::diff(left, right)
+}
+
+impl Document for Value {
+    fn from_json(value: &serde_json::Value) -> Result<Self, PatchErrorKind> {
+        Ok(match value {
+            serde_json::Value::Null => return Err(PatchErrorKind::UnsupportedValue),
+            serde_json::Value::Bool(b) => Value::Boolean(*b),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => Value::Integer(i),
+                None if n.is_u64() => return Err(PatchErrorKind::UnsupportedValue),
+                None => Value::Float(n.as_f64().ok_or(PatchErrorKind::UnsupportedValue)?),
+            },
+            serde_json::Value::String(s) => Value::String(s.clone()),
+            serde_json::Value::Array(arr) => {
+                Value::Array(arr.iter().map(Value::from_json).collect::<Result<_, _>>()?)
+            }
+            serde_json::Value::Object(obj) => Value::Table(
+                obj.iter()
+                    .map(|(key, value)| Ok((key.clone(), Value::from_json(value)?)))
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+
+    fn child(&self, token: &Token) -> Option<&Self> {
+        match self {
+            Value::Table(table) => table.get(token.decoded().as_ref()),
+            Value::Array(arr) => arr.get(parse_index(token.encoded(), arr.len()).ok()?),
+            _ => None,
+        }
+    }
+
+    fn child_mut(&mut self, token: &Token) -> Option<&mut Self> {
+        match self {
+            Value::Table(table) => table.get_mut(token.decoded().as_ref()),
+            Value::Array(arr) => {
+                let idx = parse_index(token.encoded(), arr.len()).ok()?;
+                arr.get_mut(idx)
+            }
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, token: &Token, value: Self) -> Result<(), PatchErrorKind> {
+        match self {
+            Value::Table(table) => {
+                table.insert(token.decoded().into_owned(), value);
+            }
+            Value::Array(arr) if token.encoded() == "-" => arr.push(value),
+            Value::Array(arr) => {
+                let idx = parse_index(token.encoded(), arr.len() + 1)?;
+                arr.insert(idx, value);
+            }
+            _ => return Err(PatchErrorKind::InvalidPointer),
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, token: &Token) -> Option<Self> {
+        match self {
+            Value::Table(table) => table.remove(token.decoded().as_ref()),
+            Value::Array(arr) => {
+                let idx = parse_index(token.encoded(), arr.len()).ok()?;
+                Some(arr.remove(idx))
+            }
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "diff")]
+    type JsonError = PatchErrorKind;
+
+    #[cfg(feature = "diff")]
+    fn to_json(&self) -> Result<serde_json::Value, PatchErrorKind> {
+        Ok(match self {
+            Value::String(s) => serde_json::Value::String(s.clone()),
+            Value::Integer(i) => serde_json::Value::from(*i),
+            // Integral floats are kept as floats by `from_json`
+            Value::Float(f) => serde_json::Number::from_f64(*f)
+                .ok_or(PatchErrorKind::UnsupportedValue)?
+                .into(),
+            Value::Boolean(b) => serde_json::Value::Bool(*b),
+            Value::Datetime(_) => return Err(PatchErrorKind::UnsupportedValue),
+            Value::Array(arr) => arr.iter().map(Value::to_json).collect::<Result<_, _>>()?,
+            Value::Table(table) => table
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), value.to_json()?)))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    #[cfg(feature = "diff")]
+    fn node(&self) -> Node<'_, Self> {
+        match self {
+            Value::Table(table) => Node::Object(
+                table
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value))
+                    .collect(),
+            ),
+            Value::Array(arr) => Node::Array(arr),
+            _ => Node::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Patch, PatchErrorKind};
+    use serde_json::{from_value, json};
+    use toml::Value;
+
+    fn toml(s: &str) -> Value {
+        toml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn patch_toml() {
+        let mut doc = toml("a = [1, 2]\n[b]\nc = 1979-05-27T07:32:00Z\n");
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "/a/-", "value": 3.5 },
+            { "op": "move", "from": "/b/c", "path": "/d" },
+            { "op": "copy", "from": "/a/0", "path": "/b/e" },
+            { "op": "test", "path": "/b", "value": { "e": 1 } },
+        ]))
+        .unwrap();
+        super::patch_toml(&mut doc, &p).unwrap();
+        assert_eq!(
+            doc,
+            toml("a = [1, 2, 3.5]\nd = 1979-05-27T07:32:00Z\n[b]\ne = 1\n")
+        );
+
+        let p: Patch = from_value(json!([
+            { "op": "remove", "path": "/a/0" },
+            { "op": "add", "path": "/b/f", "value": [null] },
+        ]))
+        .unwrap();
+        let err = super::patch_toml(&mut doc, &p).unwrap_err();
+        assert_eq!(err.operation, 1);
+        assert!(matches!(err.kind, PatchErrorKind::UnsupportedValue));
+        assert_eq!(doc["a"].as_array().unwrap().len(), 3);
+
+        let p: Patch =
+            from_value(json!([{ "op": "add", "path": "/a/0", "value": u64::MAX }])).unwrap();
+        let err = super::patch_toml(&mut doc, &p).unwrap_err();
+        assert!(matches!(err.kind, PatchErrorKind::UnsupportedValue));
+    }
+
+    #[test]
+    fn merge_toml() {
+        let mut doc = toml("a = 1\n[b]\nc = 2\n");
+        let err = super::merge_toml(&mut doc, &json!({ "a": 2, "b": [null] })).unwrap_err();
+        assert!(matches!(err, PatchErrorKind::UnsupportedValue));
+        assert_eq!(doc, toml("a = 1\n[b]\nc = 2\n"));
+
+        super::merge_toml(&mut doc, &json!({ "a": null, "b": { "d": { "e": 1 } } })).unwrap();
+        assert_eq!(doc, toml("[b]\nc = 2\n[b.d]\ne = 1\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "diff")]
+    fn diff_toml() {
+        let left = toml("a = [1, 2]\nb = 1979-05-27\n[c]\nd = 1\n");
+        let right = toml("a = [1]\nb = 1979-05-27\ne = 2.0\n[c]\nd = 1.5\nf = [{ g = 1 }]\n");
+        let p = super::diff_toml(&left, &right).unwrap();
+        assert_eq!(
+            p,
+            from_value::<Patch>(json!([
+                { "op": "remove", "path": "/a/1" },
+                { "op": "replace", "path": "/c/d", "value": 1.5 },
+                { "op": "add", "path": "/c/f", "value": [{ "g": 1 }] },
+                { "op": "add", "path": "/e", "value": 2.0 },
+            ]))
+            .unwrap()
+        );
+
+        let mut patched = left.clone();
+        super::patch_toml(&mut patched, &p).unwrap();
+        assert_eq!(patched, right);
+
+        let right = toml("a = [1, 2]\nb = 1979-05-28\n[c]\nd = 1\n");
+        let err = super::diff_toml(&left, &right).unwrap_err();
+        assert!(matches!(err, PatchErrorKind::UnsupportedValue));
+        let right = toml("a = [1, 2]\nb = 1979-05-27\n[c]\nd = nan\n");
+        let err = super::diff_toml(&left, &right).unwrap_err();
+        assert!(matches!(err, PatchErrorKind::UnsupportedValue));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{AddOperation, Patch, PatchOperation, ReplaceOperation, TestOperation};
+use serde_json::Value;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+impl Patch {
+    /// Returns a wrapper displaying at most `max_ops` operations of this patch, with values longer
+    /// than `max_value_len` bytes elided, so the patch can be logged regardless of its size.
+    ///
+    /// The output is still a JSON array. An elided value is replaced with a string holding the
+    /// beginning of its JSON representation followed by `...`, and the omitted operations are
+    /// replaced with a single string element telling how many there were.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use json_patch::Patch;
+    /// use serde_json::{from_value, json};
+    ///
+    /// let p: Patch = from_value(json!([
+    ///   { "op": "add", "path": "/data", "value": "SGVsbG8sIHdvcmxkIQ==" },
+    ///   { "op": "remove", "path": "/a" },
+    ///   { "op": "remove", "path": "/b" },
+    /// ])).unwrap();
+    ///
+    /// assert_eq!(
+    ///   p.display_truncated(2, 10).to_string(),
+    ///   r#"[{"op":"add","path":"/data","value":"\"SGVsbG8sI..."},{"op":"remove","path":"/a"},"... 1 more operations"]"#
+    /// );
+    /// ```
+    pub fn display_truncated(&self, max_ops: usize, max_value_len: usize) -> Truncated<'_> {
+        Truncated {
+            patch: self,
+            max_ops,
+            max_value_len,
+        }
+    }
+}
+
+/// Displays a patch with the number of operations and the length of values limited. Created by
+/// [`Patch::display_truncated`].
+#[derive(Clone, Copy, Debug)]
+pub struct Truncated<'a> {
+    patch: &'a Patch,
+    max_ops: usize,
+    max_value_len: usize,
+}
+
+impl Display for Truncated<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut ops: Vec<Value> = self
+            .patch
+            .iter()
+            .take(self.max_ops)
+            .map(|op| truncate_operation(op, self.max_value_len))
+            .map(|op| serde_json::to_value(op).map_err(|_| fmt::Error))
+            .collect::<Result<_, _>>()?;
+        let omitted = self.patch.len().saturating_sub(self.max_ops);
+        if omitted > 0 {
+            ops.push(Value::from(format!("... {} more operations", omitted)));
+        }
+        Display::fmt(&Value::Array(ops), f)
+    }
+}
+
+fn truncate_operation(op: &PatchOperation, max_len: usize) -> PatchOperation {
+    // Avoid cloning the values which are going to be elided anyway
+    let Some(preview) = op.value().and_then(|value| preview(value, max_len)) else {
+        return op.clone();
+    };
+    let path = op.path().to_buf();
+    let value = Value::String(preview);
+    match op {
+        PatchOperation::Add(_) => PatchOperation::Add(AddOperation { path, value }),
+        PatchOperation::Replace(_) => PatchOperation::Replace(ReplaceOperation { path, value }),
+        PatchOperation::Test(_) => PatchOperation::Test(TestOperation { path, value }),
+        PatchOperation::Remove(_) | PatchOperation::Move(_) | PatchOperation::Copy(_) => {
+            unreachable!()
+        }
+    }
+}
+
+/// Returns the beginning of the compact JSON representation of the value if it is longer than
+/// `max_len` bytes.
+fn preview(value: &Value, max_len: usize) -> Option<String> {
+    let mut writer = LimitedWriter {
+        buf: Vec::new(),
+        limit: max_len,
+    };
+    if serde_json::to_writer(&mut writer, value).is_ok() {
+        return None;
+    }
+    let valid = match std::str::from_utf8(&writer.buf) {
+        Ok(valid) => valid,
+        Err(err) => std::str::from_utf8(&writer.buf[..err.valid_up_to()]).unwrap(),
+    };
+    Some(format!("{}...", valid))
+}
+
+// Writer failing once more than `limit` bytes are written, keeping the first `limit` bytes
+struct LimitedWriter {
+    buf: Vec<u8>,
+    limit: usize,
+}
+
+impl io::Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let available = self.limit - self.buf.len();
+        if buf.len() > available {
+            self.buf.extend_from_slice(&buf[..available]);
+            return Err(io::Error::other("limit exceeded"));
+        }
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Patch;
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn truncated() {
+        let p: Patch = from_value(json!([
+            { "op": "replace", "path": "/a", "value": { "text": "héllo" } },
+            { "op": "test", "path": "/b", "value": [1, 2] },
+        ]))
+        .unwrap();
+
+        assert_eq!(p.display_truncated(10, 1000).to_string(), p.to_string());
+        // Cut within a multibyte character
+        assert_eq!(
+            p.display_truncated(1, 11).to_string(),
+            r#"[{"op":"replace","path":"/a","value":"{\"text\":\"h..."},"... 1 more operations"]"#
+        );
+        assert_eq!(
+            p.display_truncated(0, 0).to_string(),
+            r#"["... 2 more operations"]"#
+        );
+        assert_eq!(
+            format!("{:#}", p.display_truncated(1, 0)),
+            "[\n  {\n    \"op\": \"replace\",\n    \"path\": \"/a\",\n    \"value\": \"...\"\n  },\n  \"... 1 more operations\"\n]"
+        );
+    }
+}
@@ -0,0 +1,503 @@
+use crate::{location_affects, PatchError, PatchOperation};
+use jsonptr::PointerBuf;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::{forward_to_deserialize_any, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::fmt::{self, Display, Formatter};
+use thiserror::Error;
+
+/// Error that can occur in [`patch_struct`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PatchStructError {
+    /// The value could not be serialized into JSON.
+    #[error(transparent)]
+    Serialize(serde_json::Error),
+    /// The patch could not be applied to the serialized value.
+    #[error(transparent)]
+    Patch(#[from] PatchError),
+    /// The patched document could not be deserialized back.
+    #[error(transparent)]
+    InvalidValue(InvalidValueError),
+}
+
+/// Patched document could not be deserialized, see [`PatchStructError::InvalidValue`].
+#[derive(Debug, Error)]
+pub struct InvalidValueError {
+    /// Location of the value which could not be deserialized. For missing fields, this is the
+    /// location of the object missing them.
+    pub path: PointerBuf,
+    /// Indices of the operations which modified the value at `path`, its parents or its
+    /// children, in the order of the patch.
+    pub operations: Vec<usize>,
+    /// Error reported by the deserializer.
+    #[source]
+    pub error: serde_json::Error,
+}
+
+impl Display for InvalidValueError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.operations.last() {
+            Some(operation) => write!(
+                f,
+                "operation '/{}' produced an invalid value at '{}': {}",
+                operation, self.path, self.error
+            ),
+            None => write!(f, "invalid value at '{}': {}", self.path, self.error),
+        }
+    }
+}
+
+/// Patch provided typed value in place with JSON Patch (RFC 6902). The value is serialized into
+/// JSON, patched and deserialized back. If any of the steps fails, `target` is left unchanged.
+///
+/// If the patched document cannot be deserialized, the error tells where the invalid value is and
+/// which operations modified it.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{patch_struct, Patch, PatchStructError};
+/// use serde::{Deserialize, Serialize};
+/// use serde_json::{from_value, json};
+///
+/// #[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// struct Person {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// let mut person = Person { name: "John".into(), age: 42 };
+/// let p: Patch = from_value(json!([
+///   { "op": "replace", "path": "/name", "value": "Jane" },
+///   { "op": "replace", "path": "/age", "value": "old" },
+/// ])).unwrap();
+///
+/// let PatchStructError::InvalidValue(err) = patch_struct(&mut person, &p).unwrap_err() else {
+///     unreachable!()
+/// };
+/// assert_eq!(err.path, "/age");
+/// assert_eq!(err.operations, [1]);
+/// assert_eq!(
+///     err.to_string(),
+///     "operation '/1' produced an invalid value at '/age': invalid type: string \"old\", expected u32"
+/// );
+/// assert_eq!(person, Person { name: "John".into(), age: 42 });
+/// ```
+pub fn patch_struct<T>(target: &mut T, patch: &[PatchOperation]) -> Result<(), PatchStructError>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let mut doc = serde_json::to_value(&*target).map_err(PatchStructError::Serialize)?;
+    crate::patch(&mut doc, patch)?;
+
+    let failed_at = RefCell::new(None);
+    let result = T::deserialize(TrackedValue {
+        value: &doc,
+        path: None,
+        failed_at: &failed_at,
+    });
+    *target = result.map_err(|error| {
+        let path = failed_at.into_inner().unwrap_or_default();
+        let operations = patch
+            .iter()
+            .enumerate()
+            .filter(|(_, op)| {
+                let shifts = !matches!(op, PatchOperation::Replace(_));
+                op.modified_paths()
+                    .any(|location| location_affects(location, shifts, &path))
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        PatchStructError::InvalidValue(InvalidValueError {
+            path,
+            operations,
+            error,
+        })
+    })?;
+    Ok(())
+}
+
+/// Location of a value, as a linked list of tokens from the value up to the root.
+struct Path<'a> {
+    parent: Option<&'a Path<'a>>,
+    token: String,
+}
+
+fn to_pointer(path: Option<&Path<'_>>) -> PointerBuf {
+    let mut tokens = Vec::new();
+    let mut current = path;
+    while let Some(path) = current {
+        tokens.push(path.token.as_str());
+        current = path.parent;
+    }
+    PointerBuf::from_tokens(tokens.into_iter().rev())
+}
+
+/// Deserializer of a [`Value`], same as the one of `serde_json`, which records the location of
+/// the innermost value that failed to deserialize.
+#[derive(Clone, Copy)]
+struct TrackedValue<'a, 'p> {
+    value: &'a Value,
+    path: Option<&'p Path<'p>>,
+    failed_at: &'p RefCell<Option<PointerBuf>>,
+}
+
+impl<'p> TrackedValue<'_, 'p> {
+    // Errors propagate from the innermost value outwards, so only the first location is kept
+    fn track<T>(&self, result: Result<T, serde_json::Error>) -> Result<T, serde_json::Error> {
+        if result.is_err() {
+            let mut failed_at = self.failed_at.borrow_mut();
+            if failed_at.is_none() {
+                *failed_at = Some(to_pointer(self.path));
+            }
+        }
+        result
+    }
+}
+
+impl<'de, 'p> Deserializer<'de> for TrackedValue<'de, 'p> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let result = match self.value {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(v) => visitor.visit_bool(*v),
+            Value::Number(n) => n.deserialize_any(visitor),
+            Value::String(s) => visitor.visit_borrowed_str(s),
+            Value::Array(elements) => visitor.visit_seq(TrackedSeq {
+                elements: elements.iter().enumerate(),
+                parent: self,
+            }),
+            Value::Object(members) => visitor.visit_map(TrackedMap {
+                members: members.iter(),
+                value: None,
+                parent: self,
+            }),
+        };
+        self.track(result)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => self.track(visitor.visit_none()),
+            _ => self.track(visitor.visit_some(self)),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.track(visitor.visit_newtype_struct(self))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let result = match self.value {
+            Value::String(variant) => visitor.visit_enum(TrackedEnum {
+                variant,
+                value: None,
+                parent: self,
+            }),
+            Value::Object(members) if members.len() == 1 => {
+                let (variant, value) = members.iter().next().unwrap();
+                visitor.visit_enum(TrackedEnum {
+                    variant,
+                    value: Some(value),
+                    parent: self,
+                })
+            }
+            _ => Err(de::Error::invalid_type(unexpected(self.value), &"enum")),
+        };
+        self.track(result)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+fn unexpected(value: &Value) -> de::Unexpected<'_> {
+    match value {
+        Value::Null => de::Unexpected::Unit,
+        Value::Bool(v) => de::Unexpected::Bool(*v),
+        Value::Number(_) => de::Unexpected::Other("number"),
+        Value::String(s) => de::Unexpected::Str(s),
+        Value::Array(_) => de::Unexpected::Seq,
+        Value::Object(_) => de::Unexpected::Map,
+    }
+}
+
+fn nested<'de, 'p, T>(
+    parent: TrackedValue<'de, 'p>,
+    value: &'de Value,
+    token: String,
+    seed: T,
+) -> Result<T::Value, serde_json::Error>
+where
+    T: DeserializeSeed<'de>,
+{
+    let path = Path {
+        parent: parent.path,
+        token,
+    };
+    seed.deserialize(TrackedValue {
+        value,
+        path: Some(&path),
+        failed_at: parent.failed_at,
+    })
+}
+
+struct TrackedSeq<'de, 'p, I> {
+    elements: I,
+    parent: TrackedValue<'de, 'p>,
+}
+
+impl<'de, I> SeqAccess<'de> for TrackedSeq<'de, '_, I>
+where
+    I: Iterator<Item = (usize, &'de Value)> + ExactSizeIterator,
+{
+    type Error = serde_json::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        match self.elements.next() {
+            Some((idx, value)) => nested(self.parent, value, idx.to_string(), seed).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
+    }
+}
+
+struct TrackedMap<'de, 'p> {
+    members: serde_json::map::Iter<'de>,
+    value: Option<(&'de String, &'de Value)>,
+    parent: TrackedValue<'de, 'p>,
+}
+
+impl<'de> MapAccess<'de> for TrackedMap<'de, '_> {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.members.next() {
+            Some((key, value)) => {
+                self.value = Some((key, value));
+                let key = de::value::BorrowedStrDeserializer::new(key.as_str());
+                seed.deserialize(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value is missing"))?;
+        nested(self.parent, value, key.clone(), seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.members.len())
+    }
+}
+
+struct TrackedEnum<'de, 'p> {
+    variant: &'de str,
+    value: Option<&'de Value>,
+    parent: TrackedValue<'de, 'p>,
+}
+
+impl<'de, 'p> EnumAccess<'de> for TrackedEnum<'de, 'p> {
+    type Error = serde_json::Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), Self::Error> {
+        let variant = de::value::BorrowedStrDeserializer::new(self.variant);
+        Ok((seed.deserialize(variant)?, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for TrackedEnum<'de, '_> {
+    type Error = serde_json::Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match self.value {
+            None | Some(Value::Null) => Ok(()),
+            Some(value) => Err(de::Error::invalid_type(unexpected(value), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        match self.value {
+            Some(value) => nested(self.parent, value, self.variant.into(), seed),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Some(value) => nested(self.parent, value, self.variant.into(), Seed(visitor)),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Some(value) => nested(self.parent, value, self.variant.into(), Seed(visitor)),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+/// Seed deserializing a value with the given visitor.
+struct Seed<V>(V);
+
+impl<'de, V: Visitor<'de>> DeserializeSeed<'de> for Seed<V> {
+    type Value = V::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<V::Value, D::Error> {
+        deserializer.deserialize_any(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{patch_struct, PatchStructError};
+    use crate::Patch;
+    use serde::{Deserialize, Serialize};
+    use serde_json::{from_value, json};
+    use std::collections::BTreeMap;
+
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+    enum Role {
+        Admin,
+        Member { since: u32 },
+    }
+
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+    struct User {
+        name: String,
+        roles: Vec<Role>,
+        limits: BTreeMap<String, Option<u8>>,
+    }
+
+    fn user() -> User {
+        User {
+            name: "Andrew".into(),
+            roles: vec![Role::Admin, Role::Member { since: 2020 }],
+            limits: BTreeMap::from([("requests".into(), Some(10))]),
+        }
+    }
+
+    fn invalid(p: serde_json::Value) -> (String, Vec<usize>, String) {
+        let p: Patch = from_value(p).unwrap();
+        let mut target = user();
+        let err = patch_struct(&mut target, &p).unwrap_err();
+        assert_eq!(target, user());
+        let PatchStructError::InvalidValue(err) = err else {
+            panic!("unexpected error: {err}")
+        };
+        (err.path.to_string(), err.operations, err.error.to_string())
+    }
+
+    #[test]
+    fn patch() {
+        let mut target = user();
+        let p: Patch = from_value(json!([
+            { "op": "replace", "path": "/roles/1/Member/since", "value": 2021 },
+            { "op": "add", "path": "/limits/storage", "value": null },
+        ]))
+        .unwrap();
+        patch_struct(&mut target, &p).unwrap();
+        assert_eq!(target.roles[1], Role::Member { since: 2021 });
+        assert_eq!(target.limits["storage"], None);
+    }
+
+    #[test]
+    fn invalid_value() {
+        assert_eq!(
+            invalid(json!([
+                { "op": "add", "path": "/roles/0", "value": "Admin" },
+                { "op": "replace", "path": "/roles/2/Member/since", "value": -1 },
+            ])),
+            (
+                "/roles/2/Member/since".into(),
+                vec![0, 1],
+                "invalid value: integer `-1`, expected u32".into()
+            )
+        );
+        assert_eq!(
+            invalid(json!([
+                { "op": "replace", "path": "/limits/requests", "value": 1000 },
+            ])),
+            (
+                "/limits/requests".into(),
+                vec![0],
+                "invalid value: integer `1000`, expected u8".into()
+            )
+        );
+        assert_eq!(
+            invalid(json!([
+                { "op": "remove", "path": "/name" },
+            ])),
+            ("".into(), vec![0], "missing field `name`".into())
+        );
+        assert_eq!(
+            invalid(json!([
+                { "op": "replace", "path": "/roles/0", "value": "Owner" },
+            ]))
+            .0,
+            "/roles/0"
+        );
+
+        let p: Patch = from_value(json!([{ "op": "remove", "path": "/x" }])).unwrap();
+        let err = patch_struct(&mut user(), &p).unwrap_err();
+        assert!(matches!(err, PatchStructError::Patch(_)));
+    }
+}
@@ -0,0 +1,54 @@
+//! WebAssembly bindings, exposing [`patch`](crate::patch), [`diff`](crate::diff) and
+//! [`merge`](crate::merge) to JavaScript via `wasm-bindgen`.
+//!
+//! Documents and patches are passed as plain JavaScript values (objects, arrays and primitives)
+//! and the results are returned the same way:
+//!
+//! ```js
+//! import { patch, diff, merge } from "json-patch";
+//!
+//! const doc = patch({ name: "Andrew" }, [{ op: "add", path: "/happy", value: true }]);
+//! const ops = diff({ name: "Andrew" }, { name: "Maxim" });
+//! const merged = merge({ name: "Andrew", age: 42 }, { age: null });
+//! ```
+use serde::Serialize;
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+fn from_js<T: serde::de::DeserializeOwned>(value: JsValue) -> Result<T, JsError> {
+    Ok(serde_wasm_bindgen::from_value(value)?)
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    // Emit objects as plain JavaScript objects rather than `Map`s
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    Ok(value.serialize(&serializer)?)
+}
+
+/// Apply JSON Patch (RFC 6902) to the document, returning the patched document. Throws if the
+/// patch cannot be parsed or applied; the original document is never modified.
+#[wasm_bindgen]
+pub fn patch(doc: JsValue, patch: JsValue) -> Result<JsValue, JsError> {
+    let mut doc: Value = from_js(doc)?;
+    let patch: crate::Patch = from_js(patch)?;
+    crate::patch(&mut doc, &patch)?;
+    to_js(&doc)
+}
+
+/// Diff two documents, returning JSON Patch (RFC 6902) as an array of operations.
+#[cfg(feature = "diff")]
+#[wasm_bindgen]
+pub fn diff(left: JsValue, right: JsValue) -> Result<JsValue, JsError> {
+    let left: Value = from_js(left)?;
+    let right: Value = from_js(right)?;
+    to_js(&crate::diff(&left, &right))
+}
+
+/// Apply JSON Merge Patch (RFC 7396) to the document, returning the merged document.
+#[wasm_bindgen]
+pub fn merge(doc: JsValue, patch: JsValue) -> Result<JsValue, JsError> {
+    let mut doc: Value = from_js(doc)?;
+    let patch: Value = from_js(patch)?;
+    crate::merge(&mut doc, &patch);
+    to_js(&doc)
+}
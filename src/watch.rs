@@ -0,0 +1,120 @@
+use crate::{patch_affects, PatchOperation};
+use jsonptr::{Pointer, PointerBuf};
+
+/// Registry of JSON Pointer prefixes, each with a key identifying the watcher, which finds the
+/// prefixes affected by a patch.
+///
+/// A prefix is affected if the patch could change the value at or under it, using the same
+/// conservative rules as [`Patch::affects`](crate::Patch::affects): modifications of the value
+/// itself, of its descendants or of its ancestors, and insertions or removals of array elements
+/// which shift the watched one.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{Patch, Watchers};
+/// use jsonptr::Pointer;
+/// use serde_json::{from_value, json};
+///
+/// let mut watchers = Watchers::new();
+/// watchers.watch(Pointer::from_static("/users/0/name").to_buf(), "first user name");
+/// watchers.watch(Pointer::from_static("/users/2").to_buf(), "third user");
+/// watchers.watch(Pointer::from_static("/settings").to_buf(), "settings");
+///
+/// let p: Patch = from_value(json!([
+///   { "op": "remove", "path": "/users/1" },
+/// ])).unwrap();
+///
+/// let affected: Vec<_> = watchers.affected(&p).map(|(_, key)| *key).collect();
+/// assert_eq!(affected, ["third user"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Watchers<K> {
+    entries: Vec<(PointerBuf, K)>,
+}
+
+impl<K> Default for Watchers<K> {
+    fn default() -> Self {
+        Watchers {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<K> Watchers<K> {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the prefix with the given key. The same prefix can be registered with several
+    /// keys, and the same key with several prefixes.
+    pub fn watch(&mut self, prefix: impl Into<PointerBuf>, key: K) {
+        self.entries.push((prefix.into(), key));
+    }
+
+    /// Remove all prefixes registered with the given key. Returns `true` if any were registered.
+    pub fn unwatch(&mut self, key: &K) -> bool
+    where
+        K: PartialEq,
+    {
+        let len = self.entries.len();
+        self.entries.retain(|(_, registered)| registered != key);
+        self.entries.len() != len
+    }
+
+    /// Returns the number of registered prefixes.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no prefixes are registered.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the registered prefixes affected by the patch, with their keys, in registration
+    /// order.
+    pub fn affected<'a>(
+        &'a self,
+        patch: &'a [PatchOperation],
+    ) -> impl Iterator<Item = (&'a Pointer, &'a K)> + 'a {
+        self.entries
+            .iter()
+            .filter(move |(prefix, _)| patch_affects(patch, prefix))
+            .map(|(prefix, key)| (prefix.as_ptr(), key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Watchers;
+    use crate::Patch;
+    use jsonptr::PointerBuf;
+    use serde_json::{from_value, json};
+
+    #[test]
+    fn affected() {
+        let mut watchers = Watchers::new();
+        for (idx, prefix) in ["", "/a", "/a/b/c", "/list/1", "/list/3/x", "/other"]
+            .into_iter()
+            .enumerate()
+        {
+            watchers.watch(PointerBuf::parse(prefix).unwrap(), idx);
+        }
+
+        let p: Patch = from_value(json!([
+            { "op": "replace", "path": "/a/b", "value": {} },
+            { "op": "add", "path": "/list/2", "value": 0 },
+        ]))
+        .unwrap();
+        let affected: Vec<usize> = watchers.affected(&p).map(|(_, idx)| *idx).collect();
+        assert_eq!(affected, [0, 1, 2, 4]);
+
+        assert!(watchers.unwatch(&4));
+        assert!(!watchers.unwatch(&4));
+        assert_eq!(watchers.len(), 5);
+        let affected: Vec<usize> = watchers.affected(&p).map(|(_, idx)| *idx).collect();
+        assert_eq!(affected, [0, 1, 2]);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{Patch, PatchOperation};
+use std::io;
+
+impl Patch {
+    /// Serializes this patch as compact JSON into the given writer, without building the JSON
+    /// string in memory. Same as [`serde_json::to_writer`].
+    pub fn write_to<W: io::Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+        let mut writer = PatchWriter::new(writer);
+        for op in self.iter() {
+            writer.write(op)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Writer serializing patch operations as compact JSON one at a time, as they are produced. The
+/// output is a JSON array of operations, same as the serialized [`Patch`].
+///
+/// The opening bracket is written with the first operation, and the closing one by
+/// [`PatchWriter::finish`], which must be called to complete the output.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{PatchOperation, PatchWriter};
+/// use jsonptr::PointerBuf;
+///
+/// let mut writer = PatchWriter::new(Vec::new());
+/// for idx in 0..2 {
+///     let op = PatchOperation::add(PointerBuf::from_tokens(["items", "-"]), idx);
+///     writer.write(&op).unwrap();
+/// }
+/// assert_eq!(writer.count(), 2);
+///
+/// let buf = writer.finish().unwrap();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     r#"[{"op":"add","path":"/items/-","value":0},{"op":"add","path":"/items/-","value":1}]"#
+/// );
+/// ```
+#[derive(Debug)]
+pub struct PatchWriter<W: io::Write> {
+    writer: W,
+    count: usize,
+}
+
+impl<W: io::Write> PatchWriter<W> {
+    /// Creates a writer writing the patch into the given writer.
+    pub fn new(writer: W) -> Self {
+        PatchWriter { writer, count: 0 }
+    }
+
+    /// Serializes the operation into the underlying writer.
+    pub fn write(&mut self, op: &PatchOperation) -> Result<(), serde_json::Error> {
+        let separator: &[u8] = if self.count == 0 { b"[" } else { b"," };
+        self.writer
+            .write_all(separator)
+            .map_err(serde_json::Error::io)?;
+        serde_json::to_writer(&mut self.writer, op)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Returns the number of operations written so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Completes the JSON array of operations, flushes the underlying writer and returns it.
+    pub fn finish(mut self) -> Result<W, serde_json::Error> {
+        let end: &[u8] = if self.count == 0 { b"[]" } else { b"]" };
+        self.writer.write_all(end).map_err(serde_json::Error::io)?;
+        self.writer.flush().map_err(serde_json::Error::io)?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PatchWriter;
+    use crate::Patch;
+    use serde_json::{from_slice, from_value, json};
+
+    #[test]
+    fn write_to() {
+        let p: Patch = from_value(json!([
+            { "op": "remove", "path": "/a" },
+            { "op": "copy", "from": "/b", "path": "/c" },
+            { "op": "test", "path": "/c", "value": { "x": [1, null] } },
+        ]))
+        .unwrap();
+
+        let mut buf = Vec::new();
+        p.write_to(&mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), p.to_string());
+
+        let buf = PatchWriter::new(Vec::new()).finish().unwrap();
+        assert_eq!(from_slice::<Patch>(&buf).unwrap(), Patch::default());
+    }
+}
@@ -0,0 +1,265 @@
+//! JSON Patch and JSON Merge Patch for YAML documents.
+//!
+//! Documents are patched as [`serde_yaml::Value`] directly, without a conversion to JSON, so the
+//! order of mapping keys and tags of the values not touched by the patch are preserved. Tagged
+//! values are transparent to JSON Pointers: `/a/b` refers to the member `b` of `!Tag { b: 1 }`.
+//! Mapping keys which are numbers or booleans are matched by their string representation.
+//!
+//! # Example
+//!
+//! ```rust
+//! use json_patch::{patch_yaml, Patch};
+//! use serde_json::{from_value, json};
+//!
+//! let mut doc: serde_yaml::Value = serde_yaml::from_str("
+//! image:
+//!   repository: nginx
+//!   tag: !Ref version
+//! replicas: 1
+//! ").unwrap();
+//!
+//! let p: Patch = from_value(json!([
+//!   { "op": "replace", "path": "/replicas", "value": 3 },
+//! ])).unwrap();
+//! patch_yaml(&mut doc, &p).unwrap();
+//!
+//! assert_eq!(serde_yaml::to_string(&doc).unwrap(), "\
+//! image:
+//!   repository: nginx
+//!   tag: !Ref version
+//! replicas: 3
+//! ");
+//! ```
+#[cfg(feature = "diff")]
+use crate::document::Node;
+use crate::{document::Document, parse_index, PatchError, PatchErrorKind, PatchOperation};
+use jsonptr::Token;
+use serde_yaml::{Mapping, Value};
+
+/// Patch provided YAML document in place, same as [`crate::patch`]. If any of the operations
+/// fails, the document is left unchanged.
+pub fn patch_yaml(doc: &mut Value, patch: &[PatchOperation]) -> Result<(), PatchError> {
+    crate::document::patch(doc, patch)
+}
+
+/// Patch provided YAML document in place with JSON Merge Patch (RFC 7396) given as YAML, same
+/// as [`crate::merge`]. Mapping keys are kept in their original order and new keys are added to
+/// the end.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::merge_yaml;
+///
+/// let mut doc: serde_yaml::Value = serde_yaml::from_str("{ a: 1, b: { c: 2, d: 3 } }").unwrap();
+/// let patch: serde_yaml::Value = serde_yaml::from_str("{ b: { c: null, e: 4 } }").unwrap();
+/// merge_yaml(&mut doc, &patch);
+/// assert_eq!(doc, serde_yaml::from_str::<serde_yaml::Value>("{ a: 1, b: { d: 3, e: 4 } }").unwrap());
+/// ```
+pub fn merge_yaml(doc: &mut Value, patch: &Value) {
+    let Value::Mapping(patch) = patch else {
+        *doc = patch.clone();
+        return;
+    };
+
+    if !doc.is_mapping() {
+        *doc = Value::Mapping(Mapping::new());
+    }
+    let Value::Mapping(map) = doc else {
+        unreachable!()
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            map.shift_remove(key);
+        } else {
+            merge_yaml(map.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// Diff two YAML documents and generate a JSON Patch (RFC 6902), same as [`crate::diff`].
+///
+/// Values added by the patch are converted to JSON, so their tags are represented as
+/// single-member objects, like `{ "!Tag": value }`. Returns an error if a value cannot be
+/// represented as JSON, for example, a mapping with a key which is itself a mapping.
+///
+/// # Example
+///
+/// ```rust
+/// use json_patch::{diff_yaml, Patch};
+/// use serde_json::{from_value, json};
+///
+/// let left: serde_yaml::Value = serde_yaml::from_str("{ a: 1, b: [x, y] }").unwrap();
+/// let right: serde_yaml::Value = serde_yaml::from_str("{ a: 2, b: [x] }").unwrap();
+/// assert_eq!(diff_yaml(&left, &right).unwrap(), from_value::<Patch>(json!([
+///   { "op": "replace", "path": "/a", "value": 2 },
+///   { "op": "remove", "path": "/b/1" },
+/// ])).unwrap());
+/// ```
+#[cfg(feature = "diff")]
+pub fn diff_yaml(left: &Value, right: &Value) -> Result<crate::Patch, serde_json::Error> {
+    crate::document::diff(left, right)
+}
+
+fn key_str(key: &Value) -> Option<String> {
+    match key {
+        Value::String(key) => Some(key.clone()),
+        Value::Number(key) => Some(key.to_string()),
+        Value::Bool(key) => Some(key.to_string()),
+        _ => None,
+    }
+}
+
+fn find_key<'a>(map: &'a Mapping, token: &Token) -> Option<&'a Value> {
+    let token = token.decoded();
+    map.keys()
+        .find(|key| key_str(key).is_some_and(|key| key == token))
+}
+
+impl Document for Value {
+    fn from_json(value: &serde_json::Value) -> Result<Self, PatchErrorKind> {
+        Ok(serde_yaml::to_value(value).expect("JSON is always representable as YAML"))
+    }
+
+    fn child(&self, token: &Token) -> Option<&Self> {
+        match self {
+            Value::Mapping(map) => map.get(find_key(map, token)?),
+            Value::Sequence(seq) => seq.get(parse_index(token.encoded(), seq.len()).ok()?),
+            Value::Tagged(tagged) => tagged.value.child(token),
+            _ => None,
+        }
+    }
+
+    fn child_mut(&mut self, token: &Token) -> Option<&mut Self> {
+        match self {
+            Value::Mapping(map) => {
+                let key = find_key(map, token)?.clone();
+                map.get_mut(key)
+            }
+            Value::Sequence(seq) => {
+                let idx = parse_index(token.encoded(), seq.len()).ok()?;
+                seq.get_mut(idx)
+            }
+            Value::Tagged(tagged) => tagged.value.child_mut(token),
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, token: &Token, value: Self) -> Result<(), PatchErrorKind> {
+        match self {
+            Value::Mapping(map) => {
+                let key = match find_key(map, token) {
+                    Some(key) => key.clone(),
+                    None => Value::String(token.decoded().into_owned()),
+                };
+                map.insert(key, value);
+            }
+            Value::Sequence(seq) if token.encoded() == "-" => seq.push(value),
+            Value::Sequence(seq) => {
+                let idx = parse_index(token.encoded(), seq.len() + 1)?;
+                seq.insert(idx, value);
+            }
+            Value::Tagged(tagged) => return tagged.value.insert(token, value),
+            _ => return Err(PatchErrorKind::InvalidPointer),
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, token: &Token) -> Option<Self> {
+        match self {
+            Value::Mapping(map) => {
+                let key = find_key(map, token)?.clone();
+                map.shift_remove(key)
+            }
+            Value::Sequence(seq) => {
+                let idx = parse_index(token.encoded(), seq.len()).ok()?;
+                Some(seq.remove(idx))
+            }
+            Value::Tagged(tagged) => tagged.value.remove(token),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "diff")]
+    type JsonError = serde_json::Error;
+
+    #[cfg(feature = "diff")]
+    fn to_json(&self) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(self)
+    }
+
+    #[cfg(feature = "diff")]
+    fn node(&self) -> Node<'_, Self> {
+        match self {
+            Value::Mapping(map) => map
+                .iter()
+                .map(|(key, value)| Some((key_str(key)?, value)))
+                .collect::<Option<_>>()
+                .map_or(Node::Other, Node::Object),
+            Value::Sequence(seq) => Node::Array(seq),
+            Value::Tagged(tagged) => Node::Tagged(tagged.tag.to_string(), &tagged.value),
+            _ => Node::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Patch;
+    use serde_json::{from_value, json};
+    use serde_yaml::Value;
+
+    fn yaml(s: &str) -> Value {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn patch_yaml() {
+        let mut doc = yaml("{ b: 1, a: !Tag { x: [1, 2] }, 3: three, true: yes }");
+        let p: Patch = from_value(json!([
+            { "op": "add", "path": "/a/x/1", "value": 5 },
+            { "op": "move", "from": "/b", "path": "/c" },
+            { "op": "copy", "from": "/3", "path": "/d" },
+            { "op": "test", "path": "/true", "value": "yes" },
+            { "op": "remove", "path": "/a/x/0" },
+        ]))
+        .unwrap();
+        super::patch_yaml(&mut doc, &p).unwrap();
+        assert_eq!(
+            doc,
+            yaml("{ a: !Tag { x: [5, 2] }, 3: three, true: yes, c: 1, d: three }")
+        );
+
+        let p: Patch = from_value(json!([
+            { "op": "replace", "path": "/c", "value": 2 },
+            { "op": "test", "path": "/a/x", "value": [5] },
+        ]))
+        .unwrap();
+        let err = super::patch_yaml(&mut doc, &p).unwrap_err();
+        assert_eq!(err.operation, 1);
+        assert!(matches!(err.kind, crate::PatchErrorKind::TestFailed));
+        assert_eq!(doc["c"], Value::from(1));
+
+        let p: Patch = from_value(json!([{ "op": "add", "path": "/a/x/9", "value": 1 }])).unwrap();
+        let err = super::patch_yaml(&mut doc, &p).unwrap_err();
+        assert!(matches!(err.kind, crate::PatchErrorKind::InvalidPointer));
+    }
+
+    #[test]
+    #[cfg(feature = "diff")]
+    fn diff_yaml() {
+        let left = yaml("{ a: !Tag { x: [1, 2] }, b: 1, c: { d: 1 }, 4: four }");
+        let right = yaml("{ a: !Tag { x: [1, 3, 4] }, c: { d: 1 }, 4: { five: 5 }, e: !Other 1 }");
+        let p = super::diff_yaml(&left, &right).unwrap();
+
+        let mut patched = left.clone();
+        super::patch_yaml(&mut patched, &p).unwrap();
+        assert_eq!(
+            patched,
+            yaml("{ a: !Tag { x: [1, 3, 4] }, c: { d: 1 }, 4: { five: 5 }, e: { '!Other': 1 } }")
+        );
+
+        let right = yaml("{ a: { [1]: 2 } }");
+        assert!(super::diff_yaml(&left, &right).is_err());
+    }
+}
@@ -1,7 +1,8 @@
 use json_patch::{
-    AddOperation, CopyOperation, MoveOperation, Patch, PatchOperation, RemoveOperation,
-    ReplaceOperation, TestOperation,
+    AddOperation, CopyOperation, MergePatch, MoveOperation, OpKind, Patch, PatchOperation,
+    RemoveOperation, ReplaceOperation, TestOperation,
 };
+use jsonptr::{Pointer, PointerBuf};
 use serde_json::{from_str, from_value, json, Value};
 
 #[test]
@@ -47,6 +48,28 @@ fn parse_from_string() {
     );
 }
 
+#[test]
+fn patch_conversions() {
+    use json_patch::ParsePatchError;
+
+    let s = r#"[{"op": "add", "path": "/a/b", "value": 1}, {"op": "remove", "path": "/c"}]"#;
+    let expected = Patch::from(vec![
+        PatchOperation::add("/a/b".parse::<PointerBuf>().unwrap(), 1),
+        PatchOperation::remove("/c".parse::<PointerBuf>().unwrap()),
+    ]);
+    assert_eq!(s.parse::<Patch>().unwrap(), expected);
+    assert_eq!(Patch::try_from(s).unwrap(), expected);
+    assert_eq!(
+        Patch::try_from(from_str::<Value>(s).unwrap()).unwrap(),
+        expected
+    );
+
+    let err = Patch::try_from(json!({ "op": "add" })).unwrap_err();
+    assert!(matches!(err, ParsePatchError::InvalidBody(_)));
+    assert!(err.to_string().starts_with("invalid patch document: "));
+    assert!("[".parse::<Patch>().is_err());
+}
+
 #[test]
 fn serialize_patch() {
     let s = r#"[{"op":"add","path":"/a/b","value":1},{"op":"remove","path":"/c"}]"#;
@@ -220,3 +243,937 @@ fn display_patch_operation_default() {
     let op = PatchOperation::default();
     assert_eq!(op.to_string(), r#"{"op":"test","path":"","value":null}"#);
 }
+
+#[test]
+fn patch_with_prefix() {
+    let patch: Patch = from_value(json!([
+        { "op": "add", "path": "/a~1b", "value": 1 },
+        { "op": "move", "from": "/c", "path": "" },
+    ]))
+    .unwrap();
+
+    let prefixed = patch.with_prefix(Pointer::from_static("/x~0y/0"));
+    assert_eq!(
+        prefixed,
+        from_value(json!([
+            { "op": "add", "path": "/x~0y/0/a~1b", "value": 1 },
+            { "op": "move", "from": "/x~0y/0/c", "path": "/x~0y/0" },
+        ]))
+        .unwrap()
+    );
+    assert_eq!(
+        prefixed.strip_prefix(Pointer::from_static("/x~0y/0")),
+        Some(patch)
+    );
+}
+
+#[test]
+fn patch_strip_prefix_token_boundary() {
+    let patch: Patch = from_value(json!([
+        { "op": "remove", "path": "/ab/c" },
+    ]))
+    .unwrap();
+
+    assert_eq!(patch.strip_prefix(Pointer::from_static("/a")), None);
+    assert_eq!(
+        patch.strip_prefix(Pointer::from_static("/ab")),
+        Some(from_value(json!([{ "op": "remove", "path": "/c" }])).unwrap())
+    );
+}
+
+#[test]
+fn patch_iterators() {
+    let patch: Patch = from_value(json!([
+        { "op": "add", "path": "/a", "value": 1 },
+        { "op": "remove", "path": "/b" },
+        { "op": "test", "path": "/c", "value": 2 },
+    ]))
+    .unwrap();
+
+    let paths: Vec<&str> = (&patch).into_iter().map(|op| op.path().as_str()).collect();
+    assert_eq!(paths, ["/a", "/b", "/c"]);
+
+    let mut filtered: Patch = patch
+        .clone()
+        .into_iter()
+        .filter(|op| !matches!(op, PatchOperation::Test(_)))
+        .collect();
+    assert_eq!(filtered.len(), 2);
+
+    filtered.extend(patch.into_iter().skip(2));
+    assert_eq!(
+        filtered,
+        from_value(json!([
+            { "op": "add", "path": "/a", "value": 1 },
+            { "op": "remove", "path": "/b" },
+            { "op": "test", "path": "/c", "value": 2 },
+        ]))
+        .unwrap()
+    );
+}
+
+#[test]
+fn operation_constructors() {
+    let a: PointerBuf = "/a".parse().unwrap();
+    let b: PointerBuf = "/b".parse().unwrap();
+    let patch = Patch(vec![
+        PatchOperation::add(a.clone(), json!({ "x": 1 })),
+        PatchOperation::remove(a.clone()),
+        PatchOperation::replace(a.clone(), "hello"),
+        PatchOperation::mov(a.clone(), b.clone()),
+        PatchOperation::copy(b.clone(), a.clone()),
+        PatchOperation::test(a.clone(), 42),
+        PatchOperation::remove(0),
+    ]);
+
+    assert_eq!(
+        patch,
+        from_value(json!([
+            { "op": "add", "path": "/a", "value": { "x": 1 } },
+            { "op": "remove", "path": "/a" },
+            { "op": "replace", "path": "/a", "value": "hello" },
+            { "op": "move", "from": "/a", "path": "/b" },
+            { "op": "copy", "from": "/b", "path": "/a" },
+            { "op": "test", "path": "/a", "value": 42 },
+            { "op": "remove", "path": "/0" },
+        ]))
+        .unwrap()
+    );
+}
+
+#[test]
+fn patch_constructors() {
+    let a: PointerBuf = "/a".parse().unwrap();
+    let b: PointerBuf = "/b".parse().unwrap();
+    let mut patch = Patch::add(a.clone(), 1);
+    patch.extend(Patch::remove(a.clone()));
+    patch.extend(Patch::replace(a.clone(), "hello"));
+    patch.extend(Patch::mov(a.clone(), b.clone()));
+    patch.extend(Patch::copy(b.clone(), a.clone()));
+    patch.extend(Patch::test(a.clone(), 42));
+    patch.push(PatchOperation::remove(b));
+
+    assert_eq!(
+        patch,
+        from_value(json!([
+            { "op": "add", "path": "/a", "value": 1 },
+            { "op": "remove", "path": "/a" },
+            { "op": "replace", "path": "/a", "value": "hello" },
+            { "op": "move", "from": "/a", "path": "/b" },
+            { "op": "copy", "from": "/b", "path": "/a" },
+            { "op": "test", "path": "/a", "value": 42 },
+            { "op": "remove", "path": "/b" },
+        ]))
+        .unwrap()
+    );
+}
+
+#[test]
+fn operation_accessors() {
+    let patch: Patch = from_value(json!([
+        { "op": "add", "path": "/a", "value": 1 },
+        { "op": "remove", "path": "/a" },
+        { "op": "move", "from": "/b", "path": "/a" },
+        { "op": "test", "path": "/a", "value": "x" },
+    ]))
+    .unwrap();
+
+    let kinds: Vec<OpKind> = patch.iter().map(PatchOperation::kind).collect();
+    assert_eq!(
+        kinds,
+        [OpKind::Add, OpKind::Remove, OpKind::Move, OpKind::Test]
+    );
+    let names: Vec<&str> = patch.iter().map(PatchOperation::op_name).collect();
+    assert_eq!(names, ["add", "remove", "move", "test"]);
+
+    assert_eq!(patch[0].value(), Some(&json!(1)));
+    assert_eq!(patch[1].value(), None);
+    assert_eq!(patch[3].value(), Some(&json!("x")));
+    assert_eq!(patch[0].from(), None);
+    assert_eq!(patch[2].from().map(|p| p.as_str()), Some("/b"));
+    assert_eq!(OpKind::Replace.to_string(), "replace");
+}
+
+#[test]
+fn serialize_patch_error() {
+    let mut doc = json!({ "a": 1 });
+    let patch: Patch = from_value(json!([
+        { "op": "test", "path": "/a", "value": 1 },
+        { "op": "remove", "path": "/b" },
+    ]))
+    .unwrap();
+
+    let err = json_patch::patch(&mut doc, &patch).unwrap_err();
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        json!({ "operation": 1, "op": "remove", "path": "/b", "kind": "InvalidPointer" })
+    );
+}
+
+#[test]
+fn merge_patch_serde() {
+    let patch: MergePatch = from_str(r#"{"a":null,"b":[1]}"#).unwrap();
+    assert_eq!(patch, MergePatch(json!({ "a": null, "b": [1] })));
+    assert_eq!(
+        serde_json::to_string(&patch).unwrap(),
+        r#"{"a":null,"b":[1]}"#
+    );
+    assert_eq!(patch.to_string(), r#"{"a":null,"b":[1]}"#);
+    assert!(patch.is_valid());
+    assert!(!MergePatch::from(json!([1])).is_valid());
+
+    let mut doc = json!({ "a": 1, "c": 2 });
+    patch.apply(&mut doc);
+    assert_eq!(doc, json!({ "c": 2, "b": [1] }));
+}
+
+#[test]
+fn any_patch() {
+    use json_patch::{AnyPatch, ParsePatchError};
+
+    assert!(AnyPatch::is_supported("application/json-patch+json"));
+    assert!(AnyPatch::is_supported(
+        "application/merge-patch+json; charset=utf-8"
+    ));
+    assert!(!AnyPatch::is_supported("application/json"));
+
+    let p = AnyPatch::from_slice("application/json-patch+json", br#"[]"#).unwrap();
+    assert_eq!(p, AnyPatch::Patch(Patch(vec![])));
+
+    let p = AnyPatch::from_slice("application/merge-patch+json", br#"[]"#).unwrap();
+    assert_eq!(p, AnyPatch::Merge(MergePatch(json!([]))));
+
+    let err = AnyPatch::from_slice("application/json", br#"{}"#).unwrap_err();
+    assert!(matches!(err, ParsePatchError::UnsupportedMediaType(ref t) if t == "application/json"));
+    assert_eq!(err.to_string(), "unsupported media type 'application/json'");
+
+    let err = AnyPatch::from_slice("application/json-patch+json", br#"{}"#).unwrap_err();
+    assert!(matches!(err, ParsePatchError::InvalidBody(_)));
+
+    let p = AnyPatch::from_slice(
+        "application/json-patch+json",
+        br#"[{ "op": "test", "path": "/a", "value": 2 }]"#,
+    )
+    .unwrap();
+    let mut doc = json!({ "a": 1 });
+    assert!(p.apply(&mut doc).is_err());
+    AnyPatch::from(MergePatch(json!({ "a": 2 })))
+        .apply(&mut doc)
+        .unwrap();
+    assert!(p.apply(&mut doc).is_ok());
+}
+
+#[test]
+fn operation_ordering() {
+    let ops: Vec<PatchOperation> = from_value(json!([
+        { "op": "test", "path": "/b", "value": { "y": 1, "x": 2 } },
+        { "op": "test", "path": "/b", "value": { "x": 2, "y": 0 } },
+        { "op": "remove", "path": "/b" },
+        { "op": "add", "path": "/b", "value": 1.0 },
+        { "op": "add", "path": "/b", "value": 1 },
+        { "op": "add", "path": "/b", "value": null },
+        { "op": "add", "path": "/a", "value": "z" },
+    ]))
+    .unwrap();
+
+    let sorted: std::collections::BTreeSet<_> = ops.iter().cloned().collect();
+    let sorted: Patch = sorted.into_iter().collect();
+    assert_eq!(
+        sorted,
+        from_value(json!([
+            { "op": "add", "path": "/a", "value": "z" },
+            { "op": "add", "path": "/b", "value": null },
+            { "op": "add", "path": "/b", "value": 1 },
+            { "op": "add", "path": "/b", "value": 1.0 },
+            { "op": "remove", "path": "/b" },
+            { "op": "test", "path": "/b", "value": { "x": 2, "y": 0 } },
+            { "op": "test", "path": "/b", "value": { "x": 2, "y": 1 } },
+        ]))
+        .unwrap()
+    );
+}
+
+#[test]
+fn operation_hash() {
+    let ops: Vec<PatchOperation> = from_value(json!([
+        { "op": "add", "path": "/a", "value": [1, 2] },
+        { "op": "remove", "path": "/a" },
+        { "op": "add", "path": "/a", "value": [1, 2] },
+    ]))
+    .unwrap();
+
+    let unique: std::collections::HashSet<_> = ops.into_iter().collect();
+    assert_eq!(unique.len(), 2);
+}
+
+#[test]
+fn patch_estimated_size() {
+    assert_eq!(Patch::default().estimated_size(), 2);
+
+    let patch: Patch = from_value(json!([
+        { "op": "add", "path": "/a~1b", "value": { "text": "Привет, \"мир\"\n" } },
+        { "op": "move", "from": "/c", "path": "/d" },
+        { "op": "test", "path": "", "value": [1.5, null, -3] },
+    ]))
+    .unwrap();
+    assert_eq!(patch.estimated_size(), patch.to_string().len());
+    for op in patch.iter() {
+        assert_eq!(op.estimated_size(), serde_json::to_vec(op).unwrap().len());
+    }
+}
+
+#[test]
+fn patch_affects() {
+    let patch: Patch = from_value(json!([
+        { "op": "test", "path": "/tested", "value": 1 },
+        { "op": "add", "path": "/list/2", "value": 1 },
+        { "op": "replace", "path": "/items/3", "value": 1 },
+        { "op": "move", "from": "/src/a", "path": "/dst/a" },
+        { "op": "copy", "from": "/orig", "path": "/tail/-" },
+    ]))
+    .unwrap();
+
+    let affects = |ptr: &str| patch.affects(&PointerBuf::parse(ptr).unwrap());
+
+    // Ancestors and descendants
+    assert!(affects(""));
+    assert!(affects("/list"));
+    assert!(affects("/items/3/name"));
+    // Array index shifts
+    assert!(affects("/list/2"));
+    assert!(affects("/list/10/name"));
+    assert!(!affects("/list/1"));
+    assert!(!affects("/items/4"));
+    assert!(affects("/tail/0"));
+    assert!(!affects("/tail/x"));
+    // Move source and target
+    assert!(affects("/src/a/b"));
+    assert!(affects("/dst"));
+    assert!(!affects("/src/ab"));
+    // Tests and copy sources do not modify anything
+    assert!(!affects("/tested"));
+    assert!(!affects("/orig"));
+
+    let paths: Vec<&str> = patch.affected_paths().map(|p| p.as_str()).collect();
+    assert_eq!(
+        paths,
+        ["/list/2", "/items/3", "/src/a", "/dst/a", "/tail/-"]
+    );
+}
+
+#[test]
+fn patch_into_merge_patch() {
+    let patch: Patch = from_value(json!([
+        { "op": "add", "path": "/a/b/c", "value": 1 },
+        { "op": "add", "path": "/a/b/d", "value": [null] },
+        { "op": "remove", "path": "/a/b/x" },
+        { "op": "remove", "path": "/e~1f" },
+        { "op": "add", "path": "/e~1f", "value": "g" },
+    ]))
+    .unwrap();
+    let merge_patch = patch.try_into_merge_patch().unwrap();
+    assert_eq!(
+        merge_patch,
+        MergePatch(json!({
+            "a": { "b": { "c": 1, "d": [null], "x": null } },
+            "e/f": "g",
+        }))
+    );
+
+    let doc = json!({ "a": { "b": { "c": 0, "x": 1 } }, "e/f": "h" });
+    let mut patched = doc.clone();
+    json_patch::patch(&mut patched, &patch).unwrap();
+    let mut merged = doc.clone();
+    json_patch::merge(&mut merged, &merge_patch.0);
+    assert_eq!(patched, merged);
+
+    for unsupported in [
+        json!([{ "op": "add", "path": "/a/0", "value": 1 }]),
+        json!([{ "op": "add", "path": "/a/-", "value": 1 }]),
+        json!([{ "op": "add", "path": "/a", "value": null }]),
+        json!([{ "op": "add", "path": "/a", "value": { "b": [null] } }]),
+        json!([{ "op": "replace", "path": "/a", "value": {} }]),
+        json!([{ "op": "replace", "path": "", "value": 1 }]),
+        json!([{ "op": "test", "path": "/a", "value": 1 }]),
+        json!([{ "op": "move", "from": "/a", "path": "/b" }]),
+        json!([
+            { "op": "remove", "path": "/a" },
+            { "op": "add", "path": "/a/b", "value": 1 },
+        ]),
+    ] {
+        let patch: Patch = from_value(unsupported).unwrap();
+        assert_eq!(patch.try_into_merge_patch(), None, "{}", patch);
+    }
+}
+
+#[test]
+fn merge_patch_into_patch() {
+    let doc = json!({
+        "a": { "b": 1, "c": [1, 2] },
+        "d": "e",
+        "f": 1,
+    });
+    let merge = json!({
+        "a": { "b": null, "c": [3], "x": { "y": null, "z": 1 } },
+        "d": { "n": null },
+        "f": 1,
+        "missing": null,
+    });
+
+    let patch = json_patch::merge_patch_to_patch(&doc, &merge);
+    let mut patched = doc.clone();
+    json_patch::patch(&mut patched, &patch).unwrap();
+
+    let mut merged = doc.clone();
+    json_patch::merge(&mut merged, &merge);
+    assert_eq!(patched, merged);
+    assert_eq!(
+        patch,
+        from_value(json!([
+            { "op": "remove", "path": "/a/b" },
+            { "op": "replace", "path": "/a/c", "value": [3] },
+            { "op": "add", "path": "/a/x", "value": { "z": 1 } },
+            { "op": "replace", "path": "/d", "value": {} },
+        ]))
+        .unwrap()
+    );
+}
+
+#[test]
+fn patch_validate() {
+    let valid: Patch = from_value(json!([
+        { "op": "add", "path": "/a/-", "value": null },
+        { "op": "move", "from": "/a/0", "path": "/b/-" },
+        { "op": "move", "from": "/a", "path": "/a" },
+        { "op": "move", "from": "/a", "path": "/ab" },
+        { "op": "copy", "from": "/a", "path": "/a/b" },
+        { "op": "replace", "path": "", "value": 1 },
+    ]))
+    .unwrap();
+    assert!(valid.validate().is_ok());
+
+    let invalid: Patch = from_value(json!([
+        { "op": "add", "path": "/a/-/b", "value": 1 },
+        { "op": "remove", "path": "" },
+        { "op": "replace", "path": "/a/-", "value": 1 },
+        { "op": "test", "path": "/-/a", "value": 1 },
+        { "op": "move", "from": "", "path": "/a" },
+        { "op": "copy", "from": "/a/-", "path": "/b" },
+        { "op": "move", "from": "/a", "path": "/a/b/c" },
+        { "op": "test", "path": "/a", "value": 1 },
+    ]))
+    .unwrap();
+    let errors: Vec<String> = invalid
+        .validate()
+        .unwrap_err()
+        .iter()
+        .map(|err| err.to_string())
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            "operation '/0' failed at path '/a/-/b': path is invalid",
+            "operation '/1' failed at path '': path is invalid",
+            "operation '/2' failed at path '/a/-': path is invalid",
+            "operation '/3' failed at path '/-/a': path is invalid",
+            "operation '/4' failed at path '/a': \"from\" path is invalid",
+            "operation '/5' failed at path '/b': \"from\" path is invalid",
+            "operation '/6' failed at path '/a/b/c': cannot move the value inside itself",
+        ]
+    );
+}
+
+#[test]
+fn patch_canonical_string() {
+    let first: Patch = from_value(json!([
+        { "op": "move", "path": "/b", "from": "/a" },
+        { "op": "test", "path": "/b", "value": { "z": 1, "y": { "k": [3, 2.5], "j": "s" } } },
+    ]))
+    .unwrap();
+    let second: Patch = from_str(
+        r#"[
+            {"from": "/a", "op": "move", "path": "/b"},
+            {"value": {"y": {"j": "s", "k": [3, 2.5]}, "z": 1}, "path": "/b", "op": "test"}
+        ]"#,
+    )
+    .unwrap();
+
+    assert_eq!(first.to_canonical_string(), second.to_canonical_string());
+    assert_eq!(
+        first.to_canonical_string(),
+        r#"[{"from":"/a","op":"move","path":"/b"},{"op":"test","path":"/b","value":{"y":{"j":"s","k":[3,2.5]},"z":1}}]"#
+    );
+}
+
+#[test]
+fn merge_typed_struct() {
+    #[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+    struct Item {
+        id: u32,
+        tags: Vec<String>,
+    }
+
+    let mut item = Item {
+        id: 1,
+        tags: vec!["a".into()],
+    };
+    json_patch::merge_struct(&mut item, &json!({ "tags": ["b", "c"] })).unwrap();
+    assert_eq!(item.tags, ["b", "c"]);
+
+    // Failing to deserialize the result leaves the target unchanged
+    json_patch::merge_struct(&mut item, &json!({ "id": null })).unwrap_err();
+    json_patch::merge_struct(&mut item, &json!({ "id": "x", "tags": [] })).unwrap_err();
+    assert_eq!(
+        item,
+        Item {
+            id: 1,
+            tags: vec!["b".into(), "c".into()],
+        }
+    );
+}
+
+#[test]
+fn merge_report() {
+    let mut doc = json!({ "a": { "b": 1, "c": [1] }, "d": "x", "e": 1 });
+    let patch = json!({ "a": { "b": 2, "c": [1], "z": null }, "d": { "f": null }, "e": null, "g": { "h": null } });
+
+    let mut merged = doc.clone();
+    json_patch::merge(&mut merged, &patch);
+
+    let report = json_patch::merge_with_report(&mut doc, &patch);
+    assert_eq!(doc, merged);
+    let ptrs = |ptrs: &[&str]| -> Vec<PointerBuf> {
+        ptrs.iter()
+            .map(|p| PointerBuf::parse(*p).unwrap())
+            .collect()
+    };
+    assert_eq!(report.added, ptrs(&["/g"]));
+    assert_eq!(report.replaced, ptrs(&["/a/b", "/d"]));
+    assert_eq!(report.removed, ptrs(&["/e"]));
+
+    assert!(json_patch::merge_with_report(&mut doc, &patch).is_empty());
+    assert_eq!(
+        json_patch::merge_with_report(&mut doc, &json!(1)).replaced,
+        ptrs(&[""])
+    );
+}
+
+#[test]
+fn merge_depth_limit() {
+    let mut options = json_patch::MergePatchOptions::default();
+    options.max_depth = Some(3);
+
+    let mut deep = json!(1);
+    for _ in 0..100 {
+        deep = json!({ "a": [deep] });
+    }
+    let mut doc = json!({});
+    let err = json_patch::merge_with(&mut doc, &deep, &options).unwrap_err();
+    assert_eq!(err.to_string(), "merge patch exceeds maximum depth of 3");
+    assert_eq!(doc, json!({}));
+
+    json_patch::merge_with(&mut doc, &json!({ "a": [{ "b": 1 }] }), &options).unwrap();
+    json_patch::merge_with(&mut doc, &json!(null), &options).unwrap();
+    assert_eq!(doc, json!(null));
+}
+
+#[test]
+fn merge_null_sentinel() {
+    let mut options = json_patch::MergePatchOptions::default();
+    options.null_sentinel = Some(json!({ "$null": true }));
+
+    let mut doc = json!({ "a": 1, "b": { "c": 2 }, "d": 3 });
+    let patch = json!({ "a": { "$null": true }, "b": { "c": { "$null": true }, "e": [{ "$null": true }] }, "d": null });
+    json_patch::merge_with(&mut doc, &patch, &options).unwrap();
+    assert_eq!(
+        doc,
+        json!({ "a": null, "b": { "c": null, "e": [{ "$null": true }] } })
+    );
+
+    // Top-level patch is not a member value, so it is merged as usual
+    let mut doc = json!({ "a": 1 });
+    json_patch::merge_with(&mut doc, &json!({ "$null": true }), &options).unwrap();
+    assert_eq!(doc, json!({ "a": 1, "$null": true }));
+}
+
+#[test]
+fn merge_key_position() {
+    let merged = |position| {
+        let mut options = json_patch::MergePatchOptions::default();
+        options.key_position = position;
+        let mut doc = json!({ "b": 1, "d": { "x": 1 }, "f": 1 });
+        let patch = json!({ "g": 1, "c": 1, "a": 1, "d": { "w": 1 } });
+        json_patch::merge_with(&mut doc, &patch, &options).unwrap();
+        doc.to_string()
+    };
+
+    use json_patch::KeyPosition;
+    assert_eq!(
+        merged(KeyPosition::Back),
+        r#"{"b":1,"d":{"x":1,"w":1},"f":1,"g":1,"c":1,"a":1}"#
+    );
+    assert_eq!(
+        merged(KeyPosition::Front),
+        r#"{"g":1,"c":1,"a":1,"b":1,"d":{"w":1,"x":1},"f":1}"#
+    );
+    assert_eq!(
+        merged(KeyPosition::Sorted),
+        r#"{"a":1,"b":1,"c":1,"d":{"w":1,"x":1},"f":1,"g":1}"#
+    );
+}
+
+#[cfg(feature = "preserve_order")]
+#[test]
+fn preserve_member_order() {
+    let mut doc = json!({ "c": 1, "a": 2, "d": 3, "b": 4 });
+    let patch: Patch = from_value(json!([
+        { "op": "remove", "path": "/a" },
+        { "op": "replace", "path": "/d", "value": 5 },
+        { "op": "add", "path": "/e", "value": 6 },
+    ]))
+    .unwrap();
+    json_patch::patch(&mut doc, &patch).unwrap();
+    assert_eq!(doc.to_string(), r#"{"c":1,"d":5,"b":4,"e":6}"#);
+
+    json_patch::merge(&mut doc, &json!({ "c": null, "a": 7 }));
+    assert_eq!(doc.to_string(), r#"{"d":5,"b":4,"e":6,"a":7}"#);
+
+    // Members restored by a failed patch are placed at the end
+    let patch: Patch = from_value(json!([
+        { "op": "remove", "path": "/d" },
+        { "op": "test", "path": "/b", "value": 0 },
+    ]))
+    .unwrap();
+    json_patch::patch(&mut doc, &patch).unwrap_err();
+    assert_eq!(doc.to_string(), r#"{"b":4,"e":6,"a":7,"d":5}"#);
+}
+
+#[test]
+fn invert_patch() {
+    let doc = json!({ "a": [1, 2], "b": { "c": 3 }, "d": 4 });
+    let patch: Patch = from_value(json!([
+        { "op": "add", "path": "/a/-", "value": 5 },
+        { "op": "move", "from": "/b/c", "path": "/d" },
+        { "op": "copy", "from": "/a/0", "path": "/a/-" },
+        { "op": "move", "from": "/d", "path": "/a/-" },
+        { "op": "remove", "path": "/b" },
+        { "op": "test", "path": "/a/0", "value": 1 },
+    ]))
+    .unwrap();
+
+    let inverse = json_patch::invert(&doc, &patch).unwrap();
+    assert!(inverse.validate().is_ok());
+    let mut patched = doc.clone();
+    json_patch::patch(&mut patched, &patch).unwrap();
+    assert_eq!(patched, json!({ "a": [1, 2, 5, 1, 3] }));
+    json_patch::patch(&mut patched, &inverse).unwrap();
+    assert_eq!(patched, doc);
+
+    let patch: Patch = from_value(json!([
+        { "op": "remove", "path": "/d" },
+        { "op": "move", "from": "/x", "path": "/a/-" },
+    ]))
+    .unwrap();
+    let err = json_patch::invert(&doc, &patch).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "operation '/1' failed at path '/a/-': \"from\" path is invalid"
+    );
+}
+
+#[test]
+fn patch_rollback_strategies() {
+    use json_patch::{PatchOptions, RollbackStrategy};
+
+    let p: Patch = from_value(json!([
+        { "op": "remove", "path": "/a/0" },
+        { "op": "add", "path": "/b", "value": 2 },
+        { "op": "test", "path": "/c", "value": 3 },
+    ]))
+    .unwrap();
+    let small = json!({ "a": [1, 2], "c": 0 });
+    let large = json!({ "a": (0..100).collect::<Vec<_>>(), "c": 0 });
+
+    for strategy in [
+        RollbackStrategy::Undo,
+        RollbackStrategy::Snapshot,
+        RollbackStrategy::Auto,
+    ] {
+        let mut options = PatchOptions::default();
+        options.rollback = strategy;
+        for original in [&small, &large] {
+            let mut doc = original.clone();
+            let err = json_patch::patch_with(&mut doc, &p, &options).unwrap_err();
+            assert_eq!(err.operation, 2);
+            assert_eq!(&doc, original, "{strategy:?}");
+        }
+    }
+}
+
+#[test]
+fn number_equality() {
+    use json_patch::{NumberEquality, PatchOptions};
+
+    let p: Patch =
+        from_value(json!([{ "op": "test", "path": "/a", "value": [1.0, { "b": 2 }] }])).unwrap();
+    let mut doc: Value = from_str(r#"{ "a": [1e0, { "b": 2.0001 }] }"#).unwrap();
+    let mut options = PatchOptions::default();
+    json_patch::patch_with(&mut doc, &p, &options).unwrap_err();
+    options.number_equality = NumberEquality::Numeric;
+    json_patch::patch_with(&mut doc, &p, &options).unwrap_err();
+    options.number_equality = NumberEquality::Epsilon(0.001);
+    json_patch::patch_with(&mut doc, &p, &options).unwrap();
+}
+
+#[test]
+fn unordered_array_test() {
+    use json_patch::{NumberEquality, PatchOptions};
+
+    let p: Patch = from_value(json!([
+        { "op": "test", "path": "/a", "value": [1, [2, 3], { "b": [4, 5] }, 1] },
+    ]))
+    .unwrap();
+    let mut doc = json!({ "a": [{ "b": [5, 4] }, 1, [3, 2], 1] });
+    let mut options = PatchOptions::default();
+    json_patch::patch_with(&mut doc, &p, &options).unwrap_err();
+    options.unordered_arrays = true;
+    json_patch::patch_with(&mut doc, &p, &options).unwrap();
+
+    // Every element is matched once
+    let mut doc = json!({ "a": [1, [2, 3], { "b": [4, 5] }, 1.0] });
+    json_patch::patch_with(&mut doc, &p, &options).unwrap_err();
+    options.number_equality = NumberEquality::Numeric;
+    json_patch::patch_with(&mut doc, &p, &options).unwrap();
+    let mut doc = json!({ "a": [1, [2, 3], { "b": [4, 5] }, [2, 3]] });
+    json_patch::patch_with(&mut doc, &p, &options).unwrap_err();
+}
+
+#[test]
+#[cfg(feature = "diff")]
+fn diff_number_equality() {
+    use json_patch::{DiffOptions, NumberEquality};
+
+    let left: Value = from_str(r#"{ "a": 1, "b": [1.0, 2], "c": 3 }"#).unwrap();
+    let right: Value = from_str(r#"{ "a": 1.0, "b": [1, 2e0], "c": 4 }"#).unwrap();
+    assert_eq!(json_patch::diff(&left, &right).0.len(), 4);
+    let mut options = DiffOptions::default();
+    options.number_equality = NumberEquality::Numeric;
+    assert_eq!(
+        json_patch::diff_with(&left, &right, &options),
+        from_value::<Patch>(json!([{ "op": "replace", "path": "/c", "value": 4 }])).unwrap()
+    );
+}
+
+#[test]
+fn dash_semantics() {
+    use json_patch::{DashSemantics, PatchOptions};
+
+    let p: Patch = from_value(json!([
+        { "op": "test", "path": "/a/-", "value": 3 },
+        { "op": "replace", "path": "/a/-", "value": 4 },
+        { "op": "move", "from": "/a/-", "path": "/b/-" },
+        { "op": "remove", "path": "/a/-" },
+    ]))
+    .unwrap();
+    let original = json!({ "a": [1, 2, 3], "b": [] });
+
+    let mut doc = original.clone();
+    let err = json_patch::patch(&mut doc, &p).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "operation '/0' failed at path '/a/-': path is invalid"
+    );
+
+    let mut options = PatchOptions::default();
+    options.dash = DashSemantics::LastElement;
+    json_patch::patch_with(&mut doc, &p, &options).unwrap();
+    assert_eq!(doc, json!({ "a": [1], "b": [4] }));
+
+    // Rolled back using actual indices
+    let mut doc = original.clone();
+    let mut failing = p.clone();
+    failing
+        .0
+        .push(PatchOperation::test(PointerBuf::parse("/b/-").unwrap(), 0));
+    json_patch::patch_with(&mut doc, &failing, &options).unwrap_err();
+    assert_eq!(doc, original);
+}
+
+#[test]
+fn patch_error_context() {
+    let p: Patch = from_value(json!([
+        { "op": "copy", "from": "/a", "path": "/b" },
+        { "op": "move", "from": "/c", "path": "/d" },
+    ]))
+    .unwrap();
+    let mut doc = json!({ "a": 1 });
+    let err = json_patch::patch(&mut doc, &p).unwrap_err();
+    assert_eq!(err.operation, 1);
+    assert_eq!(err.op, OpKind::Move);
+    assert_eq!(err.from, Some(PointerBuf::parse("/c").unwrap()));
+    assert_eq!(err.invalid_pointer(), Some(Pointer::from_static("/c")));
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        json!({ "operation": 1, "op": "move", "path": "/d", "from": "/c", "kind": "InvalidFromPointer" })
+    );
+}
+
+#[test]
+fn lint_patch() {
+    use json_patch::{LintKind, LintWarning};
+
+    let p: Patch = from_value(json!([
+        { "op": "remove", "path": "/a" },
+        { "op": "add", "path": "/a", "value": 1 },
+        { "op": "add", "path": "/b/c", "value": 2 },
+        { "op": "copy", "from": "/b/c", "path": "/d" },
+        { "op": "replace", "path": "/b", "value": {} },
+        { "op": "add", "path": "/e", "value": 3 },
+        { "op": "remove", "path": "/e" },
+        { "op": "test", "path": "/d", "value": 2 },
+        { "op": "test", "path": "/x", "value": 2 },
+        { "op": "test", "path": "/x", "value": 2 },
+    ]))
+    .unwrap();
+
+    let warnings = p.lint();
+    assert_eq!(
+        warnings,
+        [
+            LintWarning {
+                operation: 1,
+                kind: LintKind::RemoveThenAdd { remove: 0 },
+            },
+            LintWarning {
+                operation: 5,
+                kind: LintKind::Overwritten { by: 6 },
+            },
+            LintWarning {
+                operation: 7,
+                kind: LintKind::TestAfterMutation { mutation: 3 },
+            },
+            LintWarning {
+                operation: 9,
+                kind: LintKind::Duplicate { of: 8 },
+            },
+        ]
+    );
+    assert_eq!(
+        warnings[1].to_string(),
+        "operation '/5' is overwritten by operation '/6'"
+    );
+}
+
+#[test]
+fn patch_validator() {
+    use json_patch::{PatchErrorKind, PatchOptions, RollbackStrategy, Validator};
+    use jsonptr::PointerBuf;
+    use std::sync::{Arc, Mutex};
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let recorded = seen.clone();
+    let mut options = PatchOptions::default();
+    options.validator = Some(Validator::new(move |doc, touched| {
+        recorded
+            .lock()
+            .unwrap()
+            .push(touched.iter().map(|p| p.to_string()).collect::<Vec<_>>());
+        match doc.pointer("/a/b") {
+            Some(Value::Number(_)) => Ok(()),
+            _ => Err(PointerBuf::parse("/a/b").unwrap()),
+        }
+    }));
+
+    let p: Patch = from_value(json!([
+        { "op": "replace", "path": "/a", "value": { "b": "x" } },
+        { "op": "move", "from": "/c", "path": "/d" },
+        { "op": "test", "path": "/d", "value": 1 },
+        { "op": "add", "path": "/d", "value": 2 },
+    ]))
+    .unwrap();
+
+    for rollback in [RollbackStrategy::Undo, RollbackStrategy::Snapshot] {
+        options.rollback = rollback;
+        let mut doc = json!({ "a": { "b": 1 }, "c": 1 });
+        let err = json_patch::patch_with(&mut doc, &p, &options).unwrap_err();
+        assert!(matches!(err.kind, PatchErrorKind::ValidationFailed));
+        assert_eq!(err.operation, 0);
+        assert_eq!(err.path, "/a/b");
+        assert_eq!(
+            err.to_string(),
+            "operation '/0' failed at path '/a/b': document is invalid"
+        );
+        assert_eq!(doc, json!({ "a": { "b": 1 }, "c": 1 }));
+    }
+    assert_eq!(seen.lock().unwrap()[0], ["/a", "/c", "/d"]);
+
+    let mut doc = json!({ "a": { "b": 1 } });
+    json_patch::patch_with(&mut doc, &[], &options).unwrap();
+    assert_eq!(seen.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn stats_by_prefix() {
+    let p: Patch = from_value(json!([
+        { "op": "replace", "path": "", "value": {} },
+        { "op": "add", "path": "/a/b/c", "value": 1 },
+        { "op": "move", "from": "/x", "path": "/a/b" },
+        { "op": "test", "path": "/a/d", "value": 1 },
+        { "op": "remove", "path": "/e~1f/0" },
+    ]))
+    .unwrap();
+
+    let stats = |depth| {
+        p.stats_by_prefix(depth)
+            .into_iter()
+            .map(|(prefix, count)| (prefix.to_string(), count))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(stats(0), [("".to_owned(), 5)]);
+    assert_eq!(
+        stats(1),
+        [
+            ("".to_owned(), 1),
+            ("/a".to_owned(), 3),
+            ("/e~1f".to_owned(), 1)
+        ]
+    );
+    assert_eq!(
+        stats(2),
+        [
+            ("".to_owned(), 1),
+            ("/a/b".to_owned(), 2),
+            ("/a/d".to_owned(), 1),
+            ("/e~1f/0".to_owned(), 1)
+        ]
+    );
+    assert!(Patch::default().stats_by_prefix(1).is_empty());
+}
+
+#[test]
+fn compare_and_swap() {
+    let p = Patch::compare_and_swap(Pointer::from_static("/a/0").to_buf(), 1, json!({ "b": 2 }));
+    assert_eq!(
+        p,
+        from_value::<Patch>(json!([
+            { "op": "test", "path": "/a/0", "value": 1 },
+            { "op": "replace", "path": "/a/0", "value": { "b": 2 } },
+        ]))
+        .unwrap()
+    );
+
+    let mut doc = json!({ "a": [1.0] });
+    let path = Pointer::from_static("/a/0");
+    assert!(!json_patch::compare_and_swap(&mut doc, path, &json!(2), json!(3)).unwrap());
+    assert!(json_patch::compare_and_swap(&mut doc, path, &json!(1.0), json!(3)).unwrap());
+    assert_eq!(doc, json!({ "a": [3] }));
+
+    let err =
+        json_patch::compare_and_swap(&mut doc, Pointer::from_static("/a/1"), &json!(3), json!(4))
+            .unwrap_err();
+    assert!(matches!(
+        err.kind,
+        json_patch::PatchErrorKind::InvalidPointer
+    ));
+    assert_eq!(doc, json!({ "a": [3] }));
+}
@@ -0,0 +1,13 @@
+#[cfg(feature = "schemars")]
+#[test]
+fn schema() {
+    use json_patch::*;
+    use schemars::schema_for;
+
+    let schemas = serde_json::json!({
+        "Patch": schema_for!(Patch),
+        "PatchError": schema_for!(PatchError),
+    });
+    let json = serde_json::to_string_pretty(&schemas).unwrap();
+    expectorate::assert_contents("tests/schemars.json", &json);
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::manual_inspect)]
+
 use json_patch::Patch;
 use serde::Deserialize;
 use serde_json::Value;
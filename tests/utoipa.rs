@@ -11,14 +11,26 @@ fn schema() {
         responses(
             (status = 200, description = "Patch completed"),
             (status = 406, description = "Not accepted"),
+            (status = 422, description = "Patch failed", body = PatchError),
         ),
     )]
     #[allow(unused)]
     fn get_foo(body: Patch) {}
 
+    #[utoipa::path(
+        patch,
+        path = "bar",
+        request_body(content = MergePatch, content_type = MergePatch::CONTENT_TYPE),
+        responses(
+            (status = 200, description = "Merge completed"),
+        ),
+    )]
+    #[allow(unused)]
+    fn patch_bar(body: MergePatch) {}
+
     #[derive(OpenApi, Default)]
     #[openapi(
-        paths(get_foo),
+        paths(get_foo, patch_bar),
         components(schemas(
             AddOperation,
             CopyOperation,
@@ -28,6 +40,10 @@ fn schema() {
             ReplaceOperation,
             TestOperation,
             Patch,
+            OpKind,
+            PatchError,
+            PatchErrorKind,
+            MergePatch,
         ))
     )]
     struct ApiDoc;